@@ -1,12 +1,14 @@
 #![cfg_attr(windows, allow(unused, clippy::all))]
 
 use ethers_contract_abigen::{Abigen, MultiAbigen};
-use eyre::Result;
-use std::{env, path::PathBuf, process::Command};
+use ethers_core::types::{Address, Chain};
+use eyre::{Result, WrapErr};
+use std::{collections::HashMap, env, fs, path::PathBuf, process::Command};
 
 const ABI_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/abi");
 const BINDINGS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/contracts/bindings");
 const ABIGEN_CHECK: &str = "ABIGEN_CHECK";
+const ADDRESSBOOK: &str = "UNISWAP_RS_ADDRESSBOOK";
 
 // Don't print anything if we're not in "check" mode
 macro_rules! warn {
@@ -21,10 +23,19 @@ macro_rules! warn {
 // do not run on Windows due to failing in CI
 // presumably due to rustfmt not working / not behaving the same
 #[cfg(windows)]
-fn main() {}
+fn main() -> Result<()> {
+    addressbook()
+}
 
 #[cfg(not(windows))]
 fn main() -> Result<()> {
+    addressbook()?;
+
+    // the ABIs are not published, the bindings are already built
+    if !std::path::Path::new(ABI_PATH).exists() {
+        return Ok(());
+    }
+
     println!("cargo:rerun-if-changed={ABI_PATH}");
     println!("cargo:rerun-if-env-changed={ABIGEN_CHECK}");
     match env::var_os(ABIGEN_CHECK) {
@@ -37,6 +48,33 @@ fn main() -> Result<()> {
     }
 }
 
+/// Uses the addressbook at the path set in [ADDRESSBOOK] instead of the bundled one, if any.
+///
+/// Validates the file so that a malformed addressbook fails the build instead of panicking at
+/// runtime.
+fn addressbook() -> Result<()> {
+    // not supported by the cargo of older toolchains
+    if rustc_minor_version().map_or(false, |minor| minor >= 80) {
+        println!("cargo:rustc-check-cfg=cfg(custom_addressbook)");
+    }
+    println!("cargo:rerun-if-env-changed={ADDRESSBOOK}");
+    let path = match env::var_os(ADDRESSBOOK) {
+        Some(path) if !path.is_empty() => fs::canonicalize(&path)
+            .wrap_err_with(|| format!("{ADDRESSBOOK}: could not find {path:?}"))?,
+        _ => return Ok(()),
+    };
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    // same schema as `contracts::addresses::Contract`
+    let json = fs::read_to_string(&path)?;
+    serde_json::from_str::<HashMap<String, HashMap<String, HashMap<Chain, Address>>>>(&json)
+        .wrap_err_with(|| format!("{ADDRESSBOOK}: invalid addressbook {path:?}"))?;
+
+    println!("cargo:rustc-cfg=custom_addressbook");
+    println!("cargo:rustc-env={ADDRESSBOOK}={}", path.display());
+    Ok(())
+}
+
 fn abigen(check: bool) -> Result<()> {
     // current rustfmt.toml contains nightly-only config
     let rustfmt = match rustfmt_version() {
@@ -74,6 +112,14 @@ fn rustfmt_version() -> Result<String> {
     Ok(version)
 }
 
+/// Returns the minor version of the rustc used for the build, e.g. `62` for 1.62.0.
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    version.split_whitespace().nth(1)?.split('.').nth(1)?.parse().ok()
+}
+
 /// Returns a list of absolute paths to all the json files under the root.
 ///
 /// Modified from: ethers_contract_abigen::util::json_files
@@ -12,3 +12,15 @@ pub use crate::protocol::pair_code_hashes::*;
 pub const NATIVE_ADDRESS: Address = Address::repeat_byte(0xee);
 
 pub(crate) const BPS_U256: U256 = U256([10_000u64, 0, 0, 0]);
+
+/// The fixed-point scale of prices returned by this crate, `1e18`.
+pub const PRICE_SCALE: U256 = U256([1_000_000_000_000_000_000u64, 0, 0, 0]);
+
+/// The maximum gas multiplier accepted by
+/// [`Dex::estimate_swap_gas`](crate::Dex::estimate_swap_gas).
+pub const MAX_GAS_MULTIPLIER: f64 = 5.0;
+
+/// The default minimum number of seconds between a swap's deadline and the latest block accepted
+/// by [`Dex`](crate::Dex). See
+/// [`Dex::set_min_deadline_buffer`](crate::Dex::set_min_deadline_buffer).
+pub const MIN_DEADLINE_BUFFER: u64 = 30;
@@ -1,7 +1,10 @@
 //! Errors
 
 use ethers_contract::{ContractError, MulticallError};
-use ethers_core::abi::InvalidOutputType;
+use ethers_core::{
+    abi::InvalidOutputType,
+    types::{Address, U256},
+};
 use ethers_providers::{Middleware, ProviderError};
 use thiserror::Error as ThisError;
 
@@ -26,6 +29,15 @@ pub enum Error {
     #[error(transparent)]
     ProviderError(#[from] ProviderError),
 
+    /// Thrown when a request to the client fails without reverting, for example because of a
+    /// transport error.
+    #[error("{0}")]
+    MiddlewareError(String),
+
+    /// Thrown when an operation did not complete within its timeout.
+    #[error("Operation timed out")]
+    Timeout,
+
     /* ------------------------------------------- Dex ------------------------------------------ */
 
     /// Thrown when the provided slippage is invalid.
@@ -40,6 +52,37 @@ pub enum Error {
     #[error("WETH has yet to be set")]
     WethNotSet,
 
+    /// Thrown when swapping the native token on a protocol whose router does not implement the
+    /// standard native token methods.
+    #[error("Router does not support the standard native token swap methods")]
+    UnsupportedNativeSwap,
+
+    /// Thrown when swapping the native token with a wrapper that is not the chain's wrapped native
+    /// token.
+    #[error("{weth:?} is not the wrapped native token of the chain")]
+    NativeWrapperMismatch {
+        /// The provided wrapper address.
+        weth: Address,
+    },
+
+    /// Thrown when a deadline is not after the latest block's timestamp.
+    #[error("Deadline is in the past")]
+    DeadlineInPast,
+
+    /// Thrown when a deadline is less than the minimum buffer after the latest block's timestamp.
+    #[error("Deadline is only {remaining} seconds away")]
+    DeadlineTooSoon {
+        /// The seconds between the latest block's timestamp and the deadline.
+        remaining: u64,
+    },
+
+    /// Thrown when the router's factory is not the provided factory.
+    #[error("Router {router:?} does not belong to the factory")]
+    RouterFactoryMismatch {
+        /// The address of the router.
+        router: Address,
+    },
+
     /* ----------------------------------------- Library ---------------------------------------- */
 
     /// Thrown when providing identical addresses as parameters.
@@ -54,6 +97,10 @@ pub enum Error {
     #[error("Output amount is zero")]
     InsufficientOutputAmount,
 
+    /// Thrown when the result of a calculation does not fit in 256 bits.
+    #[error("Arithmetic overflow")]
+    Overflow,
+
     /// Thrown when providing a liquidity amount equal to zero.
     #[error("Liquidity is zero")]
     InsufficientLiquidity,
@@ -62,15 +109,191 @@ pub enum Error {
     #[error("Path length must be greater than or equal to 2")]
     InvalidPath,
 
+    /// Thrown when the number of provided decimals does not match the length of the path.
+    #[error("Decimals length must match the path length")]
+    DecimalsMismatch,
+
+    /// Thrown when combining token amounts with different decimals.
+    #[error("Cannot combine amounts with {0} and {1} decimals")]
+    MismatchedDecimals(u8, u8),
+
+    /// Thrown when the provided swap fee is greater than or equal to 100%.
+    #[error("Fee must be lower than 10000 basis points")]
+    InvalidFee,
+
+    /// Thrown when the provided price impact is greater than or equal to 100%.
+    #[error("Price impact must be lower than 10000 basis points")]
+    InvalidPriceImpact,
+
+    /// Thrown when the provided target reserve ratio is zero.
+    #[error("Target ratio must be greater than zero")]
+    InvalidRatio,
+
+    /// Thrown when a token is not one of the two tokens of a pair.
+    #[error("Token is not part of the pair")]
+    TokenNotInPair,
+
+    /// Thrown when using the tokens or reserves of a pair that has not been synced.
+    #[error("Pair has not been synced")]
+    NotSynced,
+
+    /// Thrown when decoding calldata that is not a known router swap call.
+    #[error("Calldata is not a known router swap")]
+    UnknownSwapCall,
+
+    /// Thrown when the input or output token of a route is in the blocklist.
+    #[error("Token {0:?} is blocked")]
+    BlockedToken(Address),
+
+    /// Thrown when a V2 operation is requested for a protocol that is not V2.
+    #[error("Protocol is not Uniswap V2 or a fork of it")]
+    NotV2Protocol,
+
+    /// Thrown when a contract is missing from the addressbook.
+    #[error("{0} is not in the addressbook for this chain")]
+    MissingAddress(&'static str),
+
+    /// Thrown when a pair could not be found.
+    #[error("Pair not found")]
+    PairNotFound,
+
+    /// Thrown when computing a pair's CREATE2 address with a factory whose pair addresses are
+    /// resolved on-chain.
+    #[error("Pair address must be resolved on-chain")]
+    OnChainPairResolution,
+
+    /// Thrown when the chain was reorganized while fetching data at a block.
+    #[error("Block was reorganized while fetching")]
+    Reorg,
+
+    /// Thrown when the output amount of a hop is lower than the provided threshold.
+    #[error("Output amount of hop {hop} is below the threshold")]
+    BelowThreshold {
+        /// The index of the hop in the path.
+        hop: usize,
+    },
+
+    /// Thrown when the final output amount of a path is lower than the provided minimum.
+    #[error("Output amount {amount_out} is below the minimum")]
+    BelowMinimum {
+        /// The final output amount.
+        amount_out: U256,
+    },
+
+    /// Thrown when the execution price of a trade deviates too much from the reference price.
+    #[error("Price deviates {deviation_bps} basis points from the reference price")]
+    PriceDeviation {
+        /// The deviation from the reference price, in basis points.
+        deviation_bps: u32,
+    },
+
+    /// Thrown when building a flash swap without any callback data.
+    #[error("Flash swap data must not be empty")]
+    EmptyFlashSwapData,
+
+    /// Thrown when a V3 tick is out of bounds.
+    #[error("Tick must be in range: MIN_TICK..=MAX_TICK")]
+    InvalidTick,
+
+    /// Thrown when a V3 sqrt price is out of bounds.
+    #[error("Sqrt ratio must be in range: MIN_SQRT_RATIO..MAX_SQRT_RATIO")]
+    InvalidSqrtRatio,
+
     /// Thrown when the factory provided returns none for pair_code_hash
     #[error("Custom protocol is missing pair_code_hash")]
     NoPairCodeHash,
 }
 
+impl Error {
+    /// Returns a stable, machine-readable code for the error, like `"INSUFFICIENT_LIQUIDITY"`.
+    ///
+    /// Unlike the [Display](std::fmt::Display) message, the code does not depend on the error's
+    /// data and is not changed across versions, so it can be used to map errors to API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ContractError(_) => "CONTRACT_ERROR",
+            Self::MulticallError(_) => "MULTICALL_ERROR",
+            Self::ProviderError(_) => "PROVIDER_ERROR",
+            Self::MiddlewareError(_) => "MIDDLEWARE_ERROR",
+            Self::Timeout => "TIMEOUT",
+            Self::InvalidSlippage => "INVALID_SLIPPAGE",
+            Self::SwapToSelf => "SWAP_TO_SELF",
+            Self::WethNotSet => "WETH_NOT_SET",
+            Self::UnsupportedNativeSwap => "UNSUPPORTED_NATIVE_SWAP",
+            Self::NativeWrapperMismatch { .. } => "NATIVE_WRAPPER_MISMATCH",
+            Self::DeadlineInPast => "DEADLINE_IN_PAST",
+            Self::DeadlineTooSoon { .. } => "DEADLINE_TOO_SOON",
+            Self::RouterFactoryMismatch { .. } => "ROUTER_FACTORY_MISMATCH",
+            Self::IdenticalAddresses => "IDENTICAL_ADDRESSES",
+            Self::InsufficientInputAmount => "INSUFFICIENT_INPUT_AMOUNT",
+            Self::InsufficientOutputAmount => "INSUFFICIENT_OUTPUT_AMOUNT",
+            Self::Overflow => "OVERFLOW",
+            Self::InsufficientLiquidity => "INSUFFICIENT_LIQUIDITY",
+            Self::InvalidPath => "INVALID_PATH",
+            Self::DecimalsMismatch => "DECIMALS_MISMATCH",
+            Self::MismatchedDecimals(..) => "MISMATCHED_DECIMALS",
+            Self::InvalidFee => "INVALID_FEE",
+            Self::InvalidPriceImpact => "INVALID_PRICE_IMPACT",
+            Self::InvalidRatio => "INVALID_RATIO",
+            Self::TokenNotInPair => "TOKEN_NOT_IN_PAIR",
+            Self::NotSynced => "NOT_SYNCED",
+            Self::UnknownSwapCall => "UNKNOWN_SWAP_CALL",
+            Self::BlockedToken(_) => "BLOCKED_TOKEN",
+            Self::NotV2Protocol => "NOT_V2_PROTOCOL",
+            Self::MissingAddress(_) => "MISSING_ADDRESS",
+            Self::PairNotFound => "PAIR_NOT_FOUND",
+            Self::OnChainPairResolution => "ON_CHAIN_PAIR_RESOLUTION",
+            Self::Reorg => "REORG",
+            Self::BelowThreshold { .. } => "BELOW_THRESHOLD",
+            Self::BelowMinimum { .. } => "BELOW_MINIMUM",
+            Self::PriceDeviation { .. } => "PRICE_DEVIATION",
+            Self::EmptyFlashSwapData => "EMPTY_FLASH_SWAP_DATA",
+            Self::InvalidTick => "INVALID_TICK",
+            Self::InvalidSqrtRatio => "INVALID_SQRT_RATIO",
+            Self::NoPairCodeHash => "NO_PAIR_CODE_HASH",
+        }
+    }
+}
+
+impl Error {
+    /// Returns whether the error was caused by the client rather than by the request, for example
+    /// a connection failure or a timeout, in which case retrying the request with another client
+    /// may succeed.
+    ///
+    /// Calls that revert are not transport errors, since they revert with every client.
+    pub fn is_transport(&self) -> bool {
+        match self {
+            Self::MiddlewareError(_) | Self::Timeout => true,
+            Self::ProviderError(e) => !is_revert(&e.to_string()),
+            _ => false,
+        }
+    }
+}
+
+/// Returns whether a JSON-RPC error message reports a reverted call, like `execution reverted`.
+fn is_revert(message: &str) -> bool {
+    message.to_lowercase().contains("revert")
+}
+
+/// Converts the error of a request to the client, keeping transport errors apart from reverts.
+fn middleware_error(message: String, revert: fn(String) -> Error) -> Error {
+    if is_revert(&message) {
+        revert(message)
+    } else {
+        Error::MiddlewareError(message)
+    }
+}
+
 // Workaround for removing generic type in [Error].
 impl<M: Middleware> From<ContractError<M>> for Error {
     fn from(value: ContractError<M>) -> Self {
-        Self::ContractError(value.to_string())
+        match value {
+            ContractError::MiddlewareError(e) => {
+                middleware_error(e.to_string(), Self::ContractError)
+            }
+            ContractError::ProviderError(e) => middleware_error(e.to_string(), Self::ContractError),
+            value => Self::ContractError(value.to_string()),
+        }
     }
 }
 
@@ -82,6 +305,14 @@ impl From<InvalidOutputType> for Error {
 
 impl<M: Middleware> From<MulticallError<M>> for Error {
     fn from(value: MulticallError<M>) -> Self {
-        Self::MulticallError(value.to_string())
+        match value {
+            MulticallError::ContractError(ContractError::MiddlewareError(e)) => {
+                middleware_error(e.to_string(), Self::MulticallError)
+            }
+            MulticallError::ContractError(ContractError::ProviderError(e)) => {
+                middleware_error(e.to_string(), Self::MulticallError)
+            }
+            value => Self::MulticallError(value.to_string()),
+        }
     }
 }
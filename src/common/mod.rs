@@ -4,6 +4,12 @@ pub use token::Erc20;
 mod call;
 pub use call::{CallExt, CallResult};
 
+mod pool;
+pub use pool::Pool;
+
+mod token_amount;
+pub use token_amount::TokenAmount;
+
 pub mod constants;
 pub mod errors;
 pub mod utils;
@@ -32,3 +38,59 @@ impl Amount {
         Self::ExactOut(amount.into())
     }
 }
+
+/// How the deadline of a transaction is determined.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DeadlinePolicy {
+    /// The given number of seconds after the latest block's timestamp.
+    SecondsFromNow(u64),
+    /// The given UNIX timestamp, in seconds.
+    Explicit(U256),
+    /// No deadline.
+    None,
+}
+
+impl DeadlinePolicy {
+    /// Returns the absolute deadline of the policy, relative to `timestamp`.
+    pub fn resolve(self, timestamp: U256) -> U256 {
+        match self {
+            Self::SecondsFromNow(seconds) => timestamp.saturating_add(seconds.into()),
+            Self::Explicit(deadline) => deadline,
+            Self::None => U256::MAX,
+        }
+    }
+}
+
+/// The rounding direction of integer divisions in amount calculations.
+///
+/// On-chain contracts always round down, so only [`Floor`](RoundingMode::Floor) produces values
+/// that are safe to use in transactions. The other modes are meant for display and estimates.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum RoundingMode {
+    /// Round towards zero, matching the on-chain behavior.
+    #[default]
+    Floor,
+    /// Round away from zero.
+    Ceil,
+    /// Round to the nearest integer, with ties rounded up.
+    Nearest,
+}
+
+impl RoundingMode {
+    /// Returns `numerator / denominator`, rounded according to `self`.
+    ///
+    /// Panics if `denominator` is zero.
+    pub fn div(self, numerator: U256, denominator: U256) -> U256 {
+        let (quotient, remainder) = numerator.div_mod(denominator);
+        let round_up = match self {
+            Self::Floor => false,
+            Self::Ceil => !remainder.is_zero(),
+            Self::Nearest => remainder >= denominator - remainder,
+        };
+        if round_up {
+            quotient + 1
+        } else {
+            quotient
+        }
+    }
+}
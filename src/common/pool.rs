@@ -0,0 +1,13 @@
+use crate::errors::Result;
+use ethers_core::types::{Address, U256};
+
+/// A liquidity pool that can be quoted without making any external calls.
+///
+/// This allows quoting pools of different protocols uniformly, for example through `Box<dyn Pool>`.
+pub trait Pool {
+    /// Returns the sorted tokens of the pool.
+    fn tokens(&self) -> Result<(Address, Address)>;
+
+    /// Returns the output amount of the other token for swapping `amount_in` of `token_in`.
+    fn amount_out(&self, token_in: Address, amount_in: U256) -> Result<U256>;
+}
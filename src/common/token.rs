@@ -1,8 +1,8 @@
-use crate::{contracts::bindings::ierc20::IERC20, errors::Result};
+use crate::{contracts::bindings::ierc20::IERC20, errors::Result, utils::format_amount};
 use ethers_contract::{builders::ContractCall, Multicall};
 use ethers_core::{
     abi::Tokenizable,
-    types::{Address, Chain},
+    types::{Address, Chain, U256},
 };
 use ethers_providers::Middleware;
 use std::{fmt, sync::Arc};
@@ -60,6 +60,13 @@ impl<M> Erc20<M> {
     pub fn decimals(&self) -> u8 {
         self.decimals.unwrap_or(18)
     }
+
+    /// Formats `amount` using the token's decimals and symbol, e.g. `"1234.56 USDC"`.
+    pub fn format_amount(&self, amount: U256) -> String {
+        let amount = format_amount(amount, self.decimals());
+        let symbol = self.symbol();
+        format!("{amount} {symbol}")
+    }
 }
 
 impl<M: Middleware> Erc20<M> {
@@ -95,6 +102,7 @@ impl<M: Middleware> Erc20<M> {
     }
 
     /// Syncs the token's name, symbol and decimals.
+    #[doc(alias = "dry_run_only")]
     pub async fn sync(&mut self, chain: Chain) -> Result<&mut Self> {
         let mut multicall = Multicall::new_with_chain_id(self.client(), None, Some(chain))?;
         self.add_metadata(&mut multicall);
@@ -141,6 +149,13 @@ mod tests {
 
         assert_eq!(format!("{token}"), "Wrapped Ether (WETH)");
         assert_eq!(format!("{token:#}"), "Wrapped Ether (WETH) @ 0xc02a…6cc2");
+
+        assert_eq!(
+            token.format_amount(U256::exp10(18) * 1234 + U256::exp10(16) * 56),
+            "1234.56 WETH"
+        );
+        assert_eq!(token.format_amount(U256::from(5)), "0.000000000000000005 WETH");
+        assert_eq!(token.format_amount(U256::zero()), "0 WETH");
     }
 
     #[tokio::test]
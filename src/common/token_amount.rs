@@ -0,0 +1,155 @@
+use crate::{
+    errors::{Error, Result},
+    utils::format_amount,
+};
+use ethers_core::types::U256;
+use std::fmt;
+
+/// An amount of a token in its smallest unit, together with the token's decimals.
+///
+/// Amounts can only be combined with amounts of the same decimals, which prevents mixing scaled
+/// and unscaled values. Use [`raw`](Self::raw) to pass the amount to the math functions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TokenAmount {
+    /// The amount in the token's smallest unit.
+    amount: U256,
+
+    /// The token's decimals.
+    decimals: u8,
+}
+
+impl TokenAmount {
+    /// Creates a new amount from a raw amount in the token's smallest unit.
+    pub fn new<T: Into<U256>>(amount: T, decimals: u8) -> Self {
+        Self { amount: amount.into(), decimals }
+    }
+
+    /// Creates a new amount of `units` whole tokens, e.g. `1` for 1e18 of a token with 18
+    /// decimals.
+    ///
+    /// Returns [`Error::Overflow`] if the scaled amount does not fit in 256 bits.
+    pub fn from_units<T: Into<U256>>(units: T, decimals: u8) -> Result<Self> {
+        let amount = units.into().checked_mul(U256::exp10(decimals as usize));
+        Ok(Self::new(amount.ok_or(Error::Overflow)?, decimals))
+    }
+
+    /// Returns the raw amount in the token's smallest unit.
+    pub fn raw(&self) -> U256 {
+        self.amount
+    }
+
+    /// Returns the token's decimals.
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// Returns whether the amount is zero.
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    /// Returns the amount in whole tokens as an [f64], which may lose precision.
+    pub fn to_f64(&self) -> f64 {
+        let amount =
+            self.amount.0.iter().rev().fold(0.0, |acc, &limb| acc * 2f64.powi(64) + limb as f64);
+        amount / 10f64.powi(self.decimals as i32)
+    }
+
+    /// Returns the amount in whole tokens, without trailing zeros, e.g. `"1.5"`.
+    ///
+    /// See [`format_amount`].
+    pub fn format(&self) -> String {
+        format_amount(self.amount, self.decimals)
+    }
+
+    /// Returns the same amount with a different number of decimals, rounding down when reducing
+    /// them.
+    ///
+    /// Returns [`Error::Overflow`] if the scaled amount does not fit in 256 bits.
+    pub fn rescale(&self, decimals: u8) -> Result<Self> {
+        let amount = if decimals >= self.decimals {
+            let scale = U256::exp10((decimals - self.decimals) as usize);
+            self.amount.checked_mul(scale).ok_or(Error::Overflow)?
+        } else {
+            self.amount / U256::exp10((self.decimals - decimals) as usize)
+        };
+        Ok(Self::new(amount, decimals))
+    }
+
+    /// Returns `self + other`.
+    ///
+    /// Returns [`Error::MismatchedDecimals`] if the amounts have different decimals, or
+    /// [`Error::Overflow`] if the sum does not fit in 256 bits.
+    pub fn checked_add(&self, other: Self) -> Result<Self> {
+        self.check_decimals(&other)?;
+        let amount = self.amount.checked_add(other.amount).ok_or(Error::Overflow)?;
+        Ok(Self::new(amount, self.decimals))
+    }
+
+    /// Returns `self - other`.
+    ///
+    /// Returns [`Error::MismatchedDecimals`] if the amounts have different decimals, or
+    /// [`Error::Overflow`] if `other` is greater than `self`.
+    pub fn checked_sub(&self, other: Self) -> Result<Self> {
+        self.check_decimals(&other)?;
+        let amount = self.amount.checked_sub(other.amount).ok_or(Error::Overflow)?;
+        Ok(Self::new(amount, self.decimals))
+    }
+
+    /// Compares two amounts.
+    ///
+    /// Returns [`Error::MismatchedDecimals`] if the amounts have different decimals.
+    pub fn checked_cmp(&self, other: &Self) -> Result<std::cmp::Ordering> {
+        self.check_decimals(other)?;
+        Ok(self.amount.cmp(&other.amount))
+    }
+
+    fn check_decimals(&self, other: &Self) -> Result<()> {
+        if self.decimals != other.decimals {
+            return Err(Error::MismatchedDecimals(self.decimals, other.decimals));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&self.format())
+    }
+}
+
+impl From<TokenAmount> for U256 {
+    fn from(value: TokenAmount) -> Self {
+        value.amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_use_token_amounts() {
+        let one = TokenAmount::from_units(1, 18).unwrap();
+        assert_eq!(one.raw(), U256::exp10(18));
+        let half = TokenAmount::new(U256::exp10(17) * 5, 18);
+
+        let sum = one.checked_add(half).unwrap();
+        assert_eq!(sum.format(), "1.5");
+        assert_eq!(sum.to_string(), "1.5");
+        assert_eq!(sum.to_f64(), 1.5);
+        assert_eq!(sum.checked_sub(one).unwrap(), half);
+        assert!(matches!(half.checked_sub(one).unwrap_err(), Error::Overflow));
+        assert_eq!(U256::from(sum), sum.raw());
+
+        let usdc = TokenAmount::from_units(1, 6).unwrap();
+        assert!(matches!(one.checked_add(usdc).unwrap_err(), Error::MismatchedDecimals(18, 6)));
+        assert!(usdc.checked_cmp(&one).is_err());
+        assert_eq!(one.rescale(6).unwrap(), usdc);
+        assert_eq!(
+            usdc.rescale(18).unwrap().checked_cmp(&half).unwrap(),
+            std::cmp::Ordering::Greater
+        );
+    }
+}
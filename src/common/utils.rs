@@ -1,9 +1,14 @@
 //! Utils
 
 use super::constants::NATIVE_ADDRESS;
-use ethers_core::types::{Address, U256};
+use ethers_core::types::{Address, Chain, U256};
 use std::time::{Duration, SystemTime};
 
+#[cfg(feature = "addresses")]
+use crate::contracts::bindings::iweth::{DepositCall, WithdrawCall};
+#[cfg(feature = "addresses")]
+use ethers_core::{abi::AbiEncode, types::Bytes};
+
 /// Returns the [Duration] since the UNIX epoch.
 #[inline]
 pub fn now() -> Duration {
@@ -45,3 +50,142 @@ pub fn map_native(path: &mut [Address], weth: Address) {
         }
     }
 }
+
+/// Returns the symbol of the wrapped native token on `chain`, for example `WBNB` on Binance Smart
+/// Chain, `WETH` by default.
+pub const fn native_wrapper_symbol(chain: Chain) -> &'static str {
+    use Chain::*;
+    match chain {
+        BinanceSmartChain | BinanceSmartChainTestnet => "WBNB",
+        Polygon | PolygonMumbai => "WMATIC",
+        Fantom | FantomTestnet => "WFTM",
+        Avalanche | AvalancheFuji => "WAVAX",
+        _ => "WETH",
+    }
+}
+
+/// Returns the address of the chain's wrapped native token, from the [addressbook], and the
+/// calldata of its `deposit()`. The call must be sent with the amount to wrap as its value.
+///
+/// Returns None if the wrapped native token is not present in the addressbook for `chain`.
+///
+/// [addressbook]: crate::contracts::addresses
+#[cfg(feature = "addresses")]
+pub fn weth_deposit_call(chain: Chain) -> Option<(Address, Bytes)> {
+    let weth = crate::contracts::addresses::try_address(native_wrapper_symbol(chain), chain)?;
+    Some((weth, DepositCall.encode().into()))
+}
+
+/// Returns the address of the chain's wrapped native token, from the [addressbook], and the
+/// calldata of its `withdraw(amount)`.
+///
+/// Returns None if the wrapped native token is not present in the addressbook for `chain`.
+///
+/// [addressbook]: crate::contracts::addresses
+#[cfg(feature = "addresses")]
+pub fn weth_withdraw_call(chain: Chain, amount: U256) -> Option<(Address, Bytes)> {
+    let weth = crate::contracts::addresses::try_address(native_wrapper_symbol(chain), chain)?;
+    Some((weth, WithdrawCall { wad: amount }.encode().into()))
+}
+
+/// Formats `amount` as a decimal number with `decimals` decimal places, trimming trailing zeros.
+///
+/// Works with any number of decimals, including zero.
+pub fn format_amount(amount: U256, decimals: u8) -> String {
+    let digits = amount.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 {
+        return digits;
+    }
+
+    let digits = format!("{digits:0>width$}", width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{integer}.{fraction}")
+    }
+}
+
+/// Awaits `future`, returning [`Error::Timeout`] if it does not complete within `timeout`.
+///
+/// This can wrap any of the crate's async functions, for example
+/// `with_timeout(timeout, Library::get_reserves_multi(&factory, &path))`.
+///
+/// Must be called within a Tokio runtime with the time driver enabled.
+///
+/// [`Error::Timeout`]: crate::errors::Error::Timeout
+#[cfg(feature = "timeout")]
+pub async fn with_timeout<T>(
+    timeout: Duration,
+    future: impl std::future::Future<Output = crate::errors::Result<T>>,
+) -> crate::errors::Result<T> {
+    tokio::time::timeout(timeout, future).await.map_err(|_| crate::errors::Error::Timeout)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[cfg(feature = "timeout")]
+    async fn test_with_timeout() {
+        use crate::errors::Error;
+
+        let res = with_timeout(Duration::from_secs(1), async { Ok(1) }).await;
+        assert_eq!(res.unwrap(), 1);
+
+        let res = with_timeout(Duration::from_millis(1), std::future::pending::<Result<(), _>>());
+        assert!(matches!(res.await.unwrap_err(), Error::Timeout));
+    }
+
+    #[test]
+    fn test_native_wrapper_symbol() {
+        assert_eq!(native_wrapper_symbol(Chain::Mainnet), "WETH");
+        assert_eq!(native_wrapper_symbol(Chain::Arbitrum), "WETH");
+        assert_eq!(native_wrapper_symbol(Chain::BinanceSmartChain), "WBNB");
+        assert_eq!(native_wrapper_symbol(Chain::Polygon), "WMATIC");
+        assert_eq!(native_wrapper_symbol(Chain::Fantom), "WFTM");
+        assert_eq!(native_wrapper_symbol(Chain::Avalanche), "WAVAX");
+    }
+
+    #[test]
+    #[cfg(feature = "addresses")]
+    fn test_weth_calls() {
+        use crate::contracts::addresses::{address, try_address};
+        use ethers_core::abi::AbiDecode;
+
+        let weth = address("WETH", Chain::Mainnet);
+        let (to, calldata) = weth_deposit_call(Chain::Mainnet).unwrap();
+        assert_eq!(to, weth);
+        assert_eq!(calldata, Bytes::from(DepositCall.encode()));
+
+        let amount = U256::exp10(18);
+        let (to, calldata) = weth_withdraw_call(Chain::Mainnet, amount).unwrap();
+        assert_eq!(to, weth);
+        assert_eq!(WithdrawCall::decode(&calldata).unwrap().wad, amount);
+
+        let (to, _) = weth_deposit_call(Chain::BinanceSmartChain).unwrap();
+        assert_eq!(Some(to), try_address("WBNB", Chain::BinanceSmartChain));
+        assert!(weth_withdraw_call(Chain::MoonbeamDev, amount).is_none());
+    }
+
+    #[test]
+    fn test_format_amount() {
+        let amount = U256::from(123_456_789u64);
+        assert_eq!(format_amount(amount, 0), "123456789");
+        assert_eq!(format_amount(amount, 6), "123.456789");
+        assert_eq!(format_amount(amount, 18), "0.000000000123456789");
+        assert_eq!(format_amount(amount, 24), "0.000000000000000123456789");
+
+        assert_eq!(format_amount(U256::exp10(24) * 5, 24), "5");
+        assert_eq!(format_amount(U256::exp10(18) + U256::exp10(17), 18), "1.1");
+        assert_eq!(format_amount(U256::zero(), 0), "0");
+        assert_eq!(format_amount(U256::zero(), 18), "0");
+
+        // full u8 range
+        assert_eq!(format_amount(U256::MAX, 77), format!("1.{}", &U256::MAX.to_string()[1..]));
+        assert_eq!(format_amount(U256::one(), u8::MAX), format!("0.{}1", "0".repeat(254)));
+    }
+}
@@ -29,17 +29,59 @@
 //!   - Router: <https://docs.quickswap.exchange/reference/smart-contracts/router02>
 //! - Spookyswap: <https://docs.spooky.fi/Resources/contracts>
 //! - Traderjoe: <https://docs.traderjoexyz.com/en/security-and-contracts/contracts>
+//!
+//! ## Custom addressbook
+//!
+//! The bundled addressbook can be replaced at compile time by setting the
+//! `UNISWAP_RS_ADDRESSBOOK` environment variable to the path of a JSON file with the same schema,
+//! preferably absolute. The file is validated by the build script, so a malformed file fails
+//! the build.
 
 use ethers_contract::Lazy;
 use ethers_core::types::{Address, Chain};
 use serde::Deserialize;
-use std::{borrow::Borrow, collections::HashMap};
+use std::{
+    borrow::{Borrow, Cow},
+    collections::HashMap,
+    io,
+    sync::{PoisonError, RwLock},
+};
 
+#[cfg(not(custom_addressbook))]
 const ADDRESSES_JSON: &str = include_str!("./addresses.json");
+#[cfg(custom_addressbook)]
+const ADDRESSES_JSON: &str = include_str!(env!("UNISWAP_RS_ADDRESSBOOK"));
 
 static ADDRESS_BOOK: Lazy<HashMap<String, Contract>> =
     Lazy::new(|| serde_json::from_str(ADDRESSES_JSON).unwrap());
 
+/// Contracts loaded at runtime with [`load_addressbook_json`], merged with the built-in ones.
+static OVERLAY: Lazy<RwLock<HashMap<&'static str, &'static Contract>>> =
+    Lazy::new(Default::default);
+
+/// Aliases of contract names: `(alias, name, chains on which the alias applies)`.
+const ALIASES: &[(&str, &str, &[Chain])] = &[
+    (
+        "ETH",
+        "WETH",
+        &[
+            Chain::Mainnet,
+            Chain::Rinkeby,
+            Chain::Ropsten,
+            Chain::Goerli,
+            Chain::Kovan,
+            Chain::Sepolia,
+            Chain::Optimism,
+            Chain::OptimismKovan,
+            Chain::Arbitrum,
+        ],
+    ),
+    ("WBNB", "WETH", &[Chain::BinanceSmartChain, Chain::BinanceSmartChainTestnet]),
+    ("WMATIC", "WETH", &[Chain::Polygon, Chain::PolygonMumbai]),
+    ("WFTM", "WETH", &[Chain::Fantom, Chain::FantomTestnet]),
+    ("WAVAX", "WETH", &[Chain::Avalanche, Chain::AvalancheFuji]),
+];
+
 /// Wrapper around a hash map that maps a [Chain] to the contract's deployed address on that chain.
 #[derive(Clone, Debug, Deserialize)]
 pub struct Contract {
@@ -52,19 +94,99 @@ impl Contract {
     pub fn address<C: Borrow<Chain>>(&self, chain: C) -> Option<Address> {
         self.addresses.get(chain.borrow()).copied()
     }
+
+    /// Returns the chains on which the contract is deployed, sorted by chain ID.
+    pub fn chains(&self) -> Vec<Chain> {
+        let mut chains: Vec<_> = self.addresses.keys().copied().collect();
+        chains.sort_unstable_by_key(|&chain| chain as u64);
+        chains
+    }
 }
 
-/// Fetch the addressbook for a contract by its name. If the contract name is not a part of the
-/// address book we return None.
+/// Returns all the chains on which any contract of the addressbook is deployed, sorted by chain ID
+/// and deduplicated.
+pub fn all_chains() -> Vec<Chain> {
+    let overlay = OVERLAY.read().unwrap_or_else(PoisonError::into_inner);
+    let mut chains: Vec<_> = ADDRESS_BOOK
+        .values()
+        .chain(overlay.values().copied())
+        .flat_map(|contract| contract.addresses.keys().copied())
+        .collect();
+    chains.sort_unstable_by_key(|&chain| chain as u64);
+    chains.dedup();
+    chains
+}
+
+/// Fetch the addressbook for a contract by its name, ignoring case. If the contract name is not a
+/// part of the address book we return None.
 pub fn try_contract<S: Borrow<str>>(name: S) -> Option<&'static Contract> {
-    ADDRESS_BOOK.get(name.borrow())
+    let overlay = OVERLAY.read().unwrap_or_else(PoisonError::into_inner);
+    find_contract(&overlay, name.borrow()).map(|(_, contract)| contract)
 }
 
-/// Fetch the address for a contract by its name and chain. If the contract name is not a part of
-/// the address book we return None.
+/// Returns the name and contract of `name` in the overlay or in the built-in addressbook. Exact
+/// matches are checked first.
+fn find_contract(
+    overlay: &HashMap<&'static str, &'static Contract>,
+    name: &str,
+) -> Option<(&'static str, &'static Contract)> {
+    let overlay_iter = || overlay.iter().map(|(key, contract)| (*key, *contract));
+    let book_iter = || ADDRESS_BOOK.iter().map(|(key, contract)| (key.as_str(), contract));
+    overlay_iter().chain(book_iter()).find(|(key, _)| *key == name).or_else(|| {
+        overlay_iter().chain(book_iter()).find(|(key, _)| key.eq_ignore_ascii_case(name))
+    })
+}
+
+/// Parses additional contracts, with the same schema as the built-in addressbook, and merges them
+/// into the addressbook used by all the lookup functions of this module.
+///
+/// `path_or_str` is parsed directly if it is a JSON object, otherwise it is read as a file path.
+///
+/// If `overwrite` is true, the loaded addresses take precedence over the existing ones for the
+/// same contract and chain, otherwise the existing ones are kept.
+///
+/// Note: loaded contracts are leaked to be `'static`, so this is meant to be called once at
+/// startup.
+pub fn load_addressbook_json(path_or_str: &str, overwrite: bool) -> io::Result<()> {
+    let json = if path_or_str.trim_start().starts_with('{') {
+        Cow::Borrowed(path_or_str)
+    } else {
+        Cow::Owned(std::fs::read_to_string(path_or_str)?)
+    };
+    let contracts: HashMap<String, Contract> = serde_json::from_str(&json)?;
+
+    let mut overlay = OVERLAY.write().unwrap_or_else(PoisonError::into_inner);
+    for (name, contract) in contracts {
+        let (name, addresses) = match find_contract(&overlay, &name) {
+            Some((name, existing)) => {
+                let mut addresses = existing.addresses.clone();
+                for (chain, address) in contract.addresses {
+                    if overwrite {
+                        addresses.insert(chain, address);
+                    } else {
+                        addresses.entry(chain).or_insert(address);
+                    }
+                }
+                (name, addresses)
+            }
+            None => (&*Box::leak(name.into_boxed_str()), contract.addresses),
+        };
+        overlay.insert(name, Box::leak(Box::new(Contract { addresses })));
+    }
+    Ok(())
+}
+
+/// Fetch the address for a contract by its name or a known alias and chain, ignoring case. If
+/// the contract name is not a part of the address book we return None.
 pub fn try_address<S: Borrow<str>, C: Borrow<Chain>>(name: S, chain: C) -> Option<Address> {
-    let contract = try_contract(name);
-    contract.and_then(|contract| contract.address(chain))
+    let (name, chain) = (name.borrow(), chain.borrow());
+    try_contract(name).and_then(|contract| contract.address(chain)).or_else(|| {
+        ALIASES
+            .iter()
+            .find(|(alias, _, chains)| alias.eq_ignore_ascii_case(name) && chains.contains(chain))
+            .and_then(|(_, name, _)| try_contract(*name))
+            .and_then(|contract| contract.address(chain))
+    })
 }
 
 /// Fetch the addressbook for a contract by its name. If the contract name is not a part of the
@@ -87,6 +209,20 @@ pub fn address<S: Borrow<str>, C: Borrow<Chain>>(name: S, chain: C) -> Address {
     })
 }
 
+/// Resolves a token symbol to its address on the specified chain, ignoring case. Returns None if
+/// the symbol or its address on the chain are not present in the addressbook.
+///
+/// This is the same as [`try_address`].
+pub fn resolve_token(symbol: &str, chain: Chain) -> Option<Address> {
+    try_address(symbol, chain)
+}
+
+/// Resolves all the token symbols of a path using [`resolve_token`]. If any of the symbols can not
+/// be resolved, the first one is returned as the error.
+pub fn resolve_path<'a>(symbols: &[&'a str], chain: Chain) -> Result<Vec<Address>, &'a str> {
+    symbols.iter().map(|&symbol| resolve_token(symbol, chain).ok_or(symbol)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +242,97 @@ mod tests {
         assert!(try_address("DAI", Chain::Mainnet).is_some());
         assert!(try_address("DAI", Chain::MoonbeamDev).is_none());
     }
+
+    #[test]
+    fn test_chains() {
+        let chains = contract("UniswapV2Factory").chains();
+        assert!(chains.contains(&Chain::Mainnet));
+        assert!(!chains.contains(&Chain::BinanceSmartChain));
+
+        let all = all_chains();
+        assert!(all.windows(2).all(|w| (w[0] as u64) < (w[1] as u64)));
+        for chain in [Chain::Mainnet, Chain::BinanceSmartChain, Chain::Polygon, Chain::Avalanche] {
+            assert!(all.contains(&chain));
+        }
+        assert!(chains.iter().all(|chain| all.contains(chain)));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let weth = try_contract("WETH").unwrap();
+        assert!(std::ptr::eq(try_contract("weth").unwrap(), weth));
+        assert!(std::ptr::eq(try_contract("wEtH").unwrap(), weth));
+        assert_eq!(
+            try_address("uniswapv2factory", Chain::Mainnet),
+            try_address("UniswapV2Factory", Chain::Mainnet)
+        );
+    }
+
+    #[test]
+    fn test_aliases() {
+        let bsc = Chain::BinanceSmartChain;
+        assert_eq!(try_address("WBNB", bsc), try_address("WETH", bsc));
+        assert_eq!(try_address("wbnb", bsc), try_address("WETH", bsc));
+        // only on the alias' chains
+        assert!(try_address("WBNB", Chain::Mainnet).is_none());
+
+        // the native wrapper of Ethereum and its L2s
+        for chain in [Chain::Mainnet, Chain::Goerli, Chain::Optimism, Chain::Arbitrum] {
+            assert!(try_address("ETH", chain).is_some());
+            assert_eq!(try_address("eth", chain), try_address("WETH", chain));
+        }
+        // on BSC, ETH is the bridged token, not the native wrapper
+        assert_ne!(try_address("ETH", bsc), try_address("WETH", bsc));
+    }
+
+    #[test]
+    fn test_load_addressbook() {
+        let (a, b, c) =
+            (Address::repeat_byte(0xa), Address::repeat_byte(0xb), Address::repeat_byte(0xc));
+        assert!(try_contract("LoadedToken").is_none());
+
+        let json = format!(r#"{{ "LoadedToken": {{ "addresses": {{ "mainnet": "{a:?}" }} }} }}"#);
+        load_addressbook_json(&json, false).unwrap();
+        assert_eq!(try_address("LoadedToken", Chain::Mainnet), Some(a));
+
+        // merged into the existing entry, ignoring case
+        let json = format!(
+            r#"{{ "loadedtoken": {{ "addresses": {{ "mainnet": "{b:?}", "goerli": "{c:?}" }} }} }}"#
+        );
+        load_addressbook_json(&json, false).unwrap();
+        assert_eq!(try_address("LoadedToken", Chain::Mainnet), Some(a));
+        assert_eq!(try_address("LoadedToken", Chain::Goerli), Some(c));
+
+        load_addressbook_json(&json, true).unwrap();
+        assert_eq!(try_address("LoadedToken", Chain::Mainnet), Some(b));
+
+        // built-in entries are extended too
+        let json = format!(
+            r#"{{ "WETH": {{ "addresses": {{ "mainnet": "{a:?}", "sepolia": "{a:?}" }} }} }}"#
+        );
+        load_addressbook_json(&json, false).unwrap();
+        assert_eq!(
+            try_address("WETH", Chain::Mainnet),
+            ADDRESS_BOOK["WETH"].address(Chain::Mainnet)
+        );
+        assert_eq!(try_address("WETH", Chain::Sepolia), Some(a));
+        // and resolved by their aliases
+        assert_eq!(try_address("ETH", Chain::Sepolia), Some(a));
+
+        assert!(load_addressbook_json("{ invalid json", false).is_err());
+        assert!(load_addressbook_json("./does/not/exist.json", false).is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dai = address("DAI", Chain::Mainnet);
+        assert_eq!(resolve_token("DAI", Chain::Mainnet), Some(dai));
+        assert_eq!(resolve_token("dai", Chain::Mainnet), Some(dai));
+        assert_eq!(resolve_token("dai", Chain::MoonbeamDev), None);
+        assert_eq!(resolve_token("rand", Chain::Mainnet), None);
+
+        let weth = address("WETH", Chain::Mainnet);
+        assert_eq!(resolve_path(&["weth", "Dai"], Chain::Mainnet), Ok(vec![weth, dai]));
+        assert_eq!(resolve_path(&["weth", "rand", "dai"], Chain::Mainnet), Err("rand"));
+    }
 }
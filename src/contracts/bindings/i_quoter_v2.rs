@@ -0,0 +1,354 @@
+pub use i_quoter_v2::*;
+#[allow(clippy::too_many_arguments, non_camel_case_types)]
+pub mod i_quoter_v2 {
+    #![allow(clippy::enum_variant_names)]
+    #![allow(dead_code)]
+    #![allow(clippy::type_complexity)]
+    #![allow(unused_imports)]
+    use ethers_contract::{
+        builders::{ContractCall, Event},
+        Contract, Lazy,
+    };
+    use ethers_core::{
+        abi::{Abi, Detokenize, InvalidOutputType, Token, Tokenizable},
+        types::*,
+    };
+    use ethers_providers::Middleware;
+    #[doc = "IQuoterV2 was auto-generated with ethers-rs Abigen. More information at: https://github.com/gakonst/ethers-rs"]
+    use std::sync::Arc;
+    # [rustfmt :: skip] const __ABI : & str = "[{\"inputs\":[{\"internalType\":\"bytes\",\"name\":\"path\",\"type\":\"bytes\"},{\"internalType\":\"uint256\",\"name\":\"amountIn\",\"type\":\"uint256\"}],\"name\":\"quoteExactInput\",\"outputs\":[{\"internalType\":\"uint256\",\"name\":\"amountOut\",\"type\":\"uint256\"},{\"internalType\":\"uint160[]\",\"name\":\"sqrtPriceX96AfterList\",\"type\":\"uint160[]\"},{\"internalType\":\"uint32[]\",\"name\":\"initializedTicksCrossedList\",\"type\":\"uint32[]\"},{\"internalType\":\"uint256\",\"name\":\"gasEstimate\",\"type\":\"uint256\"}],\"stateMutability\":\"nonpayable\",\"type\":\"function\"},{\"inputs\":[{\"components\":[{\"internalType\":\"address\",\"name\":\"tokenIn\",\"type\":\"address\"},{\"internalType\":\"address\",\"name\":\"tokenOut\",\"type\":\"address\"},{\"internalType\":\"uint256\",\"name\":\"amountIn\",\"type\":\"uint256\"},{\"internalType\":\"uint24\",\"name\":\"fee\",\"type\":\"uint24\"},{\"internalType\":\"uint160\",\"name\":\"sqrtPriceLimitX96\",\"type\":\"uint160\"}],\"internalType\":\"struct IQuoterV2.QuoteExactInputSingleParams\",\"name\":\"params\",\"type\":\"tuple\"}],\"name\":\"quoteExactInputSingle\",\"outputs\":[{\"internalType\":\"uint256\",\"name\":\"amountOut\",\"type\":\"uint256\"},{\"internalType\":\"uint160\",\"name\":\"sqrtPriceX96After\",\"type\":\"uint160\"},{\"internalType\":\"uint32\",\"name\":\"initializedTicksCrossed\",\"type\":\"uint32\"},{\"internalType\":\"uint256\",\"name\":\"gasEstimate\",\"type\":\"uint256\"}],\"stateMutability\":\"nonpayable\",\"type\":\"function\"},{\"inputs\":[{\"internalType\":\"bytes\",\"name\":\"path\",\"type\":\"bytes\"},{\"internalType\":\"uint256\",\"name\":\"amountOut\",\"type\":\"uint256\"}],\"name\":\"quoteExactOutput\",\"outputs\":[{\"internalType\":\"uint256\",\"name\":\"amountIn\",\"type\":\"uint256\"},{\"internalType\":\"uint160[]\",\"name\":\"sqrtPriceX96AfterList\",\"type\":\"uint160[]\"},{\"internalType\":\"uint32[]\",\"name\":\"initializedTicksCrossedList\",\"type\":\"uint32[]\"},{\"internalType\":\"uint256\",\"name\":\"gasEstimate\",\"type\":\"uint256\"}],\"stateMutability\":\"nonpayable\",\"type\":\"function\"},{\"inputs\":[{\"components\":[{\"internalType\":\"address\",\"name\":\"tokenIn\",\"type\":\"address\"},{\"internalType\":\"address\",\"name\":\"tokenOut\",\"type\":\"address\"},{\"internalType\":\"uint256\",\"name\":\"amount\",\"type\":\"uint256\"},{\"internalType\":\"uint24\",\"name\":\"fee\",\"type\":\"uint24\"},{\"internalType\":\"uint160\",\"name\":\"sqrtPriceLimitX96\",\"type\":\"uint160\"}],\"internalType\":\"struct IQuoterV2.QuoteExactOutputSingleParams\",\"name\":\"params\",\"type\":\"tuple\"}],\"name\":\"quoteExactOutputSingle\",\"outputs\":[{\"internalType\":\"uint256\",\"name\":\"amountIn\",\"type\":\"uint256\"},{\"internalType\":\"uint160\",\"name\":\"sqrtPriceX96After\",\"type\":\"uint160\"},{\"internalType\":\"uint32\",\"name\":\"initializedTicksCrossed\",\"type\":\"uint32\"},{\"internalType\":\"uint256\",\"name\":\"gasEstimate\",\"type\":\"uint256\"}],\"stateMutability\":\"nonpayable\",\"type\":\"function\"}]\n" ;
+    #[doc = r" The parsed JSON-ABI of the contract."]
+    pub static IQUOTERV2_ABI: ethers_contract::Lazy<ethers_core::abi::Abi> =
+        ethers_contract::Lazy::new(|| {
+            ethers_core::utils::__serde_json::from_str(__ABI).expect("invalid abi")
+        });
+    pub struct IQuoterV2<M>(ethers_contract::Contract<M>);
+    impl<M> Clone for IQuoterV2<M> {
+        fn clone(&self) -> Self {
+            IQuoterV2(self.0.clone())
+        }
+    }
+    impl<M> std::ops::Deref for IQuoterV2<M> {
+        type Target = ethers_contract::Contract<M>;
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+    impl<M> std::fmt::Debug for IQuoterV2<M> {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.debug_tuple(stringify!(IQuoterV2)).field(&self.address()).finish()
+        }
+    }
+    impl<M: ethers_providers::Middleware> IQuoterV2<M> {
+        #[doc = r" Creates a new contract instance with the specified `ethers`"]
+        #[doc = r" client at the given `Address`. The contract derefs to a `ethers::Contract`"]
+        #[doc = r" object"]
+        pub fn new<T: Into<ethers_core::types::Address>>(
+            address: T,
+            client: ::std::sync::Arc<M>,
+        ) -> Self {
+            ethers_contract::Contract::new(address.into(), IQUOTERV2_ABI.clone(), client).into()
+        }
+        #[doc = "Calls the contract's `quoteExactInput` (0xcdca1753) function"]
+        pub fn quote_exact_input(
+            &self,
+            path: ethers_core::types::Bytes,
+            amount_in: ethers_core::types::U256,
+        ) -> ethers_contract::builders::ContractCall<
+            M,
+            (
+                ethers_core::types::U256,
+                ::std::vec::Vec<ethers_core::types::U256>,
+                ::std::vec::Vec<u32>,
+                ethers_core::types::U256,
+            ),
+        > {
+            self.0
+                .method_hash([205, 202, 23, 83], (path, amount_in))
+                .expect("method not found (this should never happen)")
+        }
+        #[doc = "Calls the contract's `quoteExactInputSingle` (0xc6a5026a) function"]
+        pub fn quote_exact_input_single(
+            &self,
+            params: QuoteExactInputSingleParams,
+        ) -> ethers_contract::builders::ContractCall<
+            M,
+            (ethers_core::types::U256, ethers_core::types::U256, u32, ethers_core::types::U256),
+        > {
+            self.0
+                .method_hash([198, 165, 2, 106], (params,))
+                .expect("method not found (this should never happen)")
+        }
+        #[doc = "Calls the contract's `quoteExactOutput` (0x2f80bb1d) function"]
+        pub fn quote_exact_output(
+            &self,
+            path: ethers_core::types::Bytes,
+            amount_out: ethers_core::types::U256,
+        ) -> ethers_contract::builders::ContractCall<
+            M,
+            (
+                ethers_core::types::U256,
+                ::std::vec::Vec<ethers_core::types::U256>,
+                ::std::vec::Vec<u32>,
+                ethers_core::types::U256,
+            ),
+        > {
+            self.0
+                .method_hash([47, 128, 187, 29], (path, amount_out))
+                .expect("method not found (this should never happen)")
+        }
+        #[doc = "Calls the contract's `quoteExactOutputSingle` (0xbd21704a) function"]
+        pub fn quote_exact_output_single(
+            &self,
+            params: QuoteExactOutputSingleParams,
+        ) -> ethers_contract::builders::ContractCall<
+            M,
+            (ethers_core::types::U256, ethers_core::types::U256, u32, ethers_core::types::U256),
+        > {
+            self.0
+                .method_hash([189, 33, 112, 74], (params,))
+                .expect("method not found (this should never happen)")
+        }
+    }
+    impl<M: ethers_providers::Middleware> From<ethers_contract::Contract<M>> for IQuoterV2<M> {
+        fn from(contract: ethers_contract::Contract<M>) -> Self {
+            Self(contract)
+        }
+    }
+    #[doc = "Container type for all input parameters for the `quoteExactInput` function with signature `quoteExactInput(bytes,uint256)` and selector `[205, 202, 23, 83]`"]
+    #[derive(
+        Clone,
+        Debug,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthCall,
+        ethers_contract :: EthDisplay,
+        Default,
+    )]
+    #[ethcall(name = "quoteExactInput", abi = "quoteExactInput(bytes,uint256)")]
+    pub struct QuoteExactInputCall {
+        pub path: ethers_core::types::Bytes,
+        pub amount_in: ethers_core::types::U256,
+    }
+    #[doc = "Container type for all input parameters for the `quoteExactInputSingle` function with signature `quoteExactInputSingle((address,address,uint256,uint24,uint160))` and selector `[198, 165, 2, 106]`"]
+    #[derive(
+        Clone,
+        Debug,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthCall,
+        ethers_contract :: EthDisplay,
+        Default,
+    )]
+    #[ethcall(
+        name = "quoteExactInputSingle",
+        abi = "quoteExactInputSingle((address,address,uint256,uint24,uint160))"
+    )]
+    pub struct QuoteExactInputSingleCall {
+        pub params: QuoteExactInputSingleParams,
+    }
+    #[doc = "Container type for all input parameters for the `quoteExactOutput` function with signature `quoteExactOutput(bytes,uint256)` and selector `[47, 128, 187, 29]`"]
+    #[derive(
+        Clone,
+        Debug,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthCall,
+        ethers_contract :: EthDisplay,
+        Default,
+    )]
+    #[ethcall(name = "quoteExactOutput", abi = "quoteExactOutput(bytes,uint256)")]
+    pub struct QuoteExactOutputCall {
+        pub path: ethers_core::types::Bytes,
+        pub amount_out: ethers_core::types::U256,
+    }
+    #[doc = "Container type for all input parameters for the `quoteExactOutputSingle` function with signature `quoteExactOutputSingle((address,address,uint256,uint24,uint160))` and selector `[189, 33, 112, 74]`"]
+    #[derive(
+        Clone,
+        Debug,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthCall,
+        ethers_contract :: EthDisplay,
+        Default,
+    )]
+    #[ethcall(
+        name = "quoteExactOutputSingle",
+        abi = "quoteExactOutputSingle((address,address,uint256,uint24,uint160))"
+    )]
+    pub struct QuoteExactOutputSingleCall {
+        pub params: QuoteExactOutputSingleParams,
+    }
+    #[derive(Debug, Clone, PartialEq, Eq, ethers_contract :: EthAbiType)]
+    pub enum IQuoterV2Calls {
+        QuoteExactInput(QuoteExactInputCall),
+        QuoteExactInputSingle(QuoteExactInputSingleCall),
+        QuoteExactOutput(QuoteExactOutputCall),
+        QuoteExactOutputSingle(QuoteExactOutputSingleCall),
+    }
+    impl ethers_core::abi::AbiDecode for IQuoterV2Calls {
+        fn decode(
+            data: impl AsRef<[u8]>,
+        ) -> ::std::result::Result<Self, ethers_core::abi::AbiError> {
+            if let Ok(decoded) =
+                <QuoteExactInputCall as ethers_core::abi::AbiDecode>::decode(data.as_ref())
+            {
+                return Ok(IQuoterV2Calls::QuoteExactInput(decoded));
+            }
+            if let Ok(decoded) =
+                <QuoteExactInputSingleCall as ethers_core::abi::AbiDecode>::decode(data.as_ref())
+            {
+                return Ok(IQuoterV2Calls::QuoteExactInputSingle(decoded));
+            }
+            if let Ok(decoded) =
+                <QuoteExactOutputCall as ethers_core::abi::AbiDecode>::decode(data.as_ref())
+            {
+                return Ok(IQuoterV2Calls::QuoteExactOutput(decoded));
+            }
+            if let Ok(decoded) =
+                <QuoteExactOutputSingleCall as ethers_core::abi::AbiDecode>::decode(data.as_ref())
+            {
+                return Ok(IQuoterV2Calls::QuoteExactOutputSingle(decoded));
+            }
+            Err(ethers_core::abi::Error::InvalidData.into())
+        }
+    }
+    impl ethers_core::abi::AbiEncode for IQuoterV2Calls {
+        fn encode(self) -> Vec<u8> {
+            match self {
+                IQuoterV2Calls::QuoteExactInput(element) => element.encode(),
+                IQuoterV2Calls::QuoteExactInputSingle(element) => element.encode(),
+                IQuoterV2Calls::QuoteExactOutput(element) => element.encode(),
+                IQuoterV2Calls::QuoteExactOutputSingle(element) => element.encode(),
+            }
+        }
+    }
+    impl ::std::fmt::Display for IQuoterV2Calls {
+        fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+            match self {
+                IQuoterV2Calls::QuoteExactInput(element) => element.fmt(f),
+                IQuoterV2Calls::QuoteExactInputSingle(element) => element.fmt(f),
+                IQuoterV2Calls::QuoteExactOutput(element) => element.fmt(f),
+                IQuoterV2Calls::QuoteExactOutputSingle(element) => element.fmt(f),
+            }
+        }
+    }
+    impl ::std::convert::From<QuoteExactInputCall> for IQuoterV2Calls {
+        fn from(var: QuoteExactInputCall) -> Self {
+            IQuoterV2Calls::QuoteExactInput(var)
+        }
+    }
+    impl ::std::convert::From<QuoteExactInputSingleCall> for IQuoterV2Calls {
+        fn from(var: QuoteExactInputSingleCall) -> Self {
+            IQuoterV2Calls::QuoteExactInputSingle(var)
+        }
+    }
+    impl ::std::convert::From<QuoteExactOutputCall> for IQuoterV2Calls {
+        fn from(var: QuoteExactOutputCall) -> Self {
+            IQuoterV2Calls::QuoteExactOutput(var)
+        }
+    }
+    impl ::std::convert::From<QuoteExactOutputSingleCall> for IQuoterV2Calls {
+        fn from(var: QuoteExactOutputSingleCall) -> Self {
+            IQuoterV2Calls::QuoteExactOutputSingle(var)
+        }
+    }
+    #[doc = "Container type for all return fields from the `quoteExactInput` function with signature `quoteExactInput(bytes,uint256)` and selector `[205, 202, 23, 83]`"]
+    #[derive(
+        Clone,
+        Debug,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthAbiType,
+        ethers_contract :: EthAbiCodec,
+        Default,
+    )]
+    pub struct QuoteExactInputReturn {
+        pub amount_out: ethers_core::types::U256,
+        pub sqrt_price_x96_after_list: ::std::vec::Vec<ethers_core::types::U256>,
+        pub initialized_ticks_crossed_list: ::std::vec::Vec<u32>,
+        pub gas_estimate: ethers_core::types::U256,
+    }
+    #[doc = "Container type for all return fields from the `quoteExactInputSingle` function with signature `quoteExactInputSingle((address,address,uint256,uint24,uint160))` and selector `[198, 165, 2, 106]`"]
+    #[derive(
+        Clone,
+        Debug,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthAbiType,
+        ethers_contract :: EthAbiCodec,
+        Default,
+    )]
+    pub struct QuoteExactInputSingleReturn {
+        pub amount_out: ethers_core::types::U256,
+        pub sqrt_price_x96_after: ethers_core::types::U256,
+        pub initialized_ticks_crossed: u32,
+        pub gas_estimate: ethers_core::types::U256,
+    }
+    #[doc = "Container type for all return fields from the `quoteExactOutput` function with signature `quoteExactOutput(bytes,uint256)` and selector `[47, 128, 187, 29]`"]
+    #[derive(
+        Clone,
+        Debug,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthAbiType,
+        ethers_contract :: EthAbiCodec,
+        Default,
+    )]
+    pub struct QuoteExactOutputReturn {
+        pub amount_in: ethers_core::types::U256,
+        pub sqrt_price_x96_after_list: ::std::vec::Vec<ethers_core::types::U256>,
+        pub initialized_ticks_crossed_list: ::std::vec::Vec<u32>,
+        pub gas_estimate: ethers_core::types::U256,
+    }
+    #[doc = "Container type for all return fields from the `quoteExactOutputSingle` function with signature `quoteExactOutputSingle((address,address,uint256,uint24,uint160))` and selector `[189, 33, 112, 74]`"]
+    #[derive(
+        Clone,
+        Debug,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthAbiType,
+        ethers_contract :: EthAbiCodec,
+        Default,
+    )]
+    pub struct QuoteExactOutputSingleReturn {
+        pub amount_in: ethers_core::types::U256,
+        pub sqrt_price_x96_after: ethers_core::types::U256,
+        pub initialized_ticks_crossed: u32,
+        pub gas_estimate: ethers_core::types::U256,
+    }
+    #[doc = "`QuoteExactInputSingleParams(address,address,uint256,uint24,uint160)`"]
+    #[derive(
+        Clone,
+        Debug,
+        Default,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthAbiType,
+        ethers_contract :: EthAbiCodec,
+    )]
+    pub struct QuoteExactInputSingleParams {
+        pub token_in: ethers_core::types::Address,
+        pub token_out: ethers_core::types::Address,
+        pub amount_in: ethers_core::types::U256,
+        pub fee: u32,
+        pub sqrt_price_limit_x96: ethers_core::types::U256,
+    }
+    #[doc = "`QuoteExactOutputSingleParams(address,address,uint256,uint24,uint160)`"]
+    #[derive(
+        Clone,
+        Debug,
+        Default,
+        Eq,
+        PartialEq,
+        ethers_contract :: EthAbiType,
+        ethers_contract :: EthAbiCodec,
+    )]
+    pub struct QuoteExactOutputSingleParams {
+        pub token_in: ethers_core::types::Address,
+        pub token_out: ethers_core::types::Address,
+        pub amount: ethers_core::types::U256,
+        pub fee: u32,
+        pub sqrt_price_limit_x96: ethers_core::types::U256,
+    }
+}
@@ -3,6 +3,7 @@
 //! This is autogenerated code.
 //! Do not manually edit these files.
 //! These files may be overwritten by the codegen system at any time.
+pub mod i_quoter_v2;
 pub mod i_swap_router;
 pub mod i_uniswap_v2_factory;
 pub mod i_uniswap_v2_pair;
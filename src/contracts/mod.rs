@@ -15,8 +15,9 @@ pub mod bindings {
     mod _bindings;
 
     pub use _bindings::{
-        i_swap_router, i_uniswap_v2_factory, i_uniswap_v2_pair, i_uniswap_v2_router_02,
-        i_uniswap_v3_factory, i_uniswap_v3_pool, i_universal_router, ierc20, iweth,
+        i_quoter_v2, i_swap_router, i_uniswap_v2_factory, i_uniswap_v2_pair,
+        i_uniswap_v2_router_02, i_uniswap_v3_factory, i_uniswap_v3_pool, i_universal_router,
+        ierc20, iweth,
     };
 
     // should not be used directly as it's not a valid contract
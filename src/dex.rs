@@ -1,12 +1,16 @@
 use crate::{
+    constants::{MAX_GAS_MULTIPLIER, MIN_DEADLINE_BUFFER},
     contracts::bindings::iweth::IWETH,
     errors::{Error, Result},
     utils::*,
-    v2::Pair,
-    Amount, Protocol, ProtocolType,
+    v2::{GasAccounting, Pair},
+    Amount, DeadlinePolicy, Protocol, ProtocolType,
+};
+use ethers_contract::{builders::ContractCall, ContractError};
+use ethers_core::{
+    abi::Detokenize,
+    types::{Address, BlockNumber, U256},
 };
-use ethers_contract::builders::ContractCall;
-use ethers_core::types::{Address, U256};
 use ethers_providers::Middleware;
 use std::{fmt, sync::Arc};
 
@@ -20,17 +24,28 @@ pub struct Dex<M> {
 
     /// The address of the chain's wrapped native token.
     weth: Option<Address>,
+
+    /// The minimum number of seconds between a swap's deadline and the latest block.
+    min_deadline_buffer: u64,
 }
 
 impl<M> Clone for Dex<M> {
     fn clone(&self) -> Self {
-        Self { protocol: self.protocol.clone(), weth: self.weth }
+        Self {
+            protocol: self.protocol.clone(),
+            weth: self.weth,
+            min_deadline_buffer: self.min_deadline_buffer,
+        }
     }
 }
 
 impl<M> fmt::Debug for Dex<M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Dex").field("protocol", &self.protocol).field("weth", &self.weth).finish()
+        f.debug_struct("Dex")
+            .field("protocol", &self.protocol)
+            .field("weth", &self.weth)
+            .field("min_deadline_buffer", &self.min_deadline_buffer)
+            .finish()
     }
 }
 
@@ -38,20 +53,72 @@ impl<M: Middleware> Dex<M> {
     /// Creates a new instance of Dex using the provided addresses.
     pub fn new(client: Arc<M>, factory: Address, router: Address, protocol: ProtocolType) -> Self {
         let protocol = Protocol::new(client, factory, router, protocol);
-        Self { protocol, weth: None }
+        Self { protocol, weth: None, min_deadline_buffer: MIN_DEADLINE_BUFFER }
+    }
+
+    /// Same as [`new`](Self::new), but validates that the router belongs to the factory with
+    /// [`router_matches_factory`](crate::v2::router_matches_factory).
+    ///
+    /// Returns [`Error::RouterFactoryMismatch`] if the router was deployed for a different
+    /// factory.
+    #[doc(alias = "dry_run_only")]
+    pub async fn new_checked(
+        client: Arc<M>,
+        factory: Address,
+        router: Address,
+        protocol: ProtocolType,
+    ) -> Result<Self> {
+        if protocol.is_v2()
+            && !crate::v2::router_matches_factory(client.clone(), router, factory).await?
+        {
+            return Err(Error::RouterFactoryMismatch { router });
+        }
+        Ok(Self::new(client, factory, router, protocol))
     }
 
     /// Creates a new instance by searching for the required addresses in the [addressbook].
     ///
+    /// The router is not validated against the factory, since this constructor makes no calls and
+    /// the addressbook pairs every router with its own factory. Use
+    /// [`new_with_chain_checked`](Self::new_with_chain_checked) when the addressbook was extended
+    /// at runtime, for example with
+    /// [`load_addressbook_json`](crate::contracts::addresses::load_addressbook_json).
+    ///
     /// [addressbook]: crate::contracts::addresses
     #[cfg(feature = "addresses")]
     pub fn new_with_chain(client: Arc<M>, chain: Chain, protocol: ProtocolType) -> Option<Self> {
         Protocol::new_with_chain(client, chain, protocol).map(|protocol| {
-            let weth = crate::contracts::addresses::try_address("WETH", chain);
-            Self { protocol, weth }
+            let weth =
+                crate::contracts::addresses::try_address(native_wrapper_symbol(chain), chain);
+            Self { protocol, weth, min_deadline_buffer: MIN_DEADLINE_BUFFER }
         })
     }
 
+    /// Same as [`new_with_chain`](Self::new_with_chain), but validates that the router belongs to
+    /// the factory like [`new_checked`](Self::new_checked).
+    ///
+    /// Returns None if the addresses are not found in the addressbook, and
+    /// [`Error::RouterFactoryMismatch`] if the router was deployed for a different factory.
+    #[cfg(feature = "addresses")]
+    #[doc(alias = "dry_run_only")]
+    pub async fn new_with_chain_checked(
+        client: Arc<M>,
+        chain: Chain,
+        protocol: ProtocolType,
+    ) -> Result<Option<Self>> {
+        let dex = match Self::new_with_chain(client, chain, protocol) {
+            Some(dex) => dex,
+            None => return Ok(None),
+        };
+        let (factory, router) = (dex.protocol.factory_address(), dex.protocol.router_address());
+        if protocol.is_v2()
+            && !crate::v2::router_matches_factory(dex.client(), router, factory).await?
+        {
+            return Err(Error::RouterFactoryMismatch { router });
+        }
+        Ok(Some(dex))
+    }
+
     /// Returns a pointer to the client.
     pub fn client(&self) -> Arc<M> {
         self.protocol.client()
@@ -74,9 +141,11 @@ impl<M: Middleware> Dex<M> {
         self.protocol.create_pair(token_a, token_b)
     }
 
-    /// Returns the pair for two token addresses.
-    pub fn pair_for(&self, token_a: Address, token_b: Address) -> Pair<M> {
-        self.protocol.pair_for(token_a, token_b)
+    /// Returns the pair for two token addresses, resolved according to the factory's
+    /// [`PairResolution`](crate::v2::PairResolution).
+    #[doc(alias = "dry_run_only")]
+    pub async fn pair_for(&self, token_a: Address, token_b: Address) -> Result<Pair<M>> {
+        self.protocol.pair_for(token_a, token_b).await
     }
 
     /* ----------------------------------------- Router ----------------------------------------- */
@@ -177,6 +246,9 @@ impl<M: Middleware> Dex<M> {
     /// * `deadline` - The number of seconds after which the transaction will revert. If `None`, it
     ///   will default to 1800 seconds.
     ///
+    /// The deadline is validated like in [`validate_deadline`](Self::validate_deadline), with the
+    /// buffer set in [`set_min_deadline_buffer`](Self::set_min_deadline_buffer).
+    ///
     /// [`NATIVE_ADDRESS`]: crate::constants::NATIVE_ADDRESS
     pub async fn swap(
         &mut self,
@@ -185,10 +257,41 @@ impl<M: Middleware> Dex<M> {
         path: &[Address],
         to: Option<Address>,
         deadline: Option<u64>,
+    ) -> Result<ContractCall<M, Vec<U256>>> {
+        let timestamp = self.current_timestamp().await?;
+        let deadline = get_deadline_opt(deadline);
+        self.swap_at(amount, slippage_tolerance, path, to, deadline, timestamp).await
+    }
+
+    /// Same as [`swap`](Self::swap), but resolves the deadline from a [DeadlinePolicy] with
+    /// [`resolve_deadline`](Self::resolve_deadline), so that relative deadlines are based on the
+    /// chain's time instead of the local clock.
+    pub async fn swap_with_deadline(
+        &mut self,
+        amount: Amount,
+        slippage_tolerance: f32,
+        path: &[Address],
+        to: Option<Address>,
+        deadline: DeadlinePolicy,
+    ) -> Result<ContractCall<M, Vec<U256>>> {
+        let timestamp = self.current_timestamp().await?;
+        let deadline = deadline.resolve(timestamp);
+        self.swap_at(amount, slippage_tolerance, path, to, deadline, timestamp).await
+    }
+
+    async fn swap_at(
+        &mut self,
+        amount: Amount,
+        slippage_tolerance: f32,
+        path: &[Address],
+        to: Option<Address>,
+        deadline: U256,
+        timestamp: U256,
     ) -> Result<ContractCall<M, Vec<U256>>> {
         if !(0.0..=100.0).contains(&slippage_tolerance) {
             return Err(Error::InvalidSlippage);
         }
+        check_deadline(deadline, timestamp, self.min_deadline_buffer)?;
 
         let sender = self.client().default_sender();
         let to = self.get_to(to);
@@ -210,8 +313,6 @@ impl<M: Middleware> Dex<M> {
             return Err(Error::SwapToSelf);
         }
 
-        let deadline = get_deadline_opt(deadline);
-
         let mut call =
             self.protocol.swap(amount, slippage_tolerance, path, to, deadline, weth).await?;
 
@@ -222,6 +323,108 @@ impl<M: Middleware> Dex<M> {
         Ok(call)
     }
 
+    /// Estimates the gas used by a swap `call`, multiplies it by `gas_multiplier` and sets the
+    /// result as the call's gas limit. Returns the new gas limit.
+    ///
+    /// `gas_multiplier` pads the estimate, e.g. `1.2` for 20% headroom. Use `1.0` to set the
+    /// estimate as-is. It is clamped to `1.0..=`[`MAX_GAS_MULTIPLIER`].
+    ///
+    /// [`MAX_GAS_MULTIPLIER`]: crate::constants::MAX_GAS_MULTIPLIER
+    #[doc(alias = "dry_run_only")]
+    pub async fn estimate_swap_gas<D: Detokenize>(
+        &self,
+        call: &mut ContractCall<M, D>,
+        gas_multiplier: f64,
+    ) -> Result<U256> {
+        let estimate = call.estimate_gas().await?;
+        let gas = apply_gas_multiplier(estimate, gas_multiplier);
+        call.tx.set_gas(gas);
+        Ok(gas)
+    }
+
+    /// Returns the minimum number of seconds between a swap's deadline and the latest block.
+    pub fn min_deadline_buffer(&self) -> u64 {
+        self.min_deadline_buffer
+    }
+
+    /// Sets the minimum number of seconds between a swap's deadline and the latest block, which
+    /// defaults to [`MIN_DEADLINE_BUFFER`]. Use `0` to only reject deadlines in the past.
+    pub fn set_min_deadline_buffer(&mut self, min_deadline_buffer: u64) -> &mut Self {
+        self.min_deadline_buffer = min_deadline_buffer;
+
+        self
+    }
+
+    /// Estimates the gas paid by a swap `call` that spends `amount_in` of an allowance of
+    /// `current_allowance`, after the refund of resetting the allowance to zero.
+    ///
+    /// Unlike [`estimate_swap_gas`](Self::estimate_swap_gas), this does not set the call's gas
+    /// limit, which must cover the gas used before refunds. See
+    /// [`GasAccounting::transfer_from_refund`].
+    #[doc(alias = "dry_run_only")]
+    pub async fn estimate_swap_gas_paid<D: Detokenize>(
+        &self,
+        call: &ContractCall<M, D>,
+        current_allowance: U256,
+        amount_in: U256,
+    ) -> Result<U256> {
+        let estimate = call.estimate_gas().await?;
+        let refund = GasAccounting::transfer_from_refund(current_allowance, amount_in);
+        Ok(GasAccounting::apply_refund(estimate, refund))
+    }
+
+    /// Validates an absolute `deadline` against the latest block's timestamp, falling back to the
+    /// local clock if there is no latest block.
+    ///
+    /// Returns [`Error::DeadlineInPast`] if the deadline has already passed, or
+    /// [`Error::DeadlineTooSoon`] if it is less than `min_buffer` seconds away, in which case the
+    /// swap would likely revert before being included. See [`MIN_DEADLINE_BUFFER`] for a sensible
+    /// default.
+    #[doc(alias = "dry_run_only")]
+    pub async fn validate_deadline(&self, deadline: U256, min_buffer: u64) -> Result<()> {
+        let timestamp = self.current_timestamp().await?;
+        check_deadline(deadline, timestamp, min_buffer)
+    }
+
+    /// Resolves a [DeadlinePolicy] into an absolute deadline.
+    ///
+    /// [`SecondsFromNow`](DeadlinePolicy::SecondsFromNow) is added to the latest block's
+    /// timestamp, falling back to the local clock if there is no latest block. Only this policy
+    /// makes a call to the client.
+    #[doc(alias = "dry_run_only")]
+    pub async fn resolve_deadline(&self, policy: DeadlinePolicy) -> Result<U256> {
+        let timestamp = match policy {
+            DeadlinePolicy::SecondsFromNow(_) => self.current_timestamp().await?,
+            _ => U256::zero(),
+        };
+        Ok(policy.resolve(timestamp))
+    }
+
+    /// Returns the latest block's timestamp, or the local clock's if there is no latest block.
+    async fn current_timestamp(&self) -> Result<U256> {
+        let block = self
+            .client()
+            .get_block(BlockNumber::Latest)
+            .await
+            .map_err(ContractError::<M>::MiddlewareError)?;
+        Ok(block.map_or_else(|| now().as_secs().into(), |block| block.timestamp))
+    }
+
+    /* ----------------------------------------- Library ---------------------------------------- */
+
+    /// Returns the reserves of the pair of two tokens, sorted like the tokens provided.
+    #[doc(alias = "dry_run_only")]
+    pub async fn reserves(&self, token_a: Address, token_b: Address) -> Result<(U256, U256)> {
+        self.protocol.reserves(token_a, token_b).await
+    }
+
+    /// Returns the amounts received by swapping `amount_in` of the first token along the path,
+    /// starting with `amount_in` itself.
+    #[doc(alias = "dry_run_only")]
+    pub async fn quote(&self, amount_in: U256, path: &[Address]) -> Result<Vec<U256>> {
+        self.protocol.quote(amount_in, path).await
+    }
+
     /* ------------------------------------------ WETH ------------------------------------------ */
 
     /// Returns the address of the wrapped native token.
@@ -232,6 +435,7 @@ impl<M: Middleware> Dex<M> {
     /// Sets the wrapped native token address by calling the WETH() method on the V2 router.
     ///
     /// Note that this does nothing on a V3 protocol.
+    #[doc(alias = "dry_run_only")]
     pub async fn set_weth(&mut self) -> Result<&mut Self> {
         match &self.protocol {
             Protocol::V2(v2) => {
@@ -297,6 +501,26 @@ impl<M: Middleware> Dex<M> {
     }
 }
 
+/// Checks that `deadline` is at least `min_buffer` seconds after `timestamp`.
+fn check_deadline(deadline: U256, timestamp: U256, min_buffer: u64) -> Result<()> {
+    if deadline <= timestamp {
+        return Err(Error::DeadlineInPast);
+    }
+    let remaining = deadline - timestamp;
+    if remaining < min_buffer.into() {
+        return Err(Error::DeadlineTooSoon { remaining: remaining.as_u64() });
+    }
+    Ok(())
+}
+
+/// `gas * multiplier`, with `multiplier` clamped to `1.0..=MAX_GAS_MULTIPLIER`.
+fn apply_gas_multiplier(gas: U256, multiplier: f64) -> U256 {
+    let multiplier =
+        if multiplier.is_nan() { 1.0 } else { multiplier.clamp(1.0, MAX_GAS_MULTIPLIER) };
+    let multiplier_bps = (multiplier * 10_000.0) as u64;
+    gas * multiplier_bps / 10_000
+}
+
 /// first === last
 fn path_eq(path: &[Address], weth: &Address) -> bool {
     let first = path.first().expect("path is empty");
@@ -395,6 +619,59 @@ mod tests {
         assert!(path_eq(&path, &weth));
     }
 
+    #[test]
+    fn test_apply_gas_multiplier() {
+        let gas = U256::from(100_000);
+        assert_eq!(apply_gas_multiplier(gas, 1.0), gas);
+        assert_eq!(apply_gas_multiplier(gas, 1.2), 120_000.into());
+        assert_eq!(apply_gas_multiplier(gas, 0.5), gas);
+        assert_eq!(apply_gas_multiplier(gas, f64::NAN), gas);
+        assert_eq!(apply_gas_multiplier(gas, 100.0), 500_000.into());
+    }
+
+    #[test]
+    fn test_check_deadline() {
+        let now = U256::from(1_000_000);
+        assert!(check_deadline(now + 30, now, MIN_DEADLINE_BUFFER).is_ok());
+        assert!(check_deadline(now + 1, now, 0).is_ok());
+        assert!(matches!(check_deadline(now, now, 0).unwrap_err(), Error::DeadlineInPast));
+        assert!(matches!(check_deadline(now - 1, now, 30).unwrap_err(), Error::DeadlineInPast));
+        assert!(matches!(
+            check_deadline(now + 10, now, MIN_DEADLINE_BUFFER).unwrap_err(),
+            Error::DeadlineTooSoon { remaining: 10 }
+        ));
+    }
+
+    #[test]
+    fn test_resolve_deadline_policy() {
+        let now = U256::from(1_000_000);
+        assert_eq!(DeadlinePolicy::SecondsFromNow(60).resolve(now), now + 60);
+        assert_eq!(DeadlinePolicy::SecondsFromNow(60).resolve(U256::MAX), U256::MAX);
+        assert_eq!(DeadlinePolicy::Explicit(now).resolve(U256::zero()), now);
+        assert_eq!(DeadlinePolicy::None.resolve(now), U256::MAX);
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    #[cfg(feature = "addresses")]
+    async fn can_check_router_factory() {
+        let client = Arc::new(MAINNET.provider());
+        let (factory, router) = ProtocolType::UniswapV2.try_addresses(Chain::Mainnet);
+        let (factory, router) = (factory.unwrap(), router.unwrap());
+        let sushi = ProtocolType::Sushiswap.try_addresses(Chain::Mainnet).0.unwrap();
+
+        let dex = Dex::new_checked(client.clone(), factory, router, ProtocolType::UniswapV2).await;
+        assert!(dex.is_ok());
+        let res = Dex::new_checked(client.clone(), sushi, router, ProtocolType::UniswapV2).await;
+        assert!(matches!(res.unwrap_err(), Error::RouterFactoryMismatch { .. }));
+
+        let dex =
+            Dex::new_with_chain_checked(client.clone(), Chain::Mainnet, ProtocolType::UniswapV2);
+        assert!(dex.await.unwrap().is_some());
+        let dex = Dex::new_with_chain_checked(client, Chain::MoonbeamDev, ProtocolType::UniswapV2);
+        assert!(dex.await.unwrap().is_none());
+    }
+
     #[tokio::test]
     #[ignore = "async test"]
     #[cfg(feature = "addresses")]
@@ -505,4 +782,25 @@ mod tests {
             assert_approx_eq(amount_out_min, (amounts_out[1] * mult_bps) / BPS_U256, 1_000_000u64);
         }
     }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_quote_and_get_reserves() {
+        use crate::mock::{MockReserves, MOCK_FACTORY};
+
+        let mock = MockReserves::new(1);
+        let client = Arc::new(mock.clone().into_provider());
+        let dex =
+            Dex::new(client, MOCK_FACTORY, Address::repeat_byte(0xfe), ProtocolType::UniswapV2);
+        let factory = dex.protocol().as_v2().unwrap().factory();
+        let [a, b] = MockReserves::tokens();
+        mock.set_pair_reserves(factory, a, 1000, b, 2000);
+
+        assert_eq!(dex.reserves(b, a).await.unwrap(), (2000.into(), 1000.into()));
+        let amounts = dex.quote(100.into(), &[a, b]).await.unwrap();
+        assert_eq!(
+            amounts,
+            V2Library::get_amounts_out(factory, 100.into(), &[a, b]).await.unwrap()
+        );
+    }
 }
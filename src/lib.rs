@@ -1,6 +1,22 @@
 //! # uniswap-rs
 //!
 //! Unofficial Rust SDK library for Uniswap smart contracts.
+//!
+//! ## Read-only guarantee
+//!
+//! This crate never signs or broadcasts transactions. Functions that read on-chain data, like
+//! fetching reserves or quoting amounts, only use read-only JSON-RPC methods: `eth_call`,
+//! `eth_estimateGas`, `eth_chainId`, `eth_blockNumber`, `eth_getBlockByNumber`, `eth_getCode`,
+//! `eth_getLogs` and filters (`eth_newFilter`, `eth_newBlockFilter`, `eth_getFilterChanges`).
+//! Simulations are therefore never published, which makes the crate safe to use with private
+//! mempool (MEV-protected) providers.
+//!
+//! These functions are marked with the `dry_run_only` doc alias: searching the documentation for
+//! it lists all of them.
+//!
+//! Functions that build a transaction, like [`Dex::swap`], return a
+//! [`ContractCall`](ethers_contract::builders::ContractCall) which is only broadcast when the
+//! caller explicitly sends it.
 
 #![allow(clippy::too_many_arguments)]
 #![warn(missing_docs, unreachable_pub)]
@@ -19,7 +35,13 @@ pub mod universal_router;
 pub mod v2;
 pub mod v3;
 
-pub use common::{constants, errors, utils, Amount, CallExt, CallResult, Erc20};
+#[cfg(feature = "mock")]
+pub mod mock;
+
+pub use common::{
+    constants, errors, utils, Amount, CallExt, CallResult, DeadlinePolicy, Erc20, Pool,
+    RoundingMode, TokenAmount,
+};
 pub use constants::NATIVE_ADDRESS;
 pub use dex::Dex;
 pub use protocol::{pair_code_hashes, Protocol, ProtocolType};
@@ -28,7 +50,9 @@ pub use protocol::{pair_code_hashes, Protocol, ProtocolType};
 #[doc(hidden)]
 pub mod prelude {
     pub use super::{
-        common::{Amount, CallExt, CallResult, Erc20},
+        common::{
+            Amount, CallExt, CallResult, DeadlinePolicy, Erc20, Pool, RoundingMode, TokenAmount,
+        },
         constants::NATIVE_ADDRESS,
         dex::Dex,
         protocol::{Protocol, ProtocolType},
@@ -41,7 +65,9 @@ pub mod prelude {
     };
 
     #[cfg(feature = "addresses")]
-    pub use super::contracts::addresses::{address, contract, try_address, try_contract};
+    pub use super::contracts::addresses::{
+        address, contract, resolve_path, resolve_token, try_address, try_contract,
+    };
 
     // convenience re-export of all the imported ethers_* as one module.
     #[doc(hidden)]
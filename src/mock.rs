@@ -0,0 +1,359 @@
+//! A mock client for testing without a node.
+//!
+//! [`MockReserves`] is a [JsonRpcClient] that serves canned reserves for configured pairs, so that
+//! the V2 [`Library`](crate::v2::Library) reserve fetching and amount calculations can be tested
+//! deterministically offline.
+//!
+//! Only `eth_chainId`, `eth_blockNumber`, `eth_getCode`, which returns no code, and `eth_call`s
+//! of the pairs' `getReserves` and the factories' `getPair`, optionally aggregated with
+//! `Multicall.aggregate`, are supported.
+
+use crate::{
+    contracts::bindings::{i_uniswap_v2_factory::GetPairCall, i_uniswap_v2_pair::GetReservesCall},
+    v2::{Factory, Library},
+    ProtocolType,
+};
+use async_trait::async_trait;
+use ethers_contract::{EthCall, MULTICALL_ADDRESS};
+use ethers_core::{
+    abi::{self, ParamType, Token},
+    types::{Address, Bytes, U256},
+    utils::id,
+};
+use ethers_providers::{JsonRpcClient, Middleware, Provider, ProviderError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+use thiserror::Error as ThisError;
+
+/// A [Provider] that serves canned reserves. See [the module docs](self).
+pub type MockReserveMiddleware = Provider<MockReserves>;
+
+/// The block number returned by [`MockReserves`].
+pub const MOCK_BLOCK_NUMBER: u64 = 1;
+
+/// The address of the factory returned by [`MockReserves::factory`].
+pub const MOCK_FACTORY: Address = Address::repeat_byte(0xff);
+
+/// (factory, token0, token1) => pair address.
+type Pairs = Arc<RwLock<HashMap<(Address, Address, Address), Address>>>;
+
+/// Error thrown by [`MockReserves`].
+#[derive(Debug, ThisError)]
+pub enum MockError {
+    /// Thrown for every request while the client is [unavailable](MockReserves::set_unavailable).
+    #[error("Mock client is unavailable")]
+    Unavailable,
+
+    /// Thrown when the requested JSON-RPC method is not supported.
+    #[error("Unsupported method: {0}")]
+    UnsupportedMethod(String),
+
+    /// Thrown when an `eth_call` is not supported.
+    #[error("Unsupported call: {0}")]
+    UnsupportedCall(String),
+
+    /// Thrown when (de)serializing a request or response fails.
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+
+    /// Thrown when ABI decoding a call fails.
+    #[error(transparent)]
+    Abi(#[from] abi::Error),
+}
+
+impl From<MockError> for ProviderError {
+    fn from(value: MockError) -> Self {
+        Self::JsonRpcClientError(Box::new(value))
+    }
+}
+
+/// A [JsonRpcClient] that serves canned reserves for configured pairs.
+///
+/// Cloned instances share the same reserves.
+///
+/// # Example
+///
+/// ```
+/// # use uniswap_rs::{mock::MockReserves, prelude::{*, _ethers::*}};
+/// # async fn foo() -> Result<(), uniswap_rs::errors::Error> {
+/// let (weth, usdc) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+/// let mock = MockReserves::new(1);
+/// let factory = mock.factory();
+/// mock.set_pair_reserves(&factory, weth, U256::exp10(21), usdc, U256::exp10(12));
+///
+/// let amounts = V2Library::get_amounts_out(&factory, U256::exp10(18), &[weth, usdc]).await?;
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MockReserves {
+    /// The chain ID.
+    chain_id: u64,
+
+    /// Pair address => sorted reserves.
+    reserves: Arc<RwLock<HashMap<Address, (U256, U256)>>>,
+
+    /// The pairs served by the factories' `getPair`.
+    pairs: Pairs,
+
+    /// The requested JSON-RPC methods, in order.
+    methods: Arc<RwLock<Vec<String>>>,
+
+    /// Whether every request fails, like with an unreachable endpoint.
+    unavailable: Arc<AtomicBool>,
+}
+
+impl MockReserves {
+    /// Creates a new instance with the provided chain ID and no pairs.
+    ///
+    /// The chain must be supported by [Multicall][ethers_contract::Multicall].
+    pub fn new(chain_id: u64) -> Self {
+        Self { chain_id, ..Default::default() }
+    }
+
+    /// Wraps `self` in a [Provider].
+    pub fn into_provider(self) -> MockReserveMiddleware {
+        Provider::new(self)
+    }
+
+    /// Returns a Uniswap V2 [Factory] at [`MOCK_FACTORY`], whose client is a clone of `self`.
+    pub fn factory(&self) -> Factory<MockReserveMiddleware> {
+        Factory::new(Arc::new(self.clone().into_provider()), MOCK_FACTORY, ProtocolType::UniswapV2)
+    }
+
+    /// Returns `N` distinct token addresses: `0x1111..11`, `0x2222..22` and so on.
+    ///
+    /// # Panics
+    ///
+    /// If `N` is greater than 14.
+    pub fn tokens<const N: usize>() -> [Address; N] {
+        assert!(N < 15, "at most 14 mock tokens");
+        let mut i = 0;
+        [(); N].map(|_| {
+            i += 1;
+            Address::repeat_byte(0x11 * i)
+        })
+    }
+
+    /// Returns the JSON-RPC methods requested so far, in order, including the unsupported ones.
+    ///
+    /// This can be used to assert that a function makes only read-only requests.
+    pub fn requested_methods(&self) -> Vec<String> {
+        self.methods.read().unwrap().clone()
+    }
+
+    /// Sets whether every request fails with [`MockError::Unavailable`], like a transport error of
+    /// an unreachable endpoint.
+    pub fn set_unavailable(&self, unavailable: bool) -> &Self {
+        self.unavailable.store(unavailable, Ordering::Relaxed);
+        self
+    }
+
+    /// Sets the sorted reserves of the pair at `pair`.
+    pub fn set_reserves(
+        &self,
+        pair: Address,
+        reserve0: impl Into<U256>,
+        reserve1: impl Into<U256>,
+    ) -> &Self {
+        self.reserves.write().unwrap().insert(pair, (reserve0.into(), reserve1.into()));
+        self
+    }
+
+    /// Sets the reserves of the pair of `token_a` and `token_b` deployed by `factory`, and
+    /// registers the pair in the factory's `getPair`.
+    ///
+    /// The tokens and reserves do not need to be sorted.
+    pub fn set_pair_reserves<M: Middleware>(
+        &self,
+        factory: &Factory<M>,
+        token_a: Address,
+        reserve_a: impl Into<U256>,
+        token_b: Address,
+        reserve_b: impl Into<U256>,
+    ) -> &Self {
+        let pair = Library::pair_for(factory, token_a, token_b);
+        let (token0, token1) = Library::sort_tokens(token_a, token_b);
+        self.pairs.write().unwrap().insert((factory.address(), token0, token1), pair);
+        if token0 == token_a {
+            self.set_reserves(pair, reserve_a, reserve_b)
+        } else {
+            self.set_reserves(pair, reserve_b, reserve_a)
+        }
+    }
+
+    /// Returns the encoded result of a call, or empty bytes if `to` has no reserves, like a call to
+    /// an address without code.
+    fn call(&self, to: Address, data: &[u8]) -> Result<Bytes, MockError> {
+        if data.len() < 4 {
+            return Err(MockError::UnsupportedCall(format!("{to:?}: empty calldata")));
+        }
+        let (selector, args) = data.split_at(4);
+
+        if to == MULTICALL_ADDRESS && selector == aggregate_selector() {
+            let kind = ParamType::Tuple(vec![ParamType::Address, ParamType::Bytes]);
+            let calls = abi::decode(&[ParamType::Array(Box::new(kind))], args)?;
+            let calls = calls.into_iter().next().and_then(Token::into_array).unwrap_or_default();
+            let mut results = Vec::with_capacity(calls.len());
+            for call in calls {
+                let call = call.into_tuple().unwrap_or_default();
+                match (call.first().cloned(), call.get(1).cloned()) {
+                    (Some(Token::Address(to)), Some(Token::Bytes(data))) => {
+                        results.push(Token::Bytes(self.call(to, &data)?.to_vec()));
+                    }
+                    _ => return Err(MockError::UnsupportedCall("invalid aggregate call".into())),
+                }
+            }
+            let block = Token::Uint(MOCK_BLOCK_NUMBER.into());
+            return Ok(abi::encode(&[block, Token::Array(results)]).into());
+        }
+
+        if selector == GetReservesCall::selector() {
+            let reserves = self.reserves.read().unwrap().get(&to).copied();
+            return Ok(match reserves {
+                Some((reserve0, reserve1)) => abi::encode(&[
+                    Token::Uint(reserve0),
+                    Token::Uint(reserve1),
+                    Token::Uint(MOCK_BLOCK_NUMBER.into()),
+                ])
+                .into(),
+                None => Bytes::default(),
+            });
+        }
+
+        if selector == GetPairCall::selector() {
+            let tokens = abi::decode(&[ParamType::Address, ParamType::Address], args)?;
+            let tokens: Vec<_> = tokens.into_iter().filter_map(Token::into_address).collect();
+            let (token0, token1) = Library::sort_tokens(tokens[0], tokens[1]);
+            let pair = self.pairs.read().unwrap().get(&(to, token0, token1)).copied();
+            return Ok(abi::encode(&[Token::Address(pair.unwrap_or_default())]).into());
+        }
+
+        Err(MockError::UnsupportedCall(format!("{to:?}: {}", Bytes::from(data.to_vec()))))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for MockReserves {
+    type Error = MockError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, MockError>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        self.methods.write().unwrap().push(method.to_string());
+        if self.unavailable.load(Ordering::Relaxed) {
+            return Err(MockError::Unavailable);
+        }
+        let result = match method {
+            "eth_chainId" => serde_json::to_value(U256::from(self.chain_id))?,
+            "eth_blockNumber" => serde_json::to_value(U256::from(MOCK_BLOCK_NUMBER))?,
+            "eth_getCode" => serde_json::to_value(Bytes::default())?,
+            "eth_call" => {
+                let params = serde_json::to_value(params)?;
+                let tx = &params[0];
+                let to: Address = serde_json::from_value(tx["to"].clone())?;
+                let data: Bytes = match tx.get("data").or_else(|| tx.get("input")) {
+                    Some(data) => serde_json::from_value(data.clone())?,
+                    None => Bytes::default(),
+                };
+                serde_json::to_value(self.call(to, &data)?)?
+            }
+            method => return Err(MockError::UnsupportedMethod(method.to_string())),
+        };
+        Ok(serde_json::from_value::<R>(result)?)
+    }
+}
+
+fn aggregate_selector() -> [u8; 4] {
+    id("aggregate((address,bytes)[])")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        errors::Error,
+        v2::{Pair, PairResolution},
+    };
+
+    fn setup() -> (MockReserves, Factory<MockReserveMiddleware>, [Address; 3]) {
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let tokens = MockReserves::tokens();
+        let base = U256::exp10(18);
+        mock.set_pair_reserves(&factory, tokens[0], base * 1000, tokens[1], base * 2000)
+            .set_pair_reserves(&factory, tokens[2], base * 3000, tokens[1], base * 1000);
+        (mock, factory, tokens)
+    }
+
+    #[tokio::test]
+    async fn can_mock_reserves() {
+        let (mock, factory, [a, b, c]) = setup();
+        let base = U256::exp10(18);
+
+        let reserves = Library::get_reserves(&factory, b, a).await.unwrap();
+        assert_eq!(reserves, (base * 2000, base * 1000));
+
+        let reserves = Library::get_reserves_multi(&factory, &[a, b, c]).await.unwrap();
+        assert_eq!(reserves, vec![(base * 1000, base * 2000), (base * 1000, base * 3000)]);
+
+        let amounts = Library::get_amounts_out(&factory, base, &[a, b, c]).await.unwrap();
+        let amount_b = Library::get_amount_out(base, base * 1000, base * 2000).unwrap();
+        let amount_c = Library::get_amount_out(amount_b, base * 1000, base * 3000).unwrap();
+        assert_eq!(amounts, vec![base, amount_b, amount_c]);
+
+        // a non-existent pair
+        let res = Library::get_reserves_multi(&factory, &[a, c, b]).await;
+        assert!(matches!(res.unwrap_err(), Error::MulticallError(_)));
+
+        // reserves wider than 128 bits
+        mock.set_pair_reserves(&factory, a, U256::MAX, c, U256::one());
+        let reserves = Library::get_reserves(&factory, a, c).await.unwrap();
+        assert_eq!(reserves, (U256::MAX, U256::one()));
+    }
+
+    #[tokio::test]
+    async fn can_resolve_pairs_on_chain() {
+        let (mock, mut factory, [a, b, c]) = setup();
+        factory.set_pair_resolution(PairResolution::OnChain);
+        let base = U256::exp10(18);
+
+        let reserves = Library::get_reserves_multi(&factory, &[a, b, c]).await.unwrap();
+        assert_eq!(reserves, vec![(base * 1000, base * 2000), (base * 1000, base * 3000)]);
+        // 1 call to resolve the pairs, 1 call for their reserves
+        let calls = mock.requested_methods().iter().filter(|m| *m == "eth_call").count();
+        assert_eq!(calls, 2);
+
+        let pair = factory.pair_for(a, b).await.unwrap();
+        assert_eq!(pair.address(), Library::pair_for(&factory, a, b));
+        let res = factory.pair_for(a, c).await;
+        assert!(matches!(res.unwrap_err(), Error::PairNotFound));
+
+        let res = Pair::new_with_factory(&factory, a, b);
+        assert!(matches!(res.unwrap_err(), Error::OnChainPairResolution));
+    }
+
+    #[tokio::test]
+    async fn reads_are_read_only() {
+        let (mock, factory, [a, b, c]) = setup();
+        let base = U256::exp10(18);
+
+        Library::get_reserves(&factory, a, b).await.unwrap();
+        Library::get_amounts_out(&factory, base, &[a, b, c]).await.unwrap();
+        Library::get_amounts_in(&factory, base, &[a, b, c]).await.unwrap();
+
+        let methods = mock.requested_methods();
+        assert!(methods.contains(&"eth_call".to_string()));
+        assert!(methods
+            .iter()
+            .all(|method| ["eth_call", "eth_chainId"].contains(&method.as_str())));
+    }
+}
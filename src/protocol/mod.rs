@@ -152,9 +152,10 @@ impl<M: Middleware> Protocol<M> {
 
     /// The factory's `pair_for` method.
     #[inline(always)]
-    pub fn pair_for(&self, token_a: Address, token_b: Address) -> V2Pair<M> {
+    #[doc(alias = "dry_run_only")]
+    pub async fn pair_for(&self, token_a: Address, token_b: Address) -> Result<V2Pair<M>> {
         match self {
-            Self::V2(p) => p.pair_for(token_a, token_b),
+            Self::V2(p) => p.pair_for(token_a, token_b).await,
             Self::V3 => todo_v3(),
         }
     }
@@ -240,6 +241,28 @@ impl<M: Middleware> Protocol<M> {
             Self::V3 => todo_v3(),
         }
     }
+
+    /* ----------------------------------------- Library ---------------------------------------- */
+
+    /// The library's `get_reserves` method.
+    #[inline(always)]
+    #[doc(alias = "dry_run_only")]
+    pub async fn reserves(&self, token_a: Address, token_b: Address) -> Result<(U256, U256)> {
+        match self {
+            Self::V2(p) => p.reserves(token_a, token_b).await,
+            Self::V3 => todo_v3(),
+        }
+    }
+
+    /// The library's `get_amounts_out` method.
+    #[inline(always)]
+    #[doc(alias = "dry_run_only")]
+    pub async fn quote(&self, amount_in: U256, path: &[Address]) -> Result<Vec<U256>> {
+        match self {
+            Self::V2(p) => p.quote(amount_in, path).await,
+            Self::V3 => todo_v3(),
+        }
+    }
 }
 
 fn todo_v3() -> ! {
@@ -95,7 +95,7 @@ impl ProtocolType {
     }
 
     /// Returns (factory_name, router_name).
-    pub const fn contract_names(&self) -> (&str, &str) {
+    pub const fn contract_names(&self) -> (&'static str, &'static str) {
         use ProtocolType::*;
         match self {
             UniswapV2 => ("UniswapV2Factory", "UniswapV2Router02"),
@@ -142,6 +142,14 @@ impl ProtocolType {
         }
     }
 
+    /// Returns whether the protocol's router implements the standard Uniswap V2 native token
+    /// methods, like `WETH()` and `swapExactETHForTokens`.
+    ///
+    /// `Traderjoe` renames them after the native token, e.g. `swapExactAVAXForTokens`.
+    pub const fn uses_eth_router_methods(&self) -> bool {
+        !matches!(self, Self::Traderjoe)
+    }
+
     /// Returns whether the protocol is, or is a fork of, Uniswap V2.
     pub const fn is_v2(&self) -> bool {
         use ProtocolType::*;
@@ -191,6 +199,12 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_eth_router_methods() {
+        assert!(Sushiswap.uses_eth_router_methods());
+        assert!(!Traderjoe.uses_eth_router_methods());
+    }
+
     #[test]
     #[cfg(feature = "addresses")]
     fn test_addresses() {
@@ -0,0 +1,716 @@
+use super::{library::fee_multiplier, Factory, Library};
+use crate::{
+    constants::{BPS_U256, PRICE_SCALE},
+    errors::{Error, Result},
+};
+use ethers_contract::ContractError;
+use ethers_core::types::{Address, Sign, I256, U256, U512};
+use ethers_providers::Middleware;
+use futures_util::{stream, Stream, StreamExt};
+use std::{collections::HashMap, fmt, sync::Arc};
+
+/// A profitable arbitrage through a cycle of pairs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArbOpportunity {
+    /// The path of the cycle, which starts and ends with the same token.
+    pub path: Vec<Address>,
+
+    /// The optimal amount of the first token to swap through the path.
+    pub amount_in: U256,
+
+    /// The amount of the first token received at the end of the path.
+    pub amount_out: U256,
+
+    /// The addresses of the pairs swapped through, one for each hop of the path. Empty if the
+    /// opportunity was computed from reserves alone.
+    pub pairs: Vec<Address>,
+
+    /// The gross profit, `amount_out - amount_in`.
+    pub gross_profit: U256,
+
+    /// The net profit after paying the [costs](ArbCosts) of executing the arbitrage, which is
+    /// negative if they exceed the gross profit.
+    pub net_profit: I256,
+}
+
+impl ArbOpportunity {
+    /// Recomputes the [net profit](Self::net_profit) of the arbitrage with `costs`.
+    ///
+    /// See [`Library::arb_net_profit`].
+    pub fn with_costs(mut self, costs: ArbCosts) -> Self {
+        let flash_fee = costs.flash_fee(self.amount_in);
+        self.net_profit = Library::arb_net_profit(self.gross_profit, costs.gas_cost, flash_fee);
+        self
+    }
+}
+
+/// The costs of executing an arbitrage, in units of the first token of its path.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ArbCosts {
+    /// The gas cost of the transaction.
+    pub gas_cost: U256,
+
+    /// The fee of flash borrowing the input amount, in basis points, e.g. `30` for a Uniswap V2
+    /// flash swap. Zero if the input amount is not borrowed.
+    pub flash_fee_bps: u32,
+}
+
+impl ArbCosts {
+    /// Returns the flash fee of borrowing `amount_in`, rounded up.
+    pub fn flash_fee(&self, amount_in: U256) -> U256 {
+        let fee = amount_in.full_mul(self.flash_fee_bps.into()) + U512::from(BPS_U256 - 1);
+        U256::try_from(fee / U512::from(BPS_U256)).unwrap_or(U256::MAX)
+    }
+}
+
+/// Watches a set of triangular cycles of a factory's pairs, and of two-pool cycles between the
+/// factory and other factories, and emits their arbitrage opportunities as new blocks arrive.
+pub struct ArbWatcher<M> {
+    /// The factory of the pairs.
+    factory: Factory<M>,
+
+    /// The client used to watch for new blocks.
+    client: Arc<M>,
+
+    /// The watched `a -> b -> c -> a` cycles.
+    cycles: Vec<[Address; 3]>,
+
+    /// The watched `a -> b` cycles between `factory` and the venue at the index, in both
+    /// directions.
+    two_pool_cycles: Vec<(Address, Address, usize)>,
+
+    /// The other factories of the two-pool cycles, with the swap fee of their pairs.
+    venues: Vec<(Factory<M>, u32)>,
+
+    /// The minimum net profit of the emitted opportunities.
+    min_profit: U256,
+
+    /// The costs of executing the opportunities.
+    costs: ArbCosts,
+
+    /// The swap fee of the factory's pairs, in basis points.
+    fee_bps: u32,
+}
+
+impl<M> Clone for ArbWatcher<M> {
+    fn clone(&self) -> Self {
+        Self {
+            factory: self.factory.clone(),
+            client: self.client.clone(),
+            cycles: self.cycles.clone(),
+            two_pool_cycles: self.two_pool_cycles.clone(),
+            venues: self.venues.clone(),
+            min_profit: self.min_profit,
+            costs: self.costs,
+            fee_bps: self.fee_bps,
+        }
+    }
+}
+
+impl<M> fmt::Debug for ArbWatcher<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArbWatcher")
+            .field("factory", &self.factory)
+            .field("cycles", &self.cycles)
+            .field("two_pool_cycles", &self.two_pool_cycles)
+            .field("venues", &self.venues)
+            .field("min_profit", &self.min_profit)
+            .field("costs", &self.costs)
+            .field("fee_bps", &self.fee_bps)
+            .finish()
+    }
+}
+
+impl<M: Middleware> ArbWatcher<M> {
+    /// Creates a new watcher that emits the opportunities of `factory`'s pairs with a net profit
+    /// greater than or equal to `min_profit`.
+    ///
+    /// The pairs' swap fee defaults to [`Library::FEE_BPS`], see
+    /// [`set_fee_bps`](Self::set_fee_bps), and the costs default to zero, see
+    /// [`set_costs`](Self::set_costs).
+    pub fn new(factory: Factory<M>, min_profit: U256) -> Self {
+        let client = factory.client();
+        Self {
+            factory,
+            client,
+            cycles: Vec::new(),
+            two_pool_cycles: Vec::new(),
+            venues: Vec::new(),
+            min_profit,
+            costs: ArbCosts::default(),
+            fee_bps: Library::FEE_BPS,
+        }
+    }
+
+    /// Sets the costs of executing the opportunities, which are subtracted from their gross
+    /// profit before applying the profit threshold.
+    pub fn set_costs(&mut self, costs: ArbCosts) -> &mut Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Sets the swap fee of the factory's pairs, in basis points, e.g. `25` for Pancakeswap.
+    pub fn set_fee_bps(&mut self, fee_bps: u32) -> &mut Self {
+        self.fee_bps = fee_bps;
+        self
+    }
+
+    /// Adds the `a -> b -> c -> a` cycle to the watched cycles.
+    pub fn add_cycle(&mut self, a: Address, b: Address, c: Address) -> Result<&mut Self> {
+        if a == b || b == c || c == a {
+            return Err(Error::IdenticalAddresses);
+        }
+        self.cycles.push([a, b, c]);
+        Ok(self)
+    }
+
+    /// Adds the cycle between the `a`-`b` pairs of the watcher's factory and of `other`, whose
+    /// pairs have a swap fee of `other_fee_bps`, to the watched cycles. Both directions are
+    /// checked: buying `b` on one factory and selling it on the other.
+    ///
+    /// `other` must be on the same chain as the watcher's factory.
+    pub fn add_two_pool_cycle(
+        &mut self,
+        a: Address,
+        b: Address,
+        other: Factory<M>,
+        other_fee_bps: u32,
+    ) -> Result<&mut Self> {
+        if a == b {
+            return Err(Error::IdenticalAddresses);
+        }
+        self.venues.push((other, other_fee_bps));
+        self.two_pool_cycles.push((a, b, self.venues.len()));
+        Ok(self)
+    }
+
+    /// Returns the watched triangular cycles.
+    pub fn cycles(&self) -> &[[Address; 3]] {
+        &self.cycles
+    }
+
+    /// Fetches the reserves of all the watched cycles and returns the opportunities above the
+    /// profit threshold, or the error of each cycle whose opportunity could not be computed.
+    ///
+    /// The reserves of all the pairs are fetched with only 1 call to the client by using
+    /// [Multicall](ethers_contract::Multicall), plus the calls needed to resolve the pairs'
+    /// addresses with [`Library::resolve_pairs`]. If they cannot be fetched, only that error is
+    /// returned.
+    #[doc(alias = "dry_run_only")]
+    pub async fn check(&self) -> Vec<Result<ArbOpportunity>> {
+        self.check_cycles().await.unwrap_or_else(|e| vec![Err(e)])
+    }
+
+    async fn check_cycles(&self) -> Result<Vec<Result<ArbOpportunity>>> {
+        // every cycle as its path and the venue of each hop, where 0 is the watcher's factory
+        let triangular = self.cycles.iter().map(|&[a, b, c]| (vec![a, b, c, a], vec![0; 3]));
+        let two_pool = self.two_pool_cycles.iter().flat_map(|&(a, b, venue)| {
+            [(vec![a, b, a], vec![0, venue]), (vec![a, b, a], vec![venue, 0])]
+        });
+        let cycles: Vec<_> = triangular.chain(two_pool).collect();
+
+        // the unique pairs of all the cycles, as `(venue, token0, token1)`
+        let mut pairs = Vec::new();
+        let mut indices = HashMap::new();
+        for (path, venues) in &cycles {
+            for (slice, &venue) in path.windows(2).zip(venues) {
+                let (token0, token1) = Library::sort_tokens(slice[0], slice[1]);
+                indices.entry((venue, token0, token1)).or_insert_with(|| {
+                    pairs.push((venue, token0, token1));
+                    pairs.len() - 1
+                });
+            }
+        }
+
+        let mut addresses = vec![Address::zero(); pairs.len()];
+        for venue in 0..=self.venues.len() {
+            let (indices, tokens): (Vec<_>, Vec<_>) = pairs
+                .iter()
+                .enumerate()
+                .filter(|(_, pair)| pair.0 == venue)
+                .map(|(i, &(_, token0, token1))| (i, (token0, token1)))
+                .unzip();
+            if !tokens.is_empty() {
+                let resolved = Library::resolve_pairs(self.venue(venue).0, &tokens).await?;
+                for (i, address) in indices.into_iter().zip(resolved) {
+                    addresses[i] = address;
+                }
+            }
+        }
+        let tokens: Vec<_> = pairs.iter().map(|&(_, token0, token1)| (token0, token1)).collect();
+        let reserves = Library::fetch_reserves(&self.factory, &tokens, &addresses).await?;
+
+        let min_profit =
+            I256::checked_from_sign_and_abs(Sign::Positive, self.min_profit).unwrap_or(I256::MAX);
+        let arbs = cycles.into_iter().filter_map(|(path, venues)| {
+            let mut hops = Vec::with_capacity(venues.len());
+            let mut fees = Vec::with_capacity(venues.len());
+            let mut pairs = Vec::with_capacity(venues.len());
+            for (slice, &venue) in path.windows(2).zip(&venues) {
+                let (token0, token1) = Library::sort_tokens(slice[0], slice[1]);
+                let i = indices[&(venue, token0, token1)];
+                let (reserve0, reserve1) = reserves[i];
+                hops.push(if slice[0] == token0 {
+                    (reserve0, reserve1)
+                } else {
+                    (reserve1, reserve0)
+                });
+                fees.push(self.venue(venue).1);
+                pairs.push(addresses[i]);
+            }
+            let arb = Library::arb_from_reserves_with_fees(path, &hops, &fees).transpose()?;
+            let arb = arb.map(|arb| ArbOpportunity { pairs, ..arb.with_costs(self.costs) });
+            match arb {
+                Ok(arb) if arb.net_profit < min_profit => None,
+                arb => Some(arb),
+            }
+        });
+        Ok(arbs.collect())
+    }
+
+    /// Returns the factory and swap fee of a venue, where 0 is the watcher's factory.
+    fn venue(&self, venue: usize) -> (&Factory<M>, u32) {
+        match venue.checked_sub(1) {
+            Some(i) => (&self.venues[i].0, self.venues[i].1),
+            None => (&self.factory, self.fee_bps),
+        }
+    }
+
+    /// Returns a stream that [checks](Self::check) the watched cycles on every new block.
+    #[doc(alias = "dry_run_only")]
+    pub async fn stream(&self) -> Result<impl Stream<Item = Result<ArbOpportunity>> + '_> {
+        let blocks =
+            self.client.watch_blocks().await.map_err(ContractError::<M>::MiddlewareError)?;
+        Ok(blocks.then(move |_| self.check()).flat_map(stream::iter))
+    }
+}
+
+impl Library {
+    /// Fetches the reserves of the `a -> b -> c -> a` cycle, making only 1 call to the client plus
+    /// 1 call to resolve the pairs' addresses with
+    /// [`PairResolution::OnChain`](super::PairResolution::OnChain), and returns the optimal
+    /// arbitrage through it if there is one, given the pairs' swap fee in basis points, with its
+    /// net profit after `costs`.
+    #[doc(alias = "dry_run_only")]
+    pub async fn triangular_arb<M: Middleware>(
+        factory: &Factory<M>,
+        a: Address,
+        b: Address,
+        c: Address,
+        fee_bps: u32,
+        costs: ArbCosts,
+    ) -> Result<Option<ArbOpportunity>> {
+        if a == b || b == c || c == a {
+            return Err(Error::IdenticalAddresses);
+        }
+        let path = vec![a, b, c, a];
+        let pairs: Vec<_> = path.windows(2).map(|slice| (slice[0], slice[1])).collect();
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+        let reserves = Self::fetch_reserves(factory, &pairs, &addresses).await?;
+        let arb = Self::arb_from_reserves(path, &reserves, fee_bps)?;
+        Ok(arb.map(|arb| ArbOpportunity { pairs: addresses, ..arb.with_costs(costs) }))
+    }
+
+    /// Returns the net profit of an arbitrage, `gross_profit - gas_cost - flash_fee`, all in the
+    /// same units, which is negative if the costs exceed the gross profit.
+    ///
+    /// Saturates at the bounds of [I256], treating amounts above [`I256::MAX`] as the maximum.
+    pub fn arb_net_profit(gross_profit: U256, gas_cost: U256, flash_fee: U256) -> I256 {
+        let signed =
+            |amount| I256::checked_from_sign_and_abs(Sign::Positive, amount).unwrap_or(I256::MAX);
+        signed(gross_profit).saturating_sub(signed(gas_cost)).saturating_sub(signed(flash_fee))
+    }
+
+    /// Returns the output amount of swapping `amount_in` on one venue and then swapping the
+    /// proceeds on another, without making any external calls.
+    ///
+    /// Each pool is given as `(reserve_in, reserve_out, fee_bps)`, sorted for the direction it is
+    /// swapped in: `pool_b`'s input token is `pool_a`'s output token. For a two-venue arbitrage of
+    /// the same pair, `pool_b` is sorted the opposite way of `pool_a`, and the trade is profitable
+    /// if the result is greater than `amount_in`.
+    pub fn sequential_two_dex(
+        amount_in: U256,
+        pool_a: (U256, U256, u32),
+        pool_b: (U256, U256, u32),
+    ) -> Result<U256> {
+        let (reserve_in, reserve_out, fee_bps) = pool_a;
+        let amount = Self::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+        let (reserve_in, reserve_out, fee_bps) = pool_b;
+        Self::get_amount_out_with_fee(amount, reserve_in, reserve_out, fee_bps)
+    }
+
+    /// Returns the optimal arbitrage through a cycle of pairs, given the sorted reserves
+    /// `(reserve_in, reserve_out)` of each hop of `path`, without making any external calls.
+    ///
+    /// There is an opportunity only if the product of the fee-adjusted exchange rates of the
+    /// cycle is greater than 1. The optimal input is the one that maximizes the profit of the
+    /// pairs collapsed into a single virtual pair.
+    ///
+    /// The net profit of the returned opportunity does not include any costs, see
+    /// [`ArbOpportunity::with_costs`].
+    pub fn arb_from_reserves(
+        path: Vec<Address>,
+        reserves: &[(U256, U256)],
+        fee_bps: u32,
+    ) -> Result<Option<ArbOpportunity>> {
+        Self::arb_from_reserves_with_fees(path, reserves, &vec![fee_bps; reserves.len()])
+    }
+
+    /// Same as [`arb_from_reserves`](Self::arb_from_reserves), but with the swap fee of each hop,
+    /// for cycles through pairs of different protocols, like a two-pool cycle `[a, b, a]`.
+    pub fn arb_from_reserves_with_fees(
+        path: Vec<Address>,
+        reserves: &[(U256, U256)],
+        fees_bps: &[u32],
+    ) -> Result<Option<ArbOpportunity>> {
+        if path.len() < 3
+            || path.first() != path.last()
+            || reserves.len() != path.len() - 1
+            || fees_bps.len() != reserves.len()
+        {
+            return Err(Error::InvalidPath);
+        }
+        let amount_in = match Self::optimal_cycle_input(reserves, fees_bps)? {
+            Some(amount_in) => amount_in,
+            None => return Ok(None),
+        };
+
+        let mut amount_out = amount_in;
+        for (&(reserve_in, reserve_out), &fee_bps) in reserves.iter().zip(fees_bps) {
+            amount_out =
+                Self::get_amount_out_with_fee(amount_out, reserve_in, reserve_out, fee_bps)?;
+        }
+        if amount_out <= amount_in {
+            return Ok(None);
+        }
+
+        let gross_profit = amount_out - amount_in;
+        let net_profit = Library::arb_net_profit(gross_profit, U256::zero(), U256::zero());
+        let pairs = Vec::new();
+        Ok(Some(ArbOpportunity { path, amount_in, amount_out, pairs, gross_profit, net_profit }))
+    }
+
+    /// Returns the price both pools of the same pair converge to after the profit-maximizing
+    /// arbitrage between them, scaled by [`PRICE_SCALE`], without making any external calls.
+    ///
+    /// The reserves of both pools are given as `(reserve0, reserve1)` and the price is the one of
+    /// token0 in terms of token1. Because of the swap fee the spot prices of the pools do not meet
+    /// exactly: the returned price is the geometric mean of the two spot prices after the trade,
+    /// which is the fee-adjusted price of the last unit bought from one pool and sold to the
+    /// other. If there is no profitable arbitrage, the mean of the current prices is returned.
+    pub fn arb_equilibrium_price(
+        reserves_a: (U256, U256),
+        reserves_b: (U256, U256),
+        fee_bps: u32,
+    ) -> Result<U256> {
+        let ((mut r0_a, mut r1_a), (mut r0_b, mut r1_b)) = (reserves_a, reserves_b);
+        if [r0_a, r1_a, r0_b, r1_b].iter().any(U256::is_zero) {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        // buy token0 with token1 where it is cheaper and sell it for token1 on the other pool
+        let a_is_cheaper = r1_a.full_mul(r0_b) < r1_b.full_mul(r0_a);
+        let ((cheap_0, cheap_1), (dear_0, dear_1)) = if a_is_cheaper {
+            ((&mut r0_a, &mut r1_a), (&mut r0_b, &mut r1_b))
+        } else {
+            ((&mut r0_b, &mut r1_b), (&mut r0_a, &mut r1_a))
+        };
+        let reserves = [(*cheap_1, *cheap_0), (*dear_0, *dear_1)];
+        if let Some(amount_in) = Self::optimal_cycle_input(&reserves, &[fee_bps; 2])? {
+            let amount = Self::get_amount_out_with_fee(amount_in, *cheap_1, *cheap_0, fee_bps)?;
+            let amount_out = Self::get_amount_out_with_fee(amount, *dear_0, *dear_1, fee_bps)?;
+            *cheap_1 = cheap_1.checked_add(amount_in).ok_or(Error::Overflow)?;
+            *cheap_0 -= amount;
+            *dear_0 = dear_0.checked_add(amount).ok_or(Error::Overflow)?;
+            *dear_1 -= amount_out;
+        }
+
+        // sqrt(r1_a / r0_a * r1_b / r0_b) * PRICE_SCALE
+        let numerator = r1_a
+            .full_mul(r1_b)
+            .checked_mul(U512::from(PRICE_SCALE * PRICE_SCALE))
+            .ok_or(Error::Overflow)?;
+        let price = (numerator / r0_a.full_mul(r0_b)).integer_sqrt();
+        U256::try_from(price).map_err(|_| Error::Overflow)
+    }
+
+    /// Returns the input amount that maximizes the profit of swapping through the sorted
+    /// `reserves` of a cycle, given the swap fee of each hop, or `None` if the cycle is not
+    /// profitable.
+    fn optimal_cycle_input(reserves: &[(U256, U256)], fees_bps: &[u32]) -> Result<Option<U256>> {
+        if reserves.iter().any(|(a, b)| a.is_zero() || b.is_zero()) {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        let bps = U512::from(BPS_U256);
+
+        // collapse all the pairs into a single virtual pair, with the fee of the first hop
+        let (mut e0, mut e1) = (U512::from(reserves[0].0), U512::from(reserves[0].1));
+        for (&(reserve_in, reserve_out), &fee_bps) in reserves[1..].iter().zip(&fees_bps[1..]) {
+            let fee = U512::from(fee_multiplier(fee_bps)?);
+            let (reserve_in, reserve_out) = (U512::from(reserve_in), U512::from(reserve_out));
+            let denominator = reserve_in * bps + e1 * fee;
+            e0 = e0 * reserve_in * bps / denominator;
+            e1 = e1 * fee * reserve_out / denominator;
+        }
+
+        // the cycle is profitable only if `amount_out > amount_in` for an infinitesimal amount
+        let fee = U512::from(fee_multiplier(fees_bps[0])?);
+        if e1 * fee <= e0 * bps {
+            return Ok(None);
+        }
+        let amount_in = ((e0 * e1 * fee * bps).integer_sqrt() - e0 * bps) / fee;
+        let amount_in = U256::try_from(amount_in).map_err(|_| Error::InsufficientLiquidity)?;
+        Ok(Some(amount_in).filter(|amount_in| !amount_in.is_zero()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path() -> Vec<Address> {
+        let (a, b, c) =
+            (Address::repeat_byte(0x11), Address::repeat_byte(0x22), Address::repeat_byte(0x33));
+        vec![a, b, c, a]
+    }
+
+    fn profit_of(amount_in: U256, reserves: &[(U256, U256)]) -> Option<U256> {
+        let mut amount = amount_in;
+        for &(reserve_in, reserve_out) in reserves {
+            amount = Library::get_amount_out(amount, reserve_in, reserve_out).unwrap();
+        }
+        amount.checked_sub(amount_in)
+    }
+
+    #[test]
+    fn can_find_arb() {
+        let base = U256::exp10(18);
+        // 1 A = 2 B, 1 B = 3 C, 1 C = 0.2 A => product = 1.2
+        let reserves = [
+            (U256::from(1000) * base, U256::from(2000) * base),
+            (U256::from(2000) * base, U256::from(6000) * base),
+            (U256::from(5000) * base, U256::from(1000) * base),
+        ];
+
+        let arb = Library::arb_from_reserves(path(), &reserves, Library::FEE_BPS).unwrap().unwrap();
+        assert_eq!(arb.path, path());
+        assert_eq!(arb.gross_profit, arb.amount_out - arb.amount_in);
+        assert_eq!(profit_of(arb.amount_in, &reserves), Some(arb.gross_profit));
+
+        // the amount is optimal
+        let delta = arb.amount_in / 100;
+        assert!(profit_of(arb.amount_in - delta, &reserves).unwrap() <= arb.gross_profit);
+        assert!(profit_of(arb.amount_in + delta, &reserves).unwrap() <= arb.gross_profit);
+    }
+
+    #[test]
+    fn no_arb_below_fees() {
+        let base = U256::exp10(18);
+        // product = 1.005, less than the fees
+        let reserves = [
+            (U256::from(1000) * base, U256::from(2000) * base),
+            (U256::from(2000) * base, U256::from(6000) * base),
+            (U256::from(6000) * base, U256::from(1005) * base),
+        ];
+        assert_eq!(Library::arb_from_reserves(path(), &reserves, Library::FEE_BPS).unwrap(), None);
+
+        // but there is without fees
+        assert!(Library::arb_from_reserves(path(), &reserves, 0).unwrap().is_some());
+    }
+
+    #[test]
+    fn can_get_arb_net_profit() {
+        assert_eq!(Library::arb_net_profit(100.into(), 30.into(), 20.into()), I256::from(50));
+        assert_eq!(Library::arb_net_profit(100.into(), 90.into(), 20.into()), I256::from(-10));
+        assert_eq!(Library::arb_net_profit(U256::zero(), U256::MAX, U256::MAX), I256::MIN);
+
+        let arb = ArbOpportunity {
+            path: path(),
+            amount_in: 1000.into(),
+            amount_out: 1100.into(),
+            pairs: vec![],
+            gross_profit: 100.into(),
+            net_profit: 100.into(),
+        };
+        let costs = ArbCosts { gas_cost: 30.into(), flash_fee_bps: 30 };
+        assert_eq!(costs.flash_fee(1000.into()), 3.into());
+        assert_eq!(costs.flash_fee(1001.into()), 4.into());
+        assert_eq!(arb.with_costs(costs).net_profit, I256::from(67));
+    }
+
+    #[test]
+    fn can_swap_through_two_dexes() {
+        let base = U256::exp10(18);
+        let amount_in = base;
+        // 1 A = 2 B on the first venue, 1 A = 1.8 B on the second one
+        let pool_a = (U256::from(1000) * base, U256::from(2000) * base, 30);
+        let pool_b = (U256::from(1800) * base, U256::from(1000) * base, 25);
+
+        let amount_out = Library::sequential_two_dex(amount_in, pool_a, pool_b).unwrap();
+        let amount = Library::get_amount_out_with_fee(amount_in, pool_a.0, pool_a.1, 30).unwrap();
+        let expected = Library::get_amount_out_with_fee(amount, pool_b.0, pool_b.1, 25).unwrap();
+        assert_eq!(amount_out, expected);
+        assert!(amount_out > amount_in);
+
+        // the other way around is not profitable
+        let reversed = |(reserve_in, reserve_out, fee)| (reserve_out, reserve_in, fee);
+        let amount_out =
+            Library::sequential_two_dex(amount_in, reversed(pool_b), reversed(pool_a)).unwrap();
+        assert!(amount_out < amount_in);
+
+        // the optimal amount through the two venues
+        let (a, b) = (path()[0], path()[1]);
+        let reserves = [(pool_a.0, pool_a.1), (pool_b.0, pool_b.1)];
+        let arb = Library::arb_from_reserves_with_fees(vec![a, b, a], &reserves, &[30, 25]);
+        let arb = arb.unwrap().unwrap();
+        let output = |amount| Library::sequential_two_dex(amount, pool_a, pool_b).unwrap();
+        assert_eq!(arb.amount_out, output(arb.amount_in));
+        let delta = arb.amount_in / 100;
+        assert!(output(arb.amount_in - delta) - (arb.amount_in - delta) <= arb.gross_profit);
+        assert!(output(arb.amount_in + delta) - (arb.amount_in + delta) <= arb.gross_profit);
+        let res = Library::arb_from_reserves_with_fees(vec![a, b, a], &reserves, &[30]);
+        assert!(matches!(res.unwrap_err(), Error::InvalidPath));
+
+        let empty = (U256::zero(), U256::zero(), 30);
+        let res = Library::sequential_two_dex(amount_in, pool_a, empty);
+        assert!(matches!(res.unwrap_err(), Error::InsufficientLiquidity));
+    }
+
+    #[test]
+    fn can_get_arb_equilibrium_price() {
+        let base = U256::exp10(18);
+        let price = |(reserve0, reserve1): (U256, U256)| reserve1 * PRICE_SCALE / reserve0;
+        // 1 A = 2 B on the first pool, 1 A = 2.5 B on the second one
+        let pool_a = (U256::from(1000) * base, U256::from(2000) * base);
+        let pool_b = (U256::from(2000) * base, U256::from(5000) * base);
+
+        let equilibrium = Library::arb_equilibrium_price(pool_a, pool_b, Library::FEE_BPS).unwrap();
+        assert!(price(pool_a) < equilibrium && equilibrium < price(pool_b));
+        assert_eq!(equilibrium, Library::arb_equilibrium_price(pool_b, pool_a, 30).unwrap());
+
+        // without fees, both pools end up at ((sqrt(k_a) + sqrt(k_b)) / (x_a + x_b))^2
+        let expected = (2000f64.sqrt() + 10000f64.sqrt()) / 3000.0 * 1000f64.sqrt();
+        let expected = expected * expected * 1e18;
+        let equilibrium = Library::arb_equilibrium_price(pool_a, pool_b, 0).unwrap();
+        assert!((equilibrium.as_u128() as f64 / expected - 1.0).abs() < 1e-9);
+
+        // no arbitrage within the fees, the pools do not move
+        let pool_b = (U256::from(1000) * base, U256::from(2004) * base);
+        let mean = (price(pool_a) * price(pool_b)).integer_sqrt();
+        assert_eq!(Library::arb_equilibrium_price(pool_a, pool_b, 30).unwrap(), mean);
+
+        let empty = (U256::zero(), U256::zero());
+        let res = Library::arb_equilibrium_price(pool_a, empty, 30);
+        assert!(matches!(res.unwrap_err(), Error::InsufficientLiquidity));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_watch_arbs() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b, c, d] = MockReserves::tokens();
+        let base = 10u128.pow(18);
+        // a -> b -> c -> a is profitable, a -> b -> d -> a is not
+        mock.set_pair_reserves(&factory, a, 1000 * base, b, 2000 * base)
+            .set_pair_reserves(&factory, b, 2000 * base, c, 6000 * base)
+            .set_pair_reserves(&factory, c, 5000 * base, a, 1000 * base)
+            .set_pair_reserves(&factory, b, 1000 * base, d, 1000 * base)
+            .set_pair_reserves(&factory, d, 2000 * base, a, 1000 * base);
+
+        let mut watcher = ArbWatcher::new(factory, U256::zero());
+        watcher.add_cycle(a, b, c).unwrap().add_cycle(a, b, d).unwrap();
+        assert!(matches!(watcher.add_cycle(a, a, c).unwrap_err(), Error::IdenticalAddresses));
+
+        let calls = |mock: &MockReserves| {
+            mock.requested_methods().iter().filter(|method| *method == "eth_call").count()
+        };
+        let arbs = watcher.check().await;
+        assert_eq!(arbs.len(), 1);
+        let arb = arbs[0].as_ref().unwrap();
+        assert_eq!(arb.path, vec![a, b, c, a]);
+        let pair = |a, b| Library::pair_for(&watcher.factory, a, b);
+        assert_eq!(arb.pairs, vec![pair(a, b), pair(b, c), pair(c, a)]);
+        // one multicall for the 5 pairs
+        assert_eq!(calls(&mock), 1);
+
+        // without fees the cycle is more profitable
+        let profit = arb.gross_profit;
+        assert_eq!(arb.net_profit, I256::from_raw(profit));
+        watcher.set_fee_bps(0);
+        let arbs = watcher.check().await;
+        assert!(arbs[0].as_ref().unwrap().gross_profit > profit);
+        watcher.set_fee_bps(Library::FEE_BPS);
+
+        watcher.min_profit = profit + 1;
+        assert!(watcher.check().await.is_empty());
+
+        // the threshold applies to the net profit
+        watcher.min_profit = U256::zero();
+        watcher.set_costs(ArbCosts { gas_cost: profit, flash_fee_bps: 0 });
+        let arbs = watcher.check().await;
+        assert_eq!(arbs[0].as_ref().unwrap().net_profit, I256::zero());
+        watcher.set_costs(ArbCosts { gas_cost: profit + 1, flash_fee_bps: 0 });
+        assert!(watcher.check().await.is_empty());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_watch_two_pool_arbs() {
+        use crate::{mock::MockReserves, ProtocolType};
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let other =
+            Factory::new(factory.client(), Address::repeat_byte(0xee), ProtocolType::Pancakeswap);
+        let [a, b, c] = MockReserves::tokens();
+        let base = 10u128.pow(18);
+        // b is cheaper on the other factory, b-c has the same price on both
+        mock.set_pair_reserves(&factory, a, 1000 * base, b, 2000 * base)
+            .set_pair_reserves(&other, a, 1000 * base, b, 2200 * base)
+            .set_pair_reserves(&factory, b, 1000 * base, c, 1000 * base)
+            .set_pair_reserves(&other, b, 1000 * base, c, 1000 * base);
+
+        let mut watcher = ArbWatcher::new(factory.clone(), U256::zero());
+        watcher.add_two_pool_cycle(a, b, other.clone(), 25).unwrap();
+        watcher.add_two_pool_cycle(b, c, other.clone(), 25).unwrap();
+        assert!(watcher.add_two_pool_cycle(a, a, other.clone(), 25).is_err());
+
+        let arbs = watcher.check().await;
+        assert_eq!(arbs.len(), 1);
+        let arb = arbs[0].as_ref().unwrap();
+        assert_eq!(arb.path, vec![a, b, a]);
+        // buy b on the other factory and sell it on the watcher's
+        let (pair, other_pair) =
+            (Library::pair_for(&factory, a, b), Library::pair_for(&other, a, b));
+        assert_eq!(arb.pairs, vec![other_pair, pair]);
+
+        let pool = |reserve_in: u128, reserve_out: u128, fee| {
+            (U256::from(reserve_in), U256::from(reserve_out), fee)
+        };
+        let amount_out = Library::sequential_two_dex(
+            arb.amount_in,
+            pool(1000 * base, 2200 * base, 25),
+            pool(2000 * base, 1000 * base, 30),
+        )
+        .unwrap();
+        assert_eq!(arb.amount_out, amount_out);
+        assert_eq!(mock.requested_methods().iter().filter(|m| *m == "eth_call").count(), 1);
+    }
+
+    #[test]
+    fn arb_invalid_input() {
+        let reserves = [(U256::one(), U256::one()); 3];
+        let res = Library::arb_from_reserves(path()[..3].to_vec(), &reserves, 30);
+        assert!(matches!(res.unwrap_err(), Error::InvalidPath));
+
+        let reserves =
+            [(U256::one(), U256::one()), (U256::one(), U256::one()), (U256::zero(), U256::one())];
+        let res = Library::arb_from_reserves(path(), &reserves, 30);
+        assert!(matches!(res.unwrap_err(), Error::InsufficientLiquidity));
+    }
+}
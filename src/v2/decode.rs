@@ -0,0 +1,155 @@
+use crate::{
+    contracts::bindings::i_uniswap_v2_router_02::IUniswapV2Router02Calls,
+    errors::{Error, Result},
+};
+use ethers_core::{
+    abi::AbiDecode,
+    types::{Address, Bytes, U256},
+};
+
+/// A decoded router swap call.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecodedSwap {
+    /// The name of the router function.
+    pub function: &'static str,
+
+    /// Whether the input amount is exact, otherwise the output amount is exact.
+    pub exact_input: bool,
+
+    /// The exact or maximum input amount. None if the input is the transaction's value.
+    pub amount_in: Option<U256>,
+
+    /// The exact or minimum output amount.
+    pub amount_out: U256,
+
+    /// The path of the swap.
+    pub path: Vec<Address>,
+
+    /// The recipient of the output.
+    pub to: Address,
+
+    /// The deadline of the swap, as a UNIX timestamp.
+    pub deadline: U256,
+
+    /// Whether the function supports fee-on-transfer tokens.
+    pub supporting_fee_on_transfer: bool,
+}
+
+/// Decodes the input of a transaction to a V2 router's swap function.
+///
+/// Returns [`Error::UnknownSwapCall`] if `input` is not a call to any of the router's swap
+/// functions.
+pub fn decode_router_call(input: &Bytes) -> Result<DecodedSwap> {
+    use IUniswapV2Router02Calls::*;
+
+    macro_rules! decoded {
+        ($name:literal, $exact_input:literal, $fot:literal, $call:ident, $amount_in:expr, $amount_out:ident) => {
+            DecodedSwap {
+                function: $name,
+                exact_input: $exact_input,
+                amount_in: $amount_in,
+                amount_out: $call.$amount_out,
+                path: $call.path,
+                to: $call.to,
+                deadline: $call.deadline,
+                supporting_fee_on_transfer: $fot,
+            }
+        };
+    }
+
+    let call = IUniswapV2Router02Calls::decode(input).map_err(|_| Error::UnknownSwapCall)?;
+    Ok(match call {
+        SwapExactTokensForTokens(c) => {
+            decoded!("swapExactTokensForTokens", true, false, c, Some(c.amount_in), amount_out_min)
+        }
+        SwapTokensForExactTokens(c) => {
+            decoded!("swapTokensForExactTokens", false, false, c, Some(c.amount_in_max), amount_out)
+        }
+        SwapExactETHForTokens(c) => {
+            decoded!("swapExactETHForTokens", true, false, c, None, amount_out_min)
+        }
+        SwapTokensForExactETH(c) => {
+            decoded!("swapTokensForExactETH", false, false, c, Some(c.amount_in_max), amount_out)
+        }
+        SwapExactTokensForETH(c) => {
+            decoded!("swapExactTokensForETH", true, false, c, Some(c.amount_in), amount_out_min)
+        }
+        SwapETHForExactTokens(c) => {
+            decoded!("swapETHForExactTokens", false, false, c, None, amount_out)
+        }
+        SwapExactTokensForTokensSupportingFeeOnTransferTokens(c) => decoded!(
+            "swapExactTokensForTokensSupportingFeeOnTransferTokens",
+            true,
+            true,
+            c,
+            Some(c.amount_in),
+            amount_out_min
+        ),
+        SwapExactETHForTokensSupportingFeeOnTransferTokens(c) => decoded!(
+            "swapExactETHForTokensSupportingFeeOnTransferTokens",
+            true,
+            true,
+            c,
+            None,
+            amount_out_min
+        ),
+        SwapExactTokensForETHSupportingFeeOnTransferTokens(c) => decoded!(
+            "swapExactTokensForETHSupportingFeeOnTransferTokens",
+            true,
+            true,
+            c,
+            Some(c.amount_in),
+            amount_out_min
+        ),
+        _ => return Err(Error::UnknownSwapCall),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::bindings::i_uniswap_v2_router_02::{
+        QuoteCall, SwapETHForExactTokensCall, SwapExactTokensForTokensCall,
+    };
+    use ethers_core::abi::AbiEncode;
+
+    #[test]
+    fn can_decode_router_call() {
+        let path = vec![Address::repeat_byte(0x11), Address::repeat_byte(0x22)];
+        let to = Address::repeat_byte(0x33);
+
+        let call = SwapExactTokensForTokensCall {
+            amount_in: 100.into(),
+            amount_out_min: 90.into(),
+            path: path.clone(),
+            to,
+            deadline: 1234.into(),
+        };
+        let decoded = decode_router_call(&call.encode().into()).unwrap();
+        assert_eq!(
+            decoded,
+            DecodedSwap {
+                function: "swapExactTokensForTokens",
+                exact_input: true,
+                amount_in: Some(100.into()),
+                amount_out: 90.into(),
+                path: path.clone(),
+                to,
+                deadline: 1234.into(),
+                supporting_fee_on_transfer: false,
+            }
+        );
+
+        let call =
+            SwapETHForExactTokensCall { amount_out: 50.into(), path, to, deadline: 1234.into() };
+        let decoded = decode_router_call(&call.encode().into()).unwrap();
+        assert!(!decoded.exact_input);
+        assert_eq!((decoded.amount_in, decoded.amount_out), (None, 50.into()));
+
+        let call = QuoteCall { amount_a: 1.into(), reserve_a: 1.into(), reserve_b: 1.into() };
+        let res = decode_router_call(&call.encode().into());
+        assert!(matches!(res.unwrap_err(), Error::UnknownSwapCall));
+        let res = decode_router_call(&Bytes::from(vec![1u8, 2, 3]));
+        assert!(matches!(res.unwrap_err(), Error::UnknownSwapCall));
+    }
+}
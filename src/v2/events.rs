@@ -0,0 +1,156 @@
+use crate::{
+    constants::{BPS_U256, PRICE_SCALE},
+    errors::{Error, Result},
+};
+use ethers_contract::EthLogDecode;
+use ethers_core::{
+    abi::RawLog,
+    types::{Log, U256},
+};
+
+/// A pair's `Swap` event.
+pub use crate::contracts::bindings::i_uniswap_v2_pair::SwapFilter as SwapEvent;
+
+/// A pair's `Mint` event, emitted when liquidity is added.
+pub use crate::contracts::bindings::i_uniswap_v2_pair::MintFilter as MintEvent;
+
+/// A pair's `Burn` event, emitted when liquidity is removed.
+pub use crate::contracts::bindings::i_uniswap_v2_pair::BurnFilter as BurnEvent;
+
+/// A factory's `PairCreated` event.
+pub use crate::contracts::bindings::i_uniswap_v2_factory::PairCreatedFilter as PairCreatedEvent;
+
+/// The change of a pair's price caused by a swap.
+///
+/// Prices are of token0 in terms of token1, scaled by [`PRICE_SCALE`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PriceImpact {
+    /// The price before the swap.
+    pub price_before: U256,
+
+    /// The price after the swap.
+    pub price_after: U256,
+
+    /// The absolute change of the price relative to `price_before`, in basis points.
+    pub impact_bps: U256,
+}
+
+impl SwapEvent {
+    /// Returns the reserves of the pair after the swap, given the reserves before it.
+    ///
+    /// Saturates at zero if the event does not match the reserves.
+    pub fn reserves_after(&self, (reserve0, reserve1): (U256, U256)) -> (U256, U256) {
+        (
+            (reserve0 + self.amount_0_in).saturating_sub(self.amount_0_out),
+            (reserve1 + self.amount_1_in).saturating_sub(self.amount_1_out),
+        )
+    }
+
+    /// Returns the prices before and after the swap and the resulting impact, given the sorted
+    /// reserves of the pair before the swap.
+    pub fn price_impact(&self, reserves_before: (U256, U256)) -> PriceImpact {
+        let price = |(reserve0, reserve1): (U256, U256)| {
+            if reserve0.is_zero() {
+                U256::zero()
+            } else {
+                reserve1 * PRICE_SCALE / reserve0
+            }
+        };
+        let price_before = price(reserves_before);
+        let price_after = price(self.reserves_after(reserves_before));
+        let impact_bps = if price_before.is_zero() {
+            U256::zero()
+        } else {
+            let change = if price_after > price_before {
+                price_after - price_before
+            } else {
+                price_before - price_after
+            };
+            change * BPS_U256 / price_before
+        };
+        PriceImpact { price_before, price_after, impact_bps }
+    }
+}
+
+/// Decodes a pair's `Mint` event from a log, returning the sender and the amounts of token0 and
+/// token1 deposited.
+pub fn decode_mint(log: &Log) -> Result<MintEvent> {
+    decode(log)
+}
+
+/// Decodes a pair's `Burn` event from a log, returning the sender, the amounts of token0 and token1
+/// withdrawn and their recipient.
+pub fn decode_burn(log: &Log) -> Result<BurnEvent> {
+    decode(log)
+}
+
+/// Decodes an event from a log.
+pub(super) fn decode<E: EthLogDecode>(log: &Log) -> Result<E> {
+    let log = RawLog { topics: log.topics.clone(), data: log.data.to_vec() };
+    E::decode_log(&log).map_err(|e| Error::ContractError(e.to_string()))
+}
+
+impl PairCreatedEvent {
+    /// Returns the index of the created pair in the factory's `allPairs`.
+    ///
+    /// The event's last field is the length of `allPairs` after the pair was added.
+    pub fn index(&self) -> U256 {
+        self.p3.saturating_sub(U256::one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::Library;
+    use ethers_contract::EthEvent;
+    use ethers_core::{
+        abi::{self, Token},
+        types::Address,
+    };
+
+    #[test]
+    fn can_decode_mint_and_burn() {
+        let (sender, to) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        let amounts = [Token::Uint(1000.into()), Token::Uint(2000.into())];
+        let mint = Log {
+            topics: vec![MintEvent::signature(), sender.into()],
+            data: abi::encode(&amounts).into(),
+            ..Default::default()
+        };
+        let burn = Log {
+            topics: vec![BurnEvent::signature(), sender.into(), to.into()],
+            data: abi::encode(&amounts).into(),
+            ..Default::default()
+        };
+
+        let event = decode_mint(&mint).unwrap();
+        assert_eq!(event, MintEvent { sender, amount_0: 1000.into(), amount_1: 2000.into() });
+        let event = decode_burn(&burn).unwrap();
+        assert_eq!(event, BurnEvent { sender, amount_0: 1000.into(), amount_1: 2000.into(), to });
+
+        assert!(matches!(decode_burn(&mint).unwrap_err(), Error::ContractError(_)));
+        assert!(matches!(decode_mint(&burn).unwrap_err(), Error::ContractError(_)));
+    }
+
+    #[test]
+    fn can_get_swap_price_impact() {
+        let base = U256::exp10(18);
+        let reserves = (U256::from(1000) * base, U256::from(2000) * base);
+        let amount_0_in = U256::from(10) * base;
+        let amount_1_out = Library::get_amount_out(amount_0_in, reserves.0, reserves.1).unwrap();
+        let event = SwapEvent { amount_0_in, amount_1_out, ..Default::default() };
+
+        let after = event.reserves_after(reserves);
+        assert_eq!(after, (reserves.0 + amount_0_in, reserves.1 - amount_1_out));
+
+        let impact = event.price_impact(reserves);
+        assert_eq!(impact.price_before, U256::from(2) * PRICE_SCALE);
+        assert!(impact.price_after < impact.price_before);
+        // ~(1 - 1 / 1.01^2), slightly lower because of the fee
+        assert_eq!(impact.impact_bps, 196.into());
+
+        let impact = event.price_impact((U256::zero(), reserves.1));
+        assert_eq!((impact.price_before, impact.impact_bps), (U256::zero(), U256::zero()));
+    }
+}
@@ -1,8 +1,18 @@
-use super::{Library, Pair};
-use crate::{contracts::bindings::i_uniswap_v2_factory::IUniswapV2Factory, ProtocolType};
+use super::{events, Library, Pair, PairCreatedEvent};
+use crate::{
+    contracts::bindings::i_uniswap_v2_factory::IUniswapV2Factory, errors::Result, ProtocolType,
+};
+use ethers_contract::ContractError;
 use ethers_core::types::{Address, Chain, H256};
 use ethers_providers::Middleware;
-use std::sync::Arc;
+use futures_util::{Stream, StreamExt};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+/// (factory, pair code hash, sorted tokens) => pair address.
+type PairCache = Arc<RwLock<HashMap<(Address, H256, Address, Address), Address>>>;
 
 contract_struct! {
     /// A Uniswap V2 factory.
@@ -15,6 +25,58 @@ contract_struct! {
 
         /// The chain.
         pub chain: Option<Chain>,
+
+        /// How the addresses of the factory's pairs are resolved.
+        pub pair_resolution: PairResolution,
+
+        /// The memoized results of [`cached_pair_for`](Self::cached_pair_for), shared by clones.
+        pair_cache: PairCache,
+    }
+}
+
+/// How the address of a pair is resolved from its tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum PairResolution {
+    /// Compute the CREATE2 address locally with [`Library::pair_for`], without making any
+    /// external calls.
+    ///
+    /// This requires the protocol's pair code hash to be correct.
+    #[default]
+    Create2,
+
+    /// Fetch the address from the factory's `getPair`.
+    ///
+    /// This makes an additional call to the client, but does not depend on the pair code hash, so
+    /// it works with forks that use an unknown one.
+    OnChain,
+}
+
+/// The result of [`Factory::health_check`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FactoryHealth {
+    /// Whether there is code deployed at the factory's address.
+    pub has_code: bool,
+
+    /// The factory's `feeToSetter`, or None if it could not be read.
+    pub fee_to_setter: Option<Address>,
+
+    /// The pair address computed locally with [`Library::pair_for`].
+    pub computed_pair: Address,
+
+    /// The pair address returned by the factory's `getPair`, or None if it could not be read.
+    pub onchain_pair: Option<Address>,
+}
+
+impl FactoryHealth {
+    /// Returns whether the computed pair address is the same as the one returned by the factory.
+    pub fn pair_matches(&self) -> bool {
+        self.onchain_pair == Some(self.computed_pair)
+    }
+
+    /// Returns whether all the checks passed.
+    pub fn is_healthy(&self) -> bool {
+        self.has_code && self.fee_to_setter.is_some() && self.pair_matches()
     }
 }
 
@@ -41,6 +103,16 @@ impl<M> Factory<M> {
     pub fn set_chain(&mut self, chain: Chain) {
         self.chain = Some(chain)
     }
+
+    /// Returns how the addresses of the factory's pairs are resolved.
+    pub fn pair_resolution(&self) -> PairResolution {
+        self.pair_resolution
+    }
+
+    /// Sets how the addresses of the factory's pairs are resolved.
+    pub fn set_pair_resolution(&mut self, pair_resolution: PairResolution) {
+        self.pair_resolution = pair_resolution
+    }
 }
 
 impl<M: Middleware> Factory<M> {
@@ -48,7 +120,13 @@ impl<M: Middleware> Factory<M> {
     pub fn new(client: Arc<M>, address: Address, protocol: ProtocolType) -> Self {
         // assert!(protocol.is_v2(), "protocol must be v2");
         let contract = IUniswapV2Factory::new(address, client);
-        Self { contract, protocol, chain: None }
+        Self {
+            contract,
+            protocol,
+            chain: None,
+            pair_resolution: Default::default(),
+            pair_cache: Default::default(),
+        }
     }
 
     /// Creates a new instance by searching for the required addresses in the [addressbook].
@@ -59,13 +137,192 @@ impl<M: Middleware> Factory<M> {
         // assert!(protocol.is_v2(), "protocol must be v2");
         protocol.try_addresses(chain).0.map(|address| {
             let contract = IUniswapV2Factory::new(address, client);
-            Self { contract, protocol, chain: Some(chain) }
+            Self {
+                contract,
+                protocol,
+                chain: Some(chain),
+                pair_resolution: Default::default(),
+                pair_cache: Default::default(),
+            }
         })
     }
 
-    /// Returns the pair for two token addresses.
-    pub fn pair_for(&self, token_a: Address, token_b: Address) -> Pair<M> {
-        let address = Library::pair_for(self, token_a, token_b);
-        Pair::new(self.client(), address, self.protocol)
+    /// Returns the pair for two token addresses, resolved according to the factory's
+    /// [`PairResolution`]. See [`Library::resolve_pairs`].
+    ///
+    /// Makes no calls with [`PairResolution::Create2`].
+    #[doc(alias = "dry_run_only")]
+    pub async fn pair_for(&self, token_a: Address, token_b: Address) -> Result<Pair<M>> {
+        let address = Library::resolve_pairs(self, &[(token_a, token_b)]).await?[0];
+        Ok(Pair::new(self.client(), address, self.protocol))
+    }
+
+    /// Same as [`Library::pair_for`], but memoizes the computed addresses, so that each pair's
+    /// CREATE2 address is hashed only once.
+    ///
+    /// The cache is thread-safe and shared by all the clones of this factory.
+    pub fn cached_pair_for(&self, token_a: Address, token_b: Address) -> Address {
+        let (a, b) = Library::sort_tokens(token_a, token_b);
+        let key = (self.address(), self.pair_code_hash(None), a, b);
+        if let Some(&pair) = self.pair_cache.read().unwrap().get(&key) {
+            return pair;
+        }
+        let pair = Library::pair_for(self, a, b);
+        self.pair_cache.write().unwrap().insert(key, pair);
+        pair
+    }
+
+    /// Returns the factory's `PairCreated` events emitted in the block range
+    /// `from_block..=to_block`, for discovering the pairs created in the past.
+    ///
+    /// Note that some providers limit the block range of log queries.
+    #[doc(alias = "dry_run_only")]
+    pub async fn pairs_created(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<PairCreatedEvent>> {
+        let events = self
+            .contract
+            .pair_created_filter()
+            .from_block(from_block)
+            .to_block(to_block)
+            .query()
+            .await?;
+        Ok(events)
+    }
+
+    /// Returns a stream of the factory's `PairCreated` events, for discovering new pairs as they
+    /// are created. See [`pairs_created`](Self::pairs_created) for the past events.
+    ///
+    /// The stream polls the `client`'s filter changes, and borrows the client since the factory
+    /// only holds a shared pointer to it.
+    #[doc(alias = "dry_run_only")]
+    pub async fn watch_pairs_created<'a>(
+        &self,
+        client: &'a M,
+    ) -> Result<impl Stream<Item = Result<PairCreatedEvent>> + 'a> {
+        let filter = self.contract.pair_created_filter().filter;
+        let logs = client.watch(&filter).await.map_err(ContractError::<M>::MiddlewareError)?;
+        Ok(logs.map(|log| events::decode(&log)))
+    }
+
+    /// Validates the factory against the chain of its client: checks that the factory is deployed,
+    /// that its `feeToSetter` is readable and that [`pair_for`](Self::pair_for) matches `getPair`
+    /// for an existing pair of `token_a` and `token_b`.
+    ///
+    /// Only fails if the factory's code could not be fetched.
+    #[doc(alias = "dry_run_only")]
+    pub async fn health_check(&self, token_a: Address, token_b: Address) -> Result<FactoryHealth> {
+        let computed_pair = Library::pair_for(self, token_a, token_b);
+        let code = self
+            .client()
+            .get_code(self.address(), None)
+            .await
+            .map_err(ContractError::<M>::MiddlewareError)?;
+        if code.is_empty() {
+            return Ok(FactoryHealth {
+                has_code: false,
+                fee_to_setter: None,
+                computed_pair,
+                onchain_pair: None,
+            });
+        }
+
+        let fee_to_setter = self.contract.fee_to_setter().call().await.ok();
+        let onchain_pair = self.contract.get_pair(token_a, token_b).call().await.ok();
+        Ok(FactoryHealth { has_code: true, fee_to_setter, computed_pair, onchain_pair })
+    }
+}
+
+/// Returns a [Factory] for each V2 [`ProtocolType`] that is deployed on `chain`, per the
+/// [addressbook]. Protocols without a deployment on `chain` are skipped.
+///
+/// [addressbook]: crate::contracts::addresses
+#[cfg(feature = "addresses")]
+pub fn all_factories<M: Middleware>(
+    client: Arc<M>,
+    chain: Chain,
+) -> Vec<(ProtocolType, Factory<M>)> {
+    ProtocolType::all()
+        .into_iter()
+        .filter(ProtocolType::is_v2)
+        .filter_map(|protocol| {
+            Factory::new_with_chain(client.clone(), chain, protocol)
+                .map(|factory| (protocol, factory))
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "addresses"))]
+mod tests {
+    use super::*;
+    use ethers_core::types::U256;
+    use ethers_providers::{Http, Provider, MAINNET};
+
+    #[test]
+    fn can_cache_pair_for() {
+        let client: Arc<Provider<Http>> = MAINNET.provider().into();
+        let mut factory =
+            Factory::new_with_chain(client, Chain::Mainnet, ProtocolType::UniswapV2).unwrap();
+        let (a, b) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+
+        let pair = factory.cached_pair_for(a, b);
+        assert_eq!(pair, Library::pair_for(&factory, a, b));
+        assert_eq!(factory.cached_pair_for(b, a), pair);
+        assert_eq!(factory.clone().pair_cache.read().unwrap().len(), 1);
+
+        // the cache is keyed on the pair code hash
+        factory.protocol = ProtocolType::Sushiswap;
+        assert_ne!(factory.cached_pair_for(a, b), pair);
+        assert_eq!(factory.cached_pair_for(a, b), Library::pair_for(&factory, a, b));
+    }
+
+    #[test]
+    fn can_get_all_factories() {
+        let client: Arc<Provider<Http>> = MAINNET.provider().into();
+        let factories = all_factories(client, Chain::Mainnet);
+        let protocols: Vec<_> = factories.iter().map(|(protocol, _)| *protocol).collect();
+        assert!(protocols.contains(&ProtocolType::UniswapV2));
+        assert!(protocols.contains(&ProtocolType::Sushiswap));
+        assert!(!protocols.contains(&ProtocolType::UniswapV3));
+        for (protocol, factory) in &factories {
+            assert_eq!(factory.protocol(), *protocol);
+            assert_eq!(factory.chain(), Some(Chain::Mainnet));
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_get_pairs_created() {
+        let client: Arc<Provider<Http>> = MAINNET.provider().into();
+        let factory =
+            Factory::new_with_chain(client, Chain::Mainnet, ProtocolType::UniswapV2).unwrap();
+        // the first pair, USDC/WETH, was created in block 10008355
+        let events = factory.pairs_created(10_008_355, 10_008_355).await.unwrap();
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.index(), U256::zero());
+        assert_eq!(event.pair, Library::pair_for(&factory, event.token_0, event.token_1));
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_health_check() {
+        let client: Arc<Provider<Http>> = MAINNET.provider().into();
+        let weth = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap();
+        let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap();
+
+        let factory =
+            Factory::new_with_chain(client.clone(), Chain::Mainnet, ProtocolType::UniswapV2)
+                .unwrap();
+        let health = factory.health_check(weth, usdc).await.unwrap();
+        assert!(health.is_healthy(), "{health:?}");
+
+        // wrong protocol
+        let factory = Factory::new(client, factory.address(), ProtocolType::Sushiswap);
+        let health = factory.health_check(weth, usdc).await.unwrap();
+        assert!(health.has_code);
+        assert!(!health.pair_matches());
     }
 }
@@ -0,0 +1,161 @@
+use super::{Library, Pair};
+use crate::{
+    contracts::bindings::i_uniswap_v2_pair::SwapCall,
+    errors::{Error, Result},
+};
+use ethers_core::{
+    abi::AbiEncode,
+    types::{Address, Bytes, U256},
+};
+
+/// Builds the calldata for a pair's `swap` function to initiate a [flash swap].
+///
+/// The borrowed amount is repaid with the pair's other token, so the repayment is calculated using
+/// [`Library::get_amount_in`].
+///
+/// # Example
+///
+/// ```
+/// # use uniswap_rs::{prelude::_ethers::*, v2::FlashSwapBuilder};
+/// let (token0, token1) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+/// let (reserve0, reserve1) = (U256::exp10(24), U256::exp10(21));
+/// let receiver = Address::repeat_byte(0x33);
+///
+/// let (calldata, repayment) = FlashSwapBuilder::new(token0, token1, reserve0, reserve1)
+///     .borrow(token0, U256::exp10(18))
+///     .to(receiver)
+///     .data(vec![1u8])
+///     .build()?;
+/// // `receiver` must pay back `repayment` of `token1` in its `uniswapV2Call` callback.
+/// # Ok::<_, uniswap_rs::errors::Error>(())
+/// ```
+///
+/// [flash swap]: https://docs.uniswap.org/contracts/v2/guides/smart-contract-integration/using-flash-swaps
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlashSwapBuilder {
+    /// The sorted tokens of the pair.
+    tokens: (Address, Address),
+
+    /// The reserves of the pair, sorted like `tokens`.
+    reserves: (U256, U256),
+
+    /// The token to borrow and the amount.
+    borrow: Option<(Address, U256)>,
+
+    /// The receiver of the borrowed tokens, which will be called back by the pair.
+    to: Address,
+
+    /// The data to pass to the receiver's callback.
+    data: Bytes,
+}
+
+impl FlashSwapBuilder {
+    /// Creates a new builder using the provided pair tokens and reserves.
+    ///
+    /// The tokens and reserves do not need to be sorted.
+    pub fn new(token_a: Address, token_b: Address, reserve_a: U256, reserve_b: U256) -> Self {
+        let (tokens, reserves) = if Library::sort_tokens(token_a, token_b).0 == token_a {
+            ((token_a, token_b), (reserve_a, reserve_b))
+        } else {
+            ((token_b, token_a), (reserve_b, reserve_a))
+        };
+        Self { tokens, reserves, ..Default::default() }
+    }
+
+    /// Creates a new builder using the tokens and reserves of a synced pair.
+    ///
+    /// Returns None if the pair's tokens or reserves have not been synced.
+    pub fn from_pair<M>(pair: &Pair<M>) -> Option<Self> {
+        let (token0, token1) = pair.tokens()?;
+        let (reserve0, reserve1, _) = pair.reserves()?;
+        Some(Self::new(token0, token1, reserve0.into(), reserve1.into()))
+    }
+
+    /// Sets the token to borrow and the amount.
+    pub fn borrow(&mut self, token: Address, amount: U256) -> &mut Self {
+        self.borrow = Some((token, amount));
+        self
+    }
+
+    /// Sets the receiver of the borrowed tokens.
+    pub fn to(&mut self, to: Address) -> &mut Self {
+        self.to = to;
+        self
+    }
+
+    /// Sets the data passed to the receiver's `uniswapV2Call` callback.
+    ///
+    /// This must not be empty, otherwise the pair will not call back the receiver.
+    pub fn data(&mut self, data: impl Into<Bytes>) -> &mut Self {
+        self.data = data.into();
+        self
+    }
+
+    /// Returns the encoded call to the pair's `swap` function and the amount of the other token
+    /// that has to be repaid.
+    pub fn build(&self) -> Result<(Bytes, U256)> {
+        let (token, amount) = self.borrow.ok_or(Error::InsufficientOutputAmount)?;
+        if amount.is_zero() {
+            return Err(Error::InsufficientOutputAmount);
+        }
+        if self.data.is_empty() {
+            return Err(Error::EmptyFlashSwapData);
+        }
+
+        let (token0, token1) = self.tokens;
+        let (reserve0, reserve1) = self.reserves;
+        let (amount_0_out, amount_1_out, reserve_in, reserve_out) = if token == token0 {
+            (amount, U256::zero(), reserve1, reserve0)
+        } else if token == token1 {
+            (U256::zero(), amount, reserve0, reserve1)
+        } else {
+            return Err(Error::TokenNotInPair);
+        };
+        if amount >= reserve_out {
+            return Err(Error::InsufficientLiquidity);
+        }
+
+        let repayment = Library::get_amount_in(amount, reserve_in, reserve_out)?;
+        let call = SwapCall { amount_0_out, amount_1_out, to: self.to, data: self.data.clone() };
+        Ok((call.encode().into(), repayment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::abi::AbiDecode;
+
+    #[test]
+    fn can_build_flash_swap() {
+        let token0 = Address::repeat_byte(0x11);
+        let token1 = Address::repeat_byte(0x22);
+        let reserve0 = U256::exp10(24);
+        let reserve1 = U256::exp10(21);
+        let to = Address::repeat_byte(0x33);
+        let amount = U256::exp10(18);
+
+        // sorts the tokens
+        let mut builder = FlashSwapBuilder::new(token1, token0, reserve1, reserve0);
+        assert_eq!(builder, FlashSwapBuilder::new(token0, token1, reserve0, reserve1));
+
+        assert!(matches!(builder.build().unwrap_err(), Error::InsufficientOutputAmount));
+        builder.borrow(token1, amount).to(to);
+        assert!(matches!(builder.build().unwrap_err(), Error::EmptyFlashSwapData));
+        builder.data(vec![1u8, 2, 3]);
+
+        let (calldata, repayment) = builder.build().unwrap();
+        let call = SwapCall::decode(&calldata).unwrap();
+        assert_eq!(call.amount_0_out, U256::zero());
+        assert_eq!(call.amount_1_out, amount);
+        assert_eq!(call.to, to);
+        assert_eq!(call.data, Bytes::from(vec![1u8, 2, 3]));
+        assert_eq!(repayment, Library::get_amount_in(amount, reserve0, reserve1).unwrap());
+
+        builder.borrow(Address::repeat_byte(0x44), amount);
+        assert!(matches!(builder.build().unwrap_err(), Error::TokenNotInPair));
+
+        builder.borrow(token1, reserve1);
+        assert!(matches!(builder.build().unwrap_err(), Error::InsufficientLiquidity));
+    }
+}
@@ -1,15 +1,177 @@
-use super::factory::Factory;
+use super::{factory::Factory, PairResolution, Router};
 use crate::{
+    constants::{BPS_U256, PRICE_SCALE},
     contracts::bindings::i_uniswap_v2_pair::IUniswapV2Pair,
     errors::{Error, Result},
+    Erc20, Pool, RoundingMode,
 };
 use ethers_contract::{Multicall, MulticallVersion};
 use ethers_core::{
-    abi::Tokenizable,
-    types::{Address, U256},
+    abi::{Token, Tokenizable},
+    types::{Address, I256, U256, U512},
 };
+
+#[cfg(feature = "addresses")]
+use ethers_core::types::Chain;
 use ethers_providers::Middleware;
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+/// The reserves of a pair, sorted like the pair's tokens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PairReserves {
+    /// The pair's first token.
+    pub token0: Address,
+
+    /// The pair's second token.
+    pub token1: Address,
+
+    /// The reserve of `token0`.
+    pub reserve0: U256,
+
+    /// The reserve of `token1`.
+    pub reserve1: U256,
+
+    /// The pair's address.
+    pub pair: Address,
+}
+
+impl PairReserves {
+    /// Returns the reserves sorted as `(reserve_in, reserve_out)` for swapping `token_in`, or None
+    /// if `token_in` is not one of the pair's tokens.
+    pub fn reserves_for(&self, token_in: Address) -> Option<(U256, U256)> {
+        if token_in == self.token0 {
+            Some((self.reserve0, self.reserve1))
+        } else if token_in == self.token1 {
+            Some((self.reserve1, self.reserve0))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the constant product of the pair's reserves.
+    ///
+    /// See [`Library::invariant`].
+    pub fn invariant(&self) -> Result<U256> {
+        Library::invariant(self.reserve0, self.reserve1)
+    }
+}
+
+impl Pool for PairReserves {
+    fn tokens(&self) -> Result<(Address, Address)> {
+        Ok((self.token0, self.token1))
+    }
+
+    fn amount_out(&self, token_in: Address, amount_in: U256) -> Result<U256> {
+        let (reserve_in, reserve_out) = self.reserves_for(token_in).ok_or(Error::TokenNotInPair)?;
+        Library::get_amount_out(amount_in, reserve_in, reserve_out)
+    }
+}
+
+/// The amounts of a single hop of a path, including the swap fee.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct HopAmounts {
+    /// The amount of the input token sent to the pair.
+    pub amount_in: U256,
+
+    /// The amount of the output token received from the pair.
+    pub amount_out: U256,
+
+    /// The part of `amount_in` charged as the swap fee, rounded down.
+    pub fee: U256,
+}
+
+/// The encoding of the reserves returned by a pair's `getReserves`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ReservesEncoding {
+    /// The standard `(uint112, uint112, uint32)`.
+    #[default]
+    Uint112,
+
+    /// Reserves or a timestamp wider than the standard, as returned by some forks. Decoded as
+    /// `(uint256, uint256, uint256)`.
+    Uint256,
+}
+
+/// Decodes the reserves fetched by a multicall built with
+/// [`Library::build_reserves_multicall`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReservesDecoder {
+    /// Whether to sort the reserves of each call.
+    sorted: Vec<bool>,
+}
+
+impl ReservesDecoder {
+    /// Creates a new instance for the `getReserves` results of the pair of each `(a, b)` of
+    /// `pairs`, in order.
+    pub(super) fn for_pairs(pairs: &[(Address, Address)]) -> Self {
+        let sorted = pairs.iter().map(|&(a, b)| Library::sort_tokens(a, b).0 == b).collect();
+        Self { sorted }
+    }
+
+    /// Returns the number of reserves calls, which are the first calls of the multicall.
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    /// Returns whether there are no reserves calls.
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    /// Returns, for each reserves call, whether the hop's input token is the pair's `token1`, that
+    /// is whether the decoded reserves are swapped from the pair's `(reserve0, reserve1)` order.
+    pub fn orientation(&self) -> &[bool] {
+        &self.sorted
+    }
+
+    /// Decodes the reserves from the raw results of the multicall, ignoring any results after the
+    /// reserves calls.
+    ///
+    /// Also accepts the `(success, result)` results of [`MulticallVersion::Multicall3`].
+    pub fn decode(&self, tokens: &[Token]) -> Result<Vec<(U256, U256)>> {
+        let reserves = self.decode_with_encoding(tokens)?;
+        Ok(reserves.into_iter().map(|(reserves, _)| reserves).collect())
+    }
+
+    /// Same as [`decode`](Self::decode), but also returns the encoding of each pair's reserves.
+    ///
+    /// Reserves that do not fit the standard [`ReservesEncoding::Uint112`] are decoded as
+    /// [`ReservesEncoding::Uint256`] instead of failing or being truncated.
+    pub fn decode_with_encoding(
+        &self,
+        tokens: &[Token],
+    ) -> Result<Vec<((U256, U256), ReservesEncoding)>> {
+        if tokens.len() < self.len() {
+            return Err(Error::MulticallError("Missing reserves results".to_string()));
+        }
+        tokens
+            .iter()
+            .zip(&self.sorted)
+            .map(|(token, &sort)| {
+                let token = match token {
+                    Token::Tuple(values) if matches!(values.first(), Some(Token::Bool(_))) => {
+                        values[1].clone()
+                    }
+                    token => token.clone(),
+                };
+                let (a, b, timestamp): (U256, U256, U256) = Tokenizable::from_token(token)?;
+                let encoding = if a.bits() <= 112 && b.bits() <= 112 && timestamp.bits() <= 32 {
+                    ReservesEncoding::Uint112
+                } else {
+                    ReservesEncoding::Uint256
+                };
+                Ok((if sort { (b, a) } else { (a, b) }, encoding))
+            })
+            .collect()
+    }
+}
 
 /// The Uniswap V2 library, ported to Rust from Solidity.
 ///
@@ -19,6 +181,12 @@ use std::cmp::Ordering;
 pub struct Library;
 
 impl Library {
+    /// The swap fee charged by Uniswap V2 pairs, in basis points (0.3%).
+    pub const FEE_BPS: u32 = 30;
+
+    /// The amount of liquidity permanently locked by a pair on the first deposit.
+    pub const MINIMUM_LIQUIDITY: u64 = 1000;
+
     /// Returns sorted token addresses, used to handle return values from pairs sorted in this
     /// order.
     #[inline]
@@ -29,6 +197,44 @@ impl Library {
         }
     }
 
+    /// Same as [`sort_tokens`](Self::sort_tokens), but also returns whether `a` and `b` were
+    /// swapped, that is whether `b` is the pair's `token0`.
+    ///
+    /// Returns [`Error::IdenticalAddresses`] if `a` and `b` are the same.
+    pub fn sort_tokens_with_flag(a: Address, b: Address) -> Result<(Address, Address, bool)> {
+        match a.cmp(&b) {
+            Ordering::Less => Ok((a, b, false)),
+            Ordering::Greater => Ok((b, a, true)),
+            Ordering::Equal => Err(Error::IdenticalAddresses),
+        }
+    }
+
+    /// Returns whether `base` is the pair's `token0` when paired with `quote`, according to the
+    /// ordering of [`sort_tokens`](Self::sort_tokens).
+    pub fn is_base_token0(base: Address, quote: Address) -> Result<bool> {
+        match base.cmp(&quote) {
+            Ordering::Less => Ok(true),
+            Ordering::Greater => Ok(false),
+            Ordering::Equal => Err(Error::IdenticalAddresses),
+        }
+    }
+
+    /// Returns the reserve of `token` from a pair's `(reserve0, reserve1)`, given the pair's
+    /// sorted `(token0, token1)`.
+    ///
+    /// Returns [`Error::TokenNotInPair`] if `token` is neither of the pair's tokens.
+    pub fn reserve_for_token(
+        token: Address,
+        tokens: (Address, Address),
+        reserves: (U256, U256),
+    ) -> Result<U256> {
+        match token {
+            t if t == tokens.0 => Ok(reserves.0),
+            t if t == tokens.1 => Ok(reserves.1),
+            _ => Err(Error::TokenNotInPair),
+        }
+    }
+
     /// Calculates the CREATE2 address for a pair without making any external calls.
     pub fn pair_for<M: Middleware>(
         factory: &Factory<M>,
@@ -45,67 +251,298 @@ impl Library {
         ethers_core::utils::get_create2_address_from_hash(from, salt, init_code_hash)
     }
 
+    /// Resolves the addresses of `pairs` according to the factory's [`PairResolution`].
+    ///
+    /// With [`PairResolution::OnChain`], makes only 1 call to the client by using [Multicall] and
+    /// returns [`Error::PairNotFound`] if any of the pairs does not exist. Since the reserves can
+    /// only be requested once the addresses are known, this call is not batched with them.
+    #[doc(alias = "dry_run_only")]
+    pub async fn resolve_pairs<M: Middleware>(
+        factory: &Factory<M>,
+        pairs: &[(Address, Address)],
+    ) -> Result<Vec<Address>> {
+        if factory.pair_resolution() == PairResolution::Create2 {
+            return Ok(pairs.iter().map(|&(a, b)| Self::pair_for(factory, a, b)).collect());
+        }
+
+        let addresses: Vec<Address> = match pairs {
+            [] => return Ok(vec![]),
+            // avoid multicall for only 1 call
+            &[(a, b)] => vec![factory.contract().get_pair(a, b).call().await?],
+            pairs => {
+                let mut multicall = Multicall::new(factory.client(), None)
+                    .await?
+                    .version(MulticallVersion::Multicall);
+                for &(a, b) in pairs {
+                    multicall.add_call(factory.contract().get_pair(a, b), false);
+                }
+                multicall
+                    .call_raw()
+                    .await?
+                    .into_iter()
+                    .map(|token| Ok(Tokenizable::from_token(token)?))
+                    .collect::<Result<_>>()?
+            }
+        };
+        if addresses.iter().any(Address::is_zero) {
+            return Err(Error::PairNotFound);
+        }
+        Ok(addresses)
+    }
+
     /// Fetches and sorts the reserves for a pair.
+    #[doc(alias = "dry_run_only")]
     pub async fn get_reserves<M: Middleware>(
         factory: &Factory<M>,
         a: Address,
         b: Address,
     ) -> Result<(U256, U256)> {
-        let (address_0, _) = Self::sort_tokens(a, b);
-        let pair = IUniswapV2Pair::new(Self::pair_for(factory, a, b), factory.client());
-        let r = pair.get_reserves().call().await?;
-        let (reserve_a, reserve_b) = (r.0.into(), r.1.into());
-        if a == address_0 {
-            Ok((reserve_a, reserve_b))
+        let addresses = Self::resolve_pairs(factory, &[(a, b)]).await?;
+        Ok(Self::fetch_reserves(factory, &[(a, b)], &addresses).await?[0])
+    }
+
+    /// Same as [`get_reserves`](Self::get_reserves), but also returns the pair's
+    /// `blockTimestampLast`, the timestamp modulo 2^32 of the block of the pair's last update.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_reserves_with_timestamp<M: Middleware>(
+        factory: &Factory<M>,
+        a: Address,
+        b: Address,
+    ) -> Result<(U256, U256, u32)> {
+        let address = Self::resolve_pairs(factory, &[(a, b)]).await?[0];
+        let pair = IUniswapV2Pair::new(address, factory.client());
+        let (reserve0, reserve1, timestamp) = pair.get_reserves().call().await?;
+        let (reserve_a, reserve_b) = if Self::sort_tokens(a, b).0 == a {
+            (reserve0, reserve1)
         } else {
-            Ok((reserve_b, reserve_a))
-        }
+            (reserve1, reserve0)
+        };
+        Ok((reserve_a.into(), reserve_b.into(), timestamp))
     }
 
     /// Fetches and sorts the reserves for multiple pairs. Makes only 1 call to the client by using
-    /// [Multicall].
+    /// [Multicall], plus 1 call to resolve the pairs' addresses with [`PairResolution::OnChain`].
+    #[doc(alias = "dry_run_only")]
     pub async fn get_reserves_multi<M: Middleware>(
         factory: &Factory<M>,
         path: &[Address],
     ) -> Result<Vec<(U256, U256)>> {
-        let len = match path.len() {
-            0 | 1 => return Err(Error::InvalidPath),
-            2 => {
-                // avoid multicall for only 1 call
-                let (a, b) = (path[0], path[1]);
-                let res = Self::get_reserves(factory, a, b).await?;
-                return Ok(vec![res]);
-            }
-            l => l - 1,
-        };
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+        let pairs: Vec<_> = path.windows(2).map(|slice| (slice[0], slice[1])).collect();
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+        Self::fetch_reserves(factory, &pairs, &addresses).await
+    }
+
+    /// Same as [`get_reserves_multi`](Self::get_reserves_multi), but also returns the encoding of
+    /// each pair's reserves. See [`ReservesDecoder::decode_with_encoding`].
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_reserves_multi_with_encoding<M: Middleware>(
+        factory: &Factory<M>,
+        path: &[Address],
+    ) -> Result<Vec<((U256, U256), ReservesEncoding)>> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+        let pairs: Vec<_> = path.windows(2).map(|slice| (slice[0], slice[1])).collect();
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+        Self::fetch_reserves_with_encoding(factory, &pairs, &addresses).await
+    }
+
+    /// Same as [`get_reserves_multi`](Self::get_reserves_multi), but also returns whether the
+    /// input token of each hop is the pair's `token1`, in which case the returned
+    /// `(reserve_in, reserve_out)` are `(reserve1, reserve0)`.
+    ///
+    /// This allows recomputing amounts with the fetched reserves, for example in the other
+    /// direction, without sorting the tokens again.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_reserves_multi_with_orientation<M: Middleware>(
+        factory: &Factory<M>,
+        path: &[Address],
+    ) -> Result<Vec<((U256, U256), bool)>> {
+        let reserves = Self::get_reserves_multi(factory, path).await?;
+        let orientation =
+            path.windows(2).map(|slice| Self::sort_tokens(slice[0], slice[1]).0 == slice[1]);
+        Ok(reserves.into_iter().zip(orientation).collect())
+    }
+
+    /// Fetches and sorts the reserves of `pairs`, deployed at `addresses`.
+    pub(super) async fn fetch_reserves<M: Middleware>(
+        factory: &Factory<M>,
+        pairs: &[(Address, Address)],
+        addresses: &[Address],
+    ) -> Result<Vec<(U256, U256)>> {
+        let reserves = Self::fetch_reserves_with_encoding(factory, pairs, addresses).await?;
+        Ok(reserves.into_iter().map(|(reserves, _)| reserves).collect())
+    }
+
+    async fn fetch_reserves_with_encoding<M: Middleware>(
+        factory: &Factory<M>,
+        pairs: &[(Address, Address)],
+        addresses: &[Address],
+    ) -> Result<Vec<((U256, U256), ReservesEncoding)>> {
+        if let (&[(a, b)], &[address]) = (pairs, addresses) {
+            // avoid multicall for only 1 call
+            let pair = IUniswapV2Pair::new(address, factory.client());
+            // decode as uint256 to not truncate non-standard reserves
+            let r: (U256, U256, U256) = pair
+                .method("getReserves", ())
+                .map_err(|e| Error::ContractError(e.to_string()))?
+                .call()
+                .await?;
+            let decoder = ReservesDecoder::for_pairs(&[(a, b)]);
+            return decoder.decode_with_encoding(&[r.into_token()]);
+        }
+
+        let (multicall, decoder) = Self::reserves_multicall(factory, pairs, addresses).await?;
+        decoder.decode_with_encoding(&multicall.call_raw().await?)
+    }
+
+    /// Builds a [Multicall] that fetches the reserves of every pair of the path, without executing
+    /// it.
+    ///
+    /// More calls can be appended to the returned multicall, after which its raw results can be
+    /// passed to [`ReservesDecoder::decode`] to read the reserves, sorted like in
+    /// [`get_reserves_multi`](Self::get_reserves_multi).
+    #[doc(alias = "dry_run_only")]
+    pub async fn build_reserves_multicall<M: Middleware>(
+        factory: &Factory<M>,
+        path: &[Address],
+    ) -> Result<(Multicall<M>, ReservesDecoder)> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+        let pairs: Vec<_> = path.windows(2).map(|slice| (slice[0], slice[1])).collect();
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+        Self::reserves_multicall(factory, &pairs, &addresses).await
+    }
 
+    async fn reserves_multicall<M: Middleware>(
+        factory: &Factory<M>,
+        pairs: &[(Address, Address)],
+        addresses: &[Address],
+    ) -> Result<(Multicall<M>, ReservesDecoder)> {
         let client = factory.client();
         let mut multicall =
             Multicall::new(client.clone(), None).await?.version(MulticallVersion::Multicall);
-        // whether to sort the reserves later
-        let mut sorted = Vec::with_capacity(len);
 
         let pair = IUniswapV2Pair::new(Address::zero(), client);
         let call = pair.get_reserves();
-        for slice in path.windows(2) {
-            let (a, b) = (slice[0], slice[1]);
-
-            let (address_0, _) = Self::sort_tokens(a, b);
-            sorted.push(address_0 == b);
-
+        for &address in addresses {
             let mut call = call.clone();
-            call.tx.set_to(Self::pair_for(factory, a, b));
+            call.tx.set_to(address);
             multicall.add_call(call, false);
         }
 
+        Ok((multicall, ReservesDecoder::for_pairs(pairs)))
+    }
+
+    /// Fetches the sorted tokens of a pair.
+    #[doc(alias = "dry_run_only")]
+    pub async fn pair_tokens<M: Middleware>(
+        client: Arc<M>,
+        pair: Address,
+    ) -> Result<(Address, Address)> {
+        let pair = IUniswapV2Pair::new(pair, client);
+        let mut multicall =
+            Multicall::new(pair.client(), None).await?.version(MulticallVersion::Multicall);
+        multicall.add_call(pair.token_0(), false).add_call(pair.token_1(), false);
+        Ok(multicall.call().await?)
+    }
+
+    /// Validates that every pair of the path resolved with [`resolve_pairs`](Self::resolve_pairs)
+    /// contains both tokens of its hop, by fetching the pairs' tokens. Makes only 1 call to the
+    /// client by using [Multicall].
+    ///
+    /// Returns [`Error::TokenNotInPair`] if any pair has different tokens.
+    #[doc(alias = "dry_run_only")]
+    pub async fn validate_path_on_chain<M: Middleware>(
+        factory: &Factory<M>,
+        path: &[Address],
+    ) -> Result<()> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+
+        let client = factory.client();
+        let mut multicall =
+            Multicall::new(client.clone(), None).await?.version(MulticallVersion::Multicall);
+        let pairs: Vec<_> = path.windows(2).map(|slice| (slice[0], slice[1])).collect();
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+
+        let pair = IUniswapV2Pair::new(Address::zero(), client);
+        let (token_0, token_1) = (pair.token_0(), pair.token_1());
+        for address in addresses {
+            for call in [&token_0, &token_1] {
+                let mut call = call.clone();
+                call.tx.set_to(address);
+                multicall.add_call(call, false);
+            }
+        }
+
+        let tokens = multicall.call_raw().await?;
+        for (slice, tokens) in path.windows(2).zip(tokens.chunks(2)) {
+            let token0: Address = Tokenizable::from_token(tokens[0].clone())?;
+            let token1: Address = Tokenizable::from_token(tokens[1].clone())?;
+            if (token0, token1) != Self::sort_tokens(slice[0], slice[1]) {
+                return Err(Error::TokenNotInPair);
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as [`get_reserves_multi`](Self::get_reserves_multi), but returns the reserves together
+    /// with the tokens and address of each pair of the path.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_pair_reserves_multi<M: Middleware>(
+        factory: &Factory<M>,
+        path: &[Address],
+    ) -> Result<Vec<PairReserves>> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+        let pairs: Vec<_> = path.windows(2).map(|slice| (slice[0], slice[1])).collect();
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+        let reserves = Self::fetch_reserves(factory, &pairs, &addresses).await?;
+        Ok(pairs
+            .into_iter()
+            .zip(reserves)
+            .zip(addresses)
+            .map(|(((a, b), (reserve_a, reserve_b)), pair)| {
+                let (token0, token1) = Self::sort_tokens(a, b);
+                let (reserve0, reserve1) =
+                    if token0 == a { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+                PairReserves { token0, token1, reserve0, reserve1, pair }
+            })
+            .collect())
+    }
+
+    /// Returns whether the protocol fee is active for each of `factories`, that is whether their
+    /// `feeTo` is set. Makes only 1 call to the client by using [Multicall].
+    ///
+    /// The factories must be on the same chain, the client of the first one is used.
+    #[doc(alias = "dry_run_only")]
+    pub async fn protocol_fees_active<M: Middleware>(
+        factories: &[Factory<M>],
+    ) -> Result<Vec<bool>> {
+        let client = match factories.first() {
+            Some(factory) => factory.client(),
+            None => return Ok(vec![]),
+        };
+        let mut multicall =
+            Multicall::new(client, None).await?.version(MulticallVersion::Multicall);
+        for factory in factories {
+            multicall.add_call(factory.contract().fee_to(), false);
+        }
+
         multicall
             .call_raw()
             .await?
             .into_iter()
-            .zip(sorted)
-            .map(|(token, sort)| {
-                let (a, b): (U256, U256) = Tokenizable::from_token(token)?;
-                Ok(if sort { (b, a) } else { (a, b) })
+            .map(|token| {
+                let fee_to: Address = Tokenizable::from_token(token)?;
+                Ok(!fee_to.is_zero())
             })
             .collect()
     }
@@ -119,128 +556,1373 @@ impl Library {
         Ok((amount_a * reserve_b) / reserve_a)
     }
 
-    /// Given an input amount of an asset and pair reserves, returns the maximum output amount of
-    /// the other asset.
-    pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256) -> Result<U256> {
+    /// Returns the marginal price of the input asset in terms of the output asset, scaled by
+    /// [`PRICE_SCALE`], given the pair reserves and swap fee in basis points.
+    ///
+    /// This is the best price achievable by an infinitesimal trade, that is
+    /// `reserve_out / reserve_in * (1 - fee)`. Unlike [`quote`](Self::quote), which returns the
+    /// mid-price used in price impact calculations, this includes the swap fee.
+    pub fn marginal_price(reserve_in: U256, reserve_out: U256, fee_bps: u32) -> Result<U256> {
         if reserve_in.is_zero() || reserve_out.is_zero() {
             return Err(Error::InsufficientLiquidity);
         }
-        let amount_in_with_fee = amount_in * 997;
-        let numerator = amount_in_with_fee * reserve_out;
-        let denominator = reserve_in * 1000 + amount_in_with_fee;
-        Ok(numerator / denominator)
+        let numerator = reserve_out * fee_multiplier(fee_bps)? * PRICE_SCALE;
+        Ok(numerator / (reserve_in * BPS_U256))
     }
 
-    /// Given an output amount of an asset and pair reserves, returns a required input amount of the
-    /// other asset.
-    pub fn get_amount_in(amount_out: U256, reserve_in: U256, reserve_out: U256) -> Result<U256> {
+    /// Returns the [marginal prices](Self::marginal_price) of A in terms of B and of B in terms of
+    /// A, both scaled by [`PRICE_SCALE`], given the pair reserves and swap fee in basis points.
+    pub fn prices_both_ways(
+        reserve_a: U256,
+        reserve_b: U256,
+        fee_bps: u32,
+    ) -> Result<(U256, U256)> {
+        Ok((
+            Self::marginal_price(reserve_a, reserve_b, fee_bps)?,
+            Self::marginal_price(reserve_b, reserve_a, fee_bps)?,
+        ))
+    }
+
+    /// Returns the price impact of swapping `amount_in`, in basis points, given the pair reserves
+    /// and swap fee in basis points.
+    ///
+    /// The price impact is the relative difference between the execution price and the mid-price
+    /// (see [`quote`](Self::quote)), so it includes the swap fee. The output amount is not rounded
+    /// down for this calculation.
+    pub fn price_impact_bps(
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        fee_bps: u32,
+    ) -> Result<u32> {
         if reserve_in.is_zero() || reserve_out.is_zero() {
             return Err(Error::InsufficientLiquidity);
         }
-        let numerator = reserve_in * amount_out * 1000;
-        let denominator = (reserve_out - amount_out) * 997;
-        Ok((numerator / denominator) + 1)
+        // execution / mid = (fee * reserve_in) / (reserve_in * 10000 + fee * amount_in)
+        let fee = fee_multiplier(fee_bps)?;
+        let numerator = fee * reserve_in * BPS_U256;
+        let denominator = reserve_in * BPS_U256 + fee * amount_in;
+        Ok((BPS_U256 - numerator / denominator).as_u32())
     }
 
-    /// Performs chained get_amount_out calculations on any number of pairs.
-    pub async fn get_amounts_out<M: Middleware>(
-        factory: &Factory<M>,
-        amount_in: U256,
-        path: &[Address],
-    ) -> Result<Vec<U256>> {
-        let len = path.len();
-        if len < 2 {
-            return Err(Error::InvalidPath);
+    /// Returns the largest input amount with a [price impact](Self::price_impact_bps) lower than or
+    /// equal to `max_impact_bps`, given the pair reserves and swap fee in basis points.
+    ///
+    /// Returns zero if `max_impact_bps` is lower than the fee, since every trade pays the fee.
+    pub fn max_amount_for_impact(
+        reserve_in: U256,
+        reserve_out: U256,
+        max_impact_bps: u32,
+        fee_bps: u32,
+    ) -> Result<U256> {
+        if reserve_in.is_zero() || reserve_out.is_zero() {
+            return Err(Error::InsufficientLiquidity);
+        }
+        if max_impact_bps >= 10_000 {
+            return Err(Error::InvalidPriceImpact);
         }
+        let fee = fee_multiplier(fee_bps)?;
+        if max_impact_bps <= fee_bps {
+            return Ok(U256::zero());
+        }
+        // inverse of `price_impact_bps`
+        let numerator = reserve_in * BPS_U256 * (max_impact_bps - fee_bps);
+        let denominator = fee * (10_000 - max_impact_bps);
+        Ok(numerator / denominator)
+    }
 
-        let reserves = Self::get_reserves_multi(factory, path).await?;
-        let mut amounts = Vec::with_capacity(len);
-        amounts.push(amount_in);
-        for (i, (reserve_in, reserve_out)) in reserves.into_iter().enumerate() {
-            amounts.push(Self::get_amount_out(amounts[i], reserve_in, reserve_out)?);
+    /// Returns which token to sell, and how much of it, to move a pair's reserve ratio to
+    /// `target_ratio`, given the pair reserves and swap fee in basis points.
+    ///
+    /// The ratio is `reserve_b / reserve_a`, that is the price of A in terms of B, scaled by
+    /// [`PRICE_SCALE`]. The returned flag is true if A must be sold, and false if B must be sold.
+    /// The amount is rounded down, so the ratio is reached from the current side. Returns
+    /// [`Error::InvalidRatio`] if `target_ratio` is zero.
+    pub fn amount_to_reach_ratio(
+        reserve_a: U256,
+        reserve_b: U256,
+        target_ratio: U256,
+        fee_bps: u32,
+    ) -> Result<(bool, U256)> {
+        if reserve_a.is_zero() || reserve_b.is_zero() {
+            return Err(Error::InsufficientLiquidity);
         }
-        Ok(amounts)
+        if target_ratio.is_zero() {
+            return Err(Error::InvalidRatio);
+        }
+        let fee = U512::from(fee_multiplier(fee_bps)?);
+        let bps = U512::from(BPS_U256);
+        let (a, b) = (U512::from(reserve_a), U512::from(reserve_b));
+        let (ratio, scale) = (U512::from(target_ratio), U512::from(PRICE_SCALE));
+
+        // selling `x` of the input reserve `r_in` for the output reserve `r_out` gives the ratio
+        // `r_in * r_out / ((r_in + fee * x) * (r_in + x))`, so `x` is the positive root of
+        // `fee * x^2 + r_in * (bps + fee) * x + bps * r_in * (r_in - r_out / target) = 0`
+        let sell_a = b * scale > a * ratio;
+        let (r_in, r_out_over_target) =
+            if sell_a { (a, b * scale / ratio) } else { (b, a * ratio / scale) };
+        let discriminant = r_in * r_in * (bps - fee) * (bps - fee)
+            + U512::from(4) * fee * bps * r_in * r_out_over_target;
+        let amount =
+            discriminant.integer_sqrt().saturating_sub(r_in * (bps + fee)) / (U512::from(2) * fee);
+        let amount = U256::try_from(amount).map_err(|_| Error::Overflow)?;
+        Ok((sell_a, amount))
     }
 
-    /// Performs chained get_amount_in calculations on any number of pairs.
-    pub async fn get_amounts_in<M: Middleware>(
-        factory: &Factory<M>,
+    /// Returns the input amount required to receive `amount_out`, or None if it cannot be received
+    /// with a [price impact](Self::price_impact_bps) lower than or equal to `max_impact_bps`, given
+    /// the pair reserves and swap fee in basis points.
+    pub fn input_for_output_within_impact(
         amount_out: U256,
-        path: &[Address],
-    ) -> Result<Vec<U256>> {
-        let len = path.len();
-        if len < 2 {
-            return Err(Error::InvalidPath);
+        reserve_in: U256,
+        reserve_out: U256,
+        max_impact_bps: u32,
+        fee_bps: u32,
+    ) -> Result<Option<U256>> {
+        if max_impact_bps >= 10_000 {
+            return Err(Error::InvalidPriceImpact);
         }
-
-        let reserves = Self::get_reserves_multi(factory, path).await?;
-        let mut amounts = vec![U256::zero(); len];
-        amounts[len - 1] = amount_out;
-        for (i, (reserve_in, reserve_out)) in reserves.into_iter().enumerate().rev() {
-            amounts[i] = Self::get_amount_in(amounts[i + 1], reserve_in, reserve_out)?;
+        if amount_out >= reserve_out {
+            return Ok(None);
         }
-        Ok(amounts)
+        let amount_in = Self::get_amount_in_with_fee(amount_out, reserve_in, reserve_out, fee_bps)?;
+        let impact = Self::price_impact_bps(amount_in, reserve_in, reserve_out, fee_bps)?;
+        Ok((impact <= max_impact_bps).then_some(amount_in))
     }
-}
-
-#[cfg(all(test, feature = "addresses"))]
-mod tests {
-    use super::*;
-    use crate::ProtocolType;
-    use ethers_contract::Lazy;
-    use ethers_core::types::Chain;
-    use ethers_providers::{Http, Provider, MAINNET};
-
-    static FACTORY: Lazy<Factory<Provider<Http>>> = Lazy::new(|| {
-        Factory::new_with_chain(MAINNET.provider().into(), Chain::Mainnet, ProtocolType::UniswapV2)
-            .unwrap()
-    });
-    static WETH: Lazy<Address> =
-        Lazy::new(|| "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap());
-    static USDC: Lazy<Address> =
-        Lazy::new(|| "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap());
-    static WETH_USDC: Lazy<Address> =
-        Lazy::new(|| "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc".parse().unwrap());
 
-    #[test]
-    fn can_sort_tokens() {
-        let (a, b) = (Address::random(), Address::random());
-        let (res_a, res_b) = Library::sort_tokens(a, b);
-        match a.cmp(&b) {
-            Ordering::Less | Ordering::Equal => {
-                assert_eq!(res_a, a);
-                assert_eq!(res_b, b);
-            }
-            Ordering::Greater => {
-                assert_eq!(res_a, b);
-                assert_eq!(res_b, a);
-            }
+    /// Returns the realized slippage of a swap in signed basis points, given the quoted
+    /// `expected_out` and the `actual_out` received.
+    ///
+    /// The slippage is positive if less than expected was received and negative if more was
+    /// received. It is rounded towards zero and saturates at the bounds of `i32`. Returns zero if
+    /// `expected_out` is zero.
+    pub fn realized_slippage_bps(expected_out: U256, actual_out: U256) -> i32 {
+        if expected_out.is_zero() {
+            return 0;
         }
+        let (diff, sign) = match expected_out.cmp(&actual_out) {
+            Ordering::Less => (actual_out - expected_out, -1),
+            _ => (expected_out - actual_out, 1),
+        };
+        let bps = diff.full_mul(BPS_U256) / expected_out;
+        let bps = if bps > i32::MAX.into() { i32::MAX } else { bps.low_u32() as i32 };
+        bps * sign
     }
 
-    #[test]
-    fn can_get_pair_for() {
-        assert_eq!(Library::pair_for(&*FACTORY, *WETH, *USDC), *WETH_USDC);
+    /// Computes the output amount like [`get_amount_out_with_fee`](Self::get_amount_out_with_fee),
+    /// but returns [`Error::PriceDeviation`] if the execution price `amount_out / amount_in`
+    /// deviates from `reference_price` by more than `max_deviation_bps`.
+    ///
+    /// `reference_price` is the price of the input asset in terms of the output asset, scaled by
+    /// [`PRICE_SCALE`], for example from an external oracle. This guards against trading on a
+    /// manipulated pair. Note that the execution price includes the swap fee and price impact.
+    pub fn quote_with_sanity(
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        fee_bps: u32,
+        reference_price: U256,
+        max_deviation_bps: u32,
+    ) -> Result<U256> {
+        if amount_in.is_zero() {
+            return Err(Error::InsufficientInputAmount);
+        }
+        let amount_out =
+            Self::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+        let price = amount_out.full_mul(PRICE_SCALE) / amount_in;
+        let reference_price = U512::from(reference_price);
+        let diff =
+            if price > reference_price { price - reference_price } else { reference_price - price };
+        let deviation_bps = if reference_price.is_zero() {
+            u32::MAX
+        } else {
+            let bps = diff * U512::from(BPS_U256) / reference_price;
+            if bps > u32::MAX.into() {
+                u32::MAX
+            } else {
+                bps.low_u32()
+            }
+        };
+        if deviation_bps > max_deviation_bps {
+            return Err(Error::PriceDeviation { deviation_bps });
+        }
+        Ok(amount_out)
     }
 
-    async fn get_weth_usdc_reserves() -> (U256, U256) {
-        Library::get_reserves(&*FACTORY, *WETH, *USDC).await.unwrap()
+    /// Returns the `(min, expected, max)` output amounts of swapping `amount_in` when the price may
+    /// move by up to `slippage_bps` in either direction, given the pair reserves and swap fee in
+    /// basis points.
+    ///
+    /// `expected` is [`get_amount_out_with_fee`](Self::get_amount_out_with_fee), and `min` is
+    /// what a swap with a `slippage_bps` tolerance would accept as its minimum output. Returns
+    /// [`Error::InvalidSlippage`] if `slippage_bps` is greater than 10000.
+    pub fn output_band(
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        fee_bps: u32,
+        slippage_bps: u32,
+    ) -> Result<(U256, U256, U256)> {
+        if slippage_bps > 10_000 {
+            return Err(Error::InvalidSlippage);
+        }
+        let expected = Self::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+        let min = expected * (10_000 - slippage_bps) / BPS_U256;
+        let max = expected * (10_000 + slippage_bps) / BPS_U256;
+        Ok((min, expected, max))
     }
 
-    #[tokio::test]
-    #[ignore = "async test"]
-    async fn can_get_reserves() {
-        get_weth_usdc_reserves().await;
+    /// Returns a suggested slippage tolerance in basis points for a trade with the given
+    /// [price impact](Self::price_impact_bps).
+    ///
+    /// The formula is `clamp(price_impact_bps + 30, 50, 1000)`: the price impact plus a 0.3% safety
+    /// margin, at least 0.5% and at most 10%. Trades with a larger impact are executed against
+    /// thinner liquidity, which moves more between quoting and execution. This is only a heuristic;
+    /// integrators with better knowledge of the chain or tokens should use their own tolerance.
+    pub fn recommended_slippage_bps(price_impact_bps: u32) -> u32 {
+        price_impact_bps.saturating_add(30).clamp(50, 1000)
     }
 
-    #[tokio::test]
-    #[ignore = "async test"]
-    async fn can_get_reserves_multi() {
-        let path = [*WETH, *USDC, *WETH, *USDC];
-        Library::get_reserves_multi(&*FACTORY, &path).await.unwrap();
+    /// Returns the optimal amounts `(amount_a, amount_b)` to deposit for adding liquidity at the
+    /// current ratio of the reserves, matching the router's `_addLiquidity`.
+    ///
+    /// If the pair has no reserves, the desired amounts are returned as-is. Otherwise, the amount
+    /// of B is quoted from `amount_a_desired`, and if it exceeds `amount_b_desired` the amount of A
+    /// is quoted from `amount_b_desired` instead.
+    pub fn add_liquidity_amounts(
+        amount_a_desired: U256,
+        amount_b_desired: U256,
+        reserve_a: U256,
+        reserve_b: U256,
+    ) -> Result<(U256, U256)> {
+        if reserve_a.is_zero() && reserve_b.is_zero() {
+            return Ok((amount_a_desired, amount_b_desired));
+        }
+        let amount_b_optimal = Self::quote(amount_a_desired, reserve_a, reserve_b)?;
+        if amount_b_optimal <= amount_b_desired {
+            return Ok((amount_a_desired, amount_b_optimal));
+        }
+        let amount_a_optimal = Self::quote(amount_b_desired, reserve_b, reserve_a)?;
+        debug_assert!(amount_a_optimal <= amount_a_desired);
+        Ok((amount_a_optimal, amount_b_desired))
     }
 
-    #[test]
+    /// Returns the amount of liquidity minted by a pair for depositing `amount_a` and `amount_b`,
+    /// given its reserves and total supply, matching the pair's `mint`.
+    ///
+    /// On the first deposit, when `total_supply` is zero, [`MINIMUM_LIQUIDITY`] is subtracted from
+    /// the minted amount and locked forever.
+    ///
+    /// Returns [`Error::InsufficientLiquidity`] if no liquidity would be minted.
+    ///
+    /// [`MINIMUM_LIQUIDITY`]: Self::MINIMUM_LIQUIDITY
+    pub fn liquidity_minted(
+        total_supply: U256,
+        amount_a: U256,
+        amount_b: U256,
+        reserve_a: U256,
+        reserve_b: U256,
+    ) -> Result<U256> {
+        let liquidity = if total_supply.is_zero() {
+            let root = U256::try_from(amount_a.full_mul(amount_b).integer_sqrt())
+                .map_err(|_| Error::InsufficientLiquidity)?;
+            let minimum = U256::from(Self::MINIMUM_LIQUIDITY);
+            if root <= minimum {
+                return Err(Error::InsufficientLiquidity);
+            }
+            root - minimum
+        } else {
+            if reserve_a.is_zero() || reserve_b.is_zero() {
+                return Err(Error::InsufficientLiquidity);
+            }
+            let liquidity_a = amount_a * total_supply / reserve_a;
+            let liquidity_b = amount_b * total_supply / reserve_b;
+            liquidity_a.min(liquidity_b)
+        };
+        if liquidity.is_zero() {
+            return Err(Error::InsufficientLiquidity);
+        }
+        Ok(liquidity)
+    }
+
+    /// Returns the amount of liquidity minted to the protocol fee recipient (`feeTo`) when
+    /// liquidity is next added or removed, given the pair's `kLast`, current reserves and total
+    /// supply, matching the pair's `_mintFee`.
+    ///
+    /// This is 1/6th of the growth in `sqrt(k)` since `kLast`. Returns zero if `k_last` is zero,
+    /// which is the case when the protocol fee is off.
+    pub fn protocol_fee_liquidity(
+        k_last: U256,
+        reserve_a: U256,
+        reserve_b: U256,
+        total_supply: U256,
+    ) -> Result<U256> {
+        Self::protocol_fee_liquidity_with_share(k_last, reserve_a, reserve_b, total_supply, 6)
+    }
+
+    /// Same as [`protocol_fee_liquidity`](Self::protocol_fee_liquidity), but with a protocol fee
+    /// of `1 / share_denominator` of the growth in `sqrt(k)`, for forks which charge a different
+    /// share.
+    ///
+    /// Returns [`Error::InvalidFee`] if `share_denominator` is zero.
+    pub fn protocol_fee_liquidity_with_share(
+        k_last: U256,
+        reserve_a: U256,
+        reserve_b: U256,
+        total_supply: U256,
+        share_denominator: u32,
+    ) -> Result<U256> {
+        if share_denominator == 0 {
+            return Err(Error::InvalidFee);
+        }
+        if k_last.is_zero() {
+            return Ok(U256::zero());
+        }
+
+        let root_k = U256::try_from(reserve_a.full_mul(reserve_b).integer_sqrt())
+            .map_err(|_| Error::InsufficientLiquidity)?;
+        let root_k_last = k_last.integer_sqrt();
+        if root_k <= root_k_last {
+            return Ok(U256::zero());
+        }
+        let numerator = total_supply * (root_k - root_k_last);
+        let denominator = root_k * (share_denominator - 1) + root_k_last;
+        Ok(numerator / denominator)
+    }
+
+    /// Returns the estimated annual percentage rate earned by liquidity providers from swap fees,
+    /// given the volume of the last 24 hours and the total value of the reserves, both in the same
+    /// unit, and the swap fee in basis points.
+    ///
+    /// For example, `0.1` means 10%. Returns zero if `reserves_value` is zero.
+    ///
+    /// The volume can be derived from [`Pair::swap_volume`](super::Pair::swap_volume).
+    pub fn estimate_fee_apr(volume_24h: U256, reserves_value: U256, fee_bps: u32) -> f64 {
+        if reserves_value.is_zero() {
+            return 0.0;
+        }
+        let daily_fees = u256_to_f64(volume_24h) * fee_bps as f64 / 10_000.0;
+        daily_fees * 365.0 / u256_to_f64(reserves_value)
+    }
+
+    /// Returns the constant product `k = reserve_a * reserve_b` of a pair.
+    ///
+    /// Returns [`Error::Overflow`] if the product does not fit in 256 bits, which cannot happen
+    /// with actual reserves since they are stored as `uint112`.
+    pub fn invariant(reserve_a: U256, reserve_b: U256) -> Result<U256> {
+        U256::try_from(reserve_a.full_mul(reserve_b)).map_err(|_| Error::Overflow)
+    }
+
+    /// Returns the relative growth of a pair's [invariant](Self::invariant) from `k_before` to
+    /// `k_after`, for example between two [`PoolSnapshot`](super::PoolSnapshot)s.
+    ///
+    /// For example, `0.1` means 10%. Since swap fees are added to the reserves, `k` grows with the
+    /// fees earned by liquidity providers. Returns zero if `k_before` is zero.
+    pub fn invariant_growth(k_before: U256, k_after: U256) -> f64 {
+        if k_before.is_zero() {
+            return 0.0;
+        }
+        u256_to_f64(k_after) / u256_to_f64(k_before) - 1.0
+    }
+
+    /// Returns the impermanent loss of a liquidity position relative to holding the deposited
+    /// tokens, after the price of one token in terms of the other is multiplied by `price_ratio`.
+    ///
+    /// The loss is `2 * sqrt(price_ratio) / (1 + price_ratio) - 1`, so it is zero or negative, for
+    /// example `-0.057` (5.7%) when the price doubles or halves. Swap fees are not included.
+    /// Returns NaN if `price_ratio` is negative.
+    pub fn impermanent_loss(price_ratio: f64) -> f64 {
+        2.0 * price_ratio.sqrt() / (1.0 + price_ratio) - 1.0
+    }
+
+    /// Returns the fees that a liquidity position must earn to break even with holding the
+    /// deposited tokens, after the price of one token in terms of the other is multiplied by
+    /// `price_ratio_change`, as a fraction of the value of the held tokens.
+    ///
+    /// This is the opposite of the [impermanent loss](Self::impermanent_loss), assuming the fees
+    /// are not reinvested. For example, `0.057` means the position must earn fees worth 5.7% of
+    /// holding when the price doubles or halves. Returns NaN if `price_ratio_change` is negative.
+    pub fn lp_breakeven_fees(price_ratio_change: f64) -> f64 {
+        -Self::impermanent_loss(price_ratio_change)
+    }
+
+    /// Returns the effective price of a swap along a path, as output tokens per input token
+    /// adjusted for their decimals, from the `amounts` returned by
+    /// [`get_amounts_out`](Self::get_amounts_out) or [`get_amounts_in`](Self::get_amounts_in).
+    ///
+    /// For example, `2000.0` for swapping 1 WETH (18 decimals) into 2000 USDC (6 decimals).
+    /// Returns zero if `amounts` is empty or its first amount is zero.
+    pub fn path_price(amounts: &[U256], decimals_in: u8, decimals_out: u8) -> f64 {
+        let (amount_in, amount_out) = match (amounts.first(), amounts.last()) {
+            (Some(amount_in), Some(amount_out)) if !amount_in.is_zero() => (amount_in, amount_out),
+            _ => return 0.0,
+        };
+        let scale = 10f64.powi(decimals_in as i32 - decimals_out as i32);
+        u256_to_f64(*amount_out) / u256_to_f64(*amount_in) * scale
+    }
+
+    /// Same as [`quote`](Self::quote), but rounds the result according to `rounding`.
+    ///
+    /// Only [`RoundingMode::Floor`] matches the on-chain calculations.
+    pub fn quote_rounded(
+        amount_a: U256,
+        reserve_a: U256,
+        reserve_b: U256,
+        rounding: RoundingMode,
+    ) -> Result<U256> {
+        if reserve_a.is_zero() || reserve_b.is_zero() {
+            return Err(Error::InsufficientLiquidity);
+        }
+        Ok(rounding.div(amount_a * reserve_b, reserve_a))
+    }
+
+    /// Given an input amount of an asset and pair reserves, returns the maximum output amount of
+    /// the other asset.
+    pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256) -> Result<U256> {
+        Self::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, Self::FEE_BPS)
+    }
+
+    /// Same as [`get_amount_out`](Self::get_amount_out), but with a custom swap fee, in basis
+    /// points.
+    pub fn get_amount_out_with_fee(
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        fee_bps: u32,
+    ) -> Result<U256> {
+        if reserve_in.is_zero() || reserve_out.is_zero() {
+            return Err(Error::InsufficientLiquidity);
+        }
+        let amount_in_with_fee = amount_in * fee_multiplier(fee_bps)?;
+        let numerator = amount_in_with_fee * reserve_out;
+        let denominator = reserve_in * BPS_U256 + amount_in_with_fee;
+        Ok(numerator / denominator)
+    }
+
+    /// Same as [`get_amount_out`](Self::get_amount_out), but rounds the result according to
+    /// `rounding`.
+    ///
+    /// Only [`RoundingMode::Floor`] matches the on-chain calculations, the other modes must not be
+    /// used for building transactions since the pair would revert.
+    pub fn get_amount_out_rounded(
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        rounding: RoundingMode,
+    ) -> Result<U256> {
+        if reserve_in.is_zero() || reserve_out.is_zero() {
+            return Err(Error::InsufficientLiquidity);
+        }
+        let amount_in_with_fee = amount_in * fee_multiplier(Self::FEE_BPS)?;
+        let numerator = amount_in_with_fee * reserve_out;
+        let denominator = reserve_in * BPS_U256 + amount_in_with_fee;
+        Ok(rounding.div(numerator, denominator))
+    }
+
+    /// Given an output amount of an asset and pair reserves, returns a required input amount of the
+    /// other asset.
+    pub fn get_amount_in(amount_out: U256, reserve_in: U256, reserve_out: U256) -> Result<U256> {
+        if reserve_in.is_zero() || reserve_out.is_zero() {
+            return Err(Error::InsufficientLiquidity);
+        }
+        let numerator = reserve_in * amount_out * 1000;
+        let denominator = (reserve_out - amount_out) * 997;
+        Ok((numerator / denominator) + 1)
+    }
+
+    /// Same as [`get_amount_in`](Self::get_amount_in), but with a custom swap fee, in basis
+    /// points.
+    ///
+    /// Returns [`Error::InsufficientLiquidity`] if `amount_out` is not lower than `reserve_out`.
+    pub fn get_amount_in_with_fee(
+        amount_out: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        fee_bps: u32,
+    ) -> Result<U256> {
+        if reserve_in.is_zero() || reserve_out <= amount_out {
+            return Err(Error::InsufficientLiquidity);
+        }
+        let numerator = reserve_in * amount_out * BPS_U256;
+        let denominator = (reserve_out - amount_out) * fee_multiplier(fee_bps)?;
+        Ok((numerator / denominator) + 1)
+    }
+
+    /// Returns the `(actual_out, required_in)` amounts of buying up to `desired_out` while
+    /// spending at most `max_in`, given the pair reserves and swap fee in basis points.
+    ///
+    /// If `max_in` is enough to buy `desired_out`, it is filled completely for the input amount
+    /// given by [`get_amount_in_with_fee`](Self::get_amount_in_with_fee). Otherwise, the order is
+    /// only partially filled by swapping all of `max_in`.
+    pub fn fillable_output(
+        desired_out: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        max_in: U256,
+        fee_bps: u32,
+    ) -> Result<(U256, U256)> {
+        if reserve_in.is_zero() || reserve_out.is_zero() {
+            return Err(Error::InsufficientLiquidity);
+        }
+        if desired_out < reserve_out {
+            let required_in =
+                Self::get_amount_in_with_fee(desired_out, reserve_in, reserve_out, fee_bps)?;
+            if required_in <= max_in {
+                return Ok((desired_out, required_in));
+            }
+        }
+        let actual_out = Self::get_amount_out_with_fee(max_in, reserve_in, reserve_out, fee_bps)?;
+        Ok((actual_out.min(desired_out), max_in))
+    }
+
+    /// Returns the updated `(reserve_in, reserve_out)` of a pair after swapping `amount_in`,
+    /// without making any external calls.
+    ///
+    /// This can be used to chain multiple hypothetical swaps against the same local pair.
+    pub fn reserves_after_swap(
+        amount_in: U256,
+        reserve_in: U256,
+        reserve_out: U256,
+        fee_bps: u32,
+    ) -> Result<(U256, U256)> {
+        let amount_out =
+            Self::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+        Ok((reserve_in + amount_in, reserve_out - amount_out))
+    }
+
+    /// Returns the output amounts of a batch of `(token_in, amount_in)` swaps executed one after
+    /// the other against the same pair, starting from `initial_reserves`.
+    ///
+    /// Every swap is quoted against the reserves left by the previous ones with
+    /// [`reserves_after_swap`](Self::reserves_after_swap), so it includes the price movement
+    /// caused by the earlier swaps of the batch. Returns [`Error::TokenNotInPair`] if a swap's
+    /// input token is not one of the pair's tokens.
+    pub fn batch_output(
+        swaps: &[(Address, U256)],
+        initial_reserves: PairReserves,
+        fee_bps: u32,
+    ) -> Result<Vec<U256>> {
+        let mut reserves = initial_reserves;
+        swaps
+            .iter()
+            .map(|&(token_in, amount_in)| {
+                let (reserve_in, reserve_out) =
+                    reserves.reserves_for(token_in).ok_or(Error::TokenNotInPair)?;
+                let amount_out =
+                    Self::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+                let (reserve_in, reserve_out) =
+                    Self::reserves_after_swap(amount_in, reserve_in, reserve_out, fee_bps)?;
+                if token_in == reserves.token0 {
+                    (reserves.reserve0, reserves.reserve1) = (reserve_in, reserve_out);
+                } else {
+                    (reserves.reserve1, reserves.reserve0) = (reserve_in, reserve_out);
+                }
+                Ok(amount_out)
+            })
+            .collect()
+    }
+
+    /// Returns the minimum output amounts of swapping `total_amount_in` in `stages` equal
+    /// tranches, executed one after the other against the same pair, with a `slippage_bps`
+    /// tolerance on each tranche.
+    ///
+    /// Every tranche is quoted against the reserves left by the previous ones with
+    /// [`reserves_after_swap`](Self::reserves_after_swap), so later tranches get lower bounds.
+    /// The remainder of the division is added to the last tranche. Returns no amounts if `stages`
+    /// is zero, or [`Error::InvalidSlippage`] if `slippage_bps` is greater than 10000.
+    pub fn staged_min_out(
+        total_amount_in: U256,
+        stages: usize,
+        mut reserve_in: U256,
+        mut reserve_out: U256,
+        fee_bps: u32,
+        slippage_bps: u32,
+    ) -> Result<Vec<U256>> {
+        if slippage_bps > 10_000 {
+            return Err(Error::InvalidSlippage);
+        }
+        if stages == 0 {
+            return Ok(Vec::new());
+        }
+
+        let tranche = total_amount_in / stages;
+        let remainder = total_amount_in % stages;
+        let mut min_outs = Vec::with_capacity(stages);
+        for stage in 0..stages {
+            let amount_in = if stage == stages - 1 { tranche + remainder } else { tranche };
+            let amount_out =
+                Self::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+            min_outs.push(amount_out * (10_000 - slippage_bps) / BPS_U256);
+            (reserve_in, reserve_out) =
+                Self::reserves_after_swap(amount_in, reserve_in, reserve_out, fee_bps)?;
+        }
+        Ok(min_outs)
+    }
+
+    /// Performs chained get_amount_out calculations on any number of pairs.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        path: &[Address],
+    ) -> Result<Vec<U256>> {
+        Self::get_amounts_out_with_pairs(factory, amount_in, path).await.map(|(amounts, _)| amounts)
+    }
+
+    /// Performs [`get_amounts_out`](Self::get_amounts_out), also returning the address of the pair
+    /// used for each hop of the path, as resolved by [`resolve_pairs`](Self::resolve_pairs).
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_with_pairs<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        path: &[Address],
+    ) -> Result<(Vec<U256>, Vec<Address>)> {
+        let len = path.len();
+        if len < 2 {
+            return Err(Error::InvalidPath);
+        }
+
+        let pairs: Vec<_> = path.windows(2).map(|slice| (slice[0], slice[1])).collect();
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+        let reserves = Self::fetch_reserves(factory, &pairs, &addresses).await?;
+        let mut amounts = Vec::with_capacity(len);
+        amounts.push(amount_in);
+        for (i, (reserve_in, reserve_out)) in reserves.into_iter().enumerate() {
+            amounts.push(Self::get_amount_out(amounts[i], reserve_in, reserve_out)?);
+        }
+        Ok((amounts, addresses))
+    }
+
+    /// Performs [`get_amounts_out`](Self::get_amounts_out) for each of the `(amount_in, path)`
+    /// queries, fetching the reserves of all the unique pairs across the queries with only 1 call
+    /// to the client by using [Multicall].
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_batch<M: Middleware>(
+        factory: &Factory<M>,
+        queries: &[(U256, Vec<Address>)],
+    ) -> Result<Vec<Vec<U256>>> {
+        if queries.iter().any(|(_, path)| path.len() < 2) {
+            return Err(Error::InvalidPath);
+        }
+
+        let mut pairs: Vec<_> = queries
+            .iter()
+            .flat_map(|(_, path)| {
+                path.windows(2).map(|slice| Self::sort_tokens(slice[0], slice[1]))
+            })
+            .collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+        if pairs.is_empty() {
+            return Ok(vec![]);
+        }
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+        let reserves = Self::fetch_reserves(factory, &pairs, &addresses).await?;
+        let reserves: HashMap<_, _> = pairs.into_iter().zip(reserves).collect();
+
+        queries
+            .iter()
+            .map(|(amount_in, path)| {
+                let mut amounts = Vec::with_capacity(path.len());
+                amounts.push(*amount_in);
+                for (i, slice) in path.windows(2).enumerate() {
+                    let (a, b) = (slice[0], slice[1]);
+                    let (token0, token1) = Self::sort_tokens(a, b);
+                    let (reserve0, reserve1) = reserves[&(token0, token1)];
+                    let (reserve_in, reserve_out) =
+                        if token0 == a { (reserve0, reserve1) } else { (reserve1, reserve0) };
+                    amounts.push(Self::get_amount_out(amounts[i], reserve_in, reserve_out)?);
+                }
+                Ok(amounts)
+            })
+            .collect()
+    }
+
+    /// Same as [`get_amounts_out`](Self::get_amounts_out), but returns None instead of an error if
+    /// any pair of the path has not been deployed or has no liquidity.
+    ///
+    /// Errors are still returned for an invalid path or a failed call.
+    #[doc(alias = "dry_run_only")]
+    pub async fn try_get_amounts_out<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        path: &[Address],
+    ) -> Result<Option<Vec<U256>>> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+        if !Self::pairs_exist(factory, path).await? {
+            return Ok(None);
+        }
+
+        match Self::get_amounts_out(factory, amount_in, path).await {
+            Err(Error::InsufficientLiquidity) => Ok(None),
+            res => res.map(Some),
+        }
+    }
+
+    /// Returns whether all the pairs of the path have been deployed, using the factory's `getPair`.
+    async fn pairs_exist<M: Middleware>(factory: &Factory<M>, path: &[Address]) -> Result<bool> {
+        let mut multicall =
+            Multicall::new(factory.client(), None).await?.version(MulticallVersion::Multicall);
+        for slice in path.windows(2) {
+            multicall.add_call(factory.contract().get_pair(slice[0], slice[1]), false);
+        }
+
+        for token in multicall.call_raw().await? {
+            let pair: Address = Tokenizable::from_token(token)?;
+            if pair.is_zero() {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns the total value locked in the pair of A and B, valued in `reference` at the
+    /// mid-prices of the pairs of A and B with `reference`. Makes only 1 call to the client by
+    /// using [Multicall], plus 1 call to resolve the pairs' addresses with
+    /// [`PairResolution::OnChain`].
+    ///
+    /// If A or B is `reference`, the other token is valued using the pair itself.
+    #[doc(alias = "dry_run_only")]
+    pub async fn pair_tvl<M: Middleware>(
+        factory: &Factory<M>,
+        a: Address,
+        b: Address,
+        reference: Address,
+    ) -> Result<U256> {
+        if a == reference || b == reference {
+            let (reserve_a, reserve_b) = Self::get_reserves(factory, a, b).await?;
+            let reserve = if a == reference { reserve_a } else { reserve_b };
+            return reserve.checked_mul(2.into()).ok_or(Error::Overflow);
+        }
+
+        let pairs = [(a, b), (a, reference), (b, reference)];
+        let addresses = Self::resolve_pairs(factory, &pairs).await?;
+        let reserves = Self::fetch_reserves(factory, &pairs, &addresses).await?;
+        let (reserve_a, reserve_b) = reserves[0];
+        let value_a = Self::quote(reserve_a, reserves[1].0, reserves[1].1)?;
+        let value_b = Self::quote(reserve_b, reserves[2].0, reserves[2].1)?;
+        value_a.checked_add(value_b).ok_or(Error::Overflow)
+    }
+
+    /// Returns the value of `amount` of `token` in USDC, by routing it through WETH or directly,
+    /// whichever returns more. The addresses of WETH and USDC are taken from the [addressbook].
+    ///
+    /// Returns [`Error::PairNotFound`] if neither route exists.
+    ///
+    /// [addressbook]: crate::contracts::addresses
+    #[cfg(feature = "addresses")]
+    #[doc(alias = "dry_run_only")]
+    pub async fn price_in_usd<M: Middleware>(
+        factory: &Factory<M>,
+        chain: Chain,
+        token: Address,
+        amount: U256,
+    ) -> Result<U256> {
+        use crate::contracts::addresses::try_address;
+
+        let usdc = try_address("USDC", chain).ok_or(Error::MissingAddress("USDC"))?;
+        let weth = try_address("WETH", chain).ok_or(Error::MissingAddress("WETH"))?;
+        if token == usdc {
+            return Ok(amount);
+        }
+
+        let direct = Self::try_get_amounts_out(factory, amount, &[token, usdc]).await?;
+        let through_weth = if token == weth {
+            None
+        } else {
+            Self::try_get_amounts_out(factory, amount, &[token, weth, usdc]).await?
+        };
+        [direct, through_weth]
+            .into_iter()
+            .flatten()
+            .filter_map(|amounts| amounts.last().copied())
+            .max()
+            .ok_or(Error::PairNotFound)
+    }
+
+    /// Same as [`get_amounts_out`](Self::get_amounts_out), but stops as soon as the output amount
+    /// of a hop is lower than `min_intermediate`, returning [`Error::BelowThreshold`].
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_bounded<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        path: &[Address],
+        min_intermediate: U256,
+    ) -> Result<Vec<U256>> {
+        let reserves = Self::get_reserves_multi(factory, path).await?;
+        Self::bounded_amounts_out(amount_in, &reserves, min_intermediate)
+    }
+
+    fn bounded_amounts_out(
+        amount_in: U256,
+        reserves: &[(U256, U256)],
+        min_intermediate: U256,
+    ) -> Result<Vec<U256>> {
+        let mut amounts = Vec::with_capacity(reserves.len() + 1);
+        amounts.push(amount_in);
+        for (hop, &(reserve_in, reserve_out)) in reserves.iter().enumerate() {
+            let amount_out = Self::get_amount_out(amounts[hop], reserve_in, reserve_out)?;
+            if amount_out < min_intermediate {
+                return Err(Error::BelowThreshold { hop });
+            }
+            amounts.push(amount_out);
+        }
+        Ok(amounts)
+    }
+
+    /// Same as [`get_amounts_out`](Self::get_amounts_out), but returns the amounts of each hop
+    /// along with the fee charged by its pair, in terms of the hop's input token.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_with_fee_breakdown<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        path: &[Address],
+    ) -> Result<Vec<HopAmounts>> {
+        let reserves = Self::get_reserves_multi(factory, path).await?;
+        Self::hop_amounts(amount_in, &reserves, Self::FEE_BPS)
+    }
+
+    fn hop_amounts(
+        mut amount_in: U256,
+        reserves: &[(U256, U256)],
+        fee_bps: u32,
+    ) -> Result<Vec<HopAmounts>> {
+        let mut hops = Vec::with_capacity(reserves.len());
+        for &(reserve_in, reserve_out) in reserves {
+            let amount_out =
+                Self::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee_bps)?;
+            let fee = amount_in * fee_bps / BPS_U256;
+            hops.push(HopAmounts { amount_in, amount_out, fee });
+            amount_in = amount_out;
+        }
+        Ok(hops)
+    }
+
+    /// Same as [`get_amounts_out`](Self::get_amounts_out), but returns [`Error::BelowMinimum`] if
+    /// the final output amount is lower than `min_out`.
+    ///
+    /// Unlike a slippage check, `min_out` is an absolute floor, useful for discarding dust routes.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_with_min<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        path: &[Address],
+        min_out: U256,
+    ) -> Result<Vec<U256>> {
+        let amounts = Self::get_amounts_out(factory, amount_in, path).await?;
+        Self::check_min_out(amounts, min_out)
+    }
+
+    fn check_min_out(amounts: Vec<U256>, min_out: U256) -> Result<Vec<U256>> {
+        match amounts.last() {
+            Some(&amount_out) if amount_out < min_out => Err(Error::BelowMinimum { amount_out }),
+            _ => Ok(amounts),
+        }
+    }
+
+    /// Same as [`get_amounts_out`](Self::get_amounts_out), but annotates each amount with the
+    /// respective token's decimals, provided in `decimals`.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_with_decimals<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        path: &[Address],
+        decimals: &[u8],
+    ) -> Result<Vec<(U256, u8)>> {
+        if decimals.len() != path.len() {
+            return Err(Error::DecimalsMismatch);
+        }
+        let amounts = Self::get_amounts_out(factory, amount_in, path).await?;
+        Ok(amounts.into_iter().zip(decimals.iter().copied()).collect())
+    }
+
+    /// Performs chained get_amount_out calculations on the path of `tokens` and formats each amount
+    /// using the respective token's decimals and symbol.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_formatted<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        tokens: &[Erc20<M>],
+    ) -> Result<Vec<String>> {
+        let path: Vec<_> = tokens.iter().map(Erc20::address).collect();
+        let amounts = Self::get_amounts_out(factory, amount_in, &path).await?;
+        Ok(tokens.iter().zip(amounts).map(|(token, amount)| token.format_amount(amount)).collect())
+    }
+
+    /// Performs chained get_amount_in calculations on any number of pairs.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_in<M: Middleware>(
+        factory: &Factory<M>,
+        amount_out: U256,
+        path: &[Address],
+    ) -> Result<Vec<U256>> {
+        let len = path.len();
+        if len < 2 {
+            return Err(Error::InvalidPath);
+        }
+
+        let reserves = Self::get_reserves_multi(factory, path).await?;
+        let mut amounts = vec![U256::zero(); len];
+        amounts[len - 1] = amount_out;
+        for (i, (reserve_in, reserve_out)) in reserves.into_iter().enumerate().rev() {
+            amounts[i] = Self::get_amount_in(amounts[i + 1], reserve_in, reserve_out)?;
+        }
+        Ok(amounts)
+    }
+
+    /// Performs both [`get_amounts_out`](Self::get_amounts_out) for `amount_in` and
+    /// [`get_amounts_in`](Self::get_amounts_in) for `amount_out` on the same path, fetching the
+    /// reserves only once.
+    ///
+    /// Returns the `(amounts_out, amounts_in)` of the forward and backward calculations.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_and_in<M: Middleware>(
+        factory: &Factory<M>,
+        amount_in: U256,
+        amount_out: U256,
+        path: &[Address],
+    ) -> Result<(Vec<U256>, Vec<U256>)> {
+        let len = path.len();
+        if len < 2 {
+            return Err(Error::InvalidPath);
+        }
+
+        let reserves = Self::get_reserves_multi(factory, path).await?;
+        let mut amounts_out = Vec::with_capacity(len);
+        amounts_out.push(amount_in);
+        for (i, &(reserve_in, reserve_out)) in reserves.iter().enumerate() {
+            amounts_out.push(Self::get_amount_out(amounts_out[i], reserve_in, reserve_out)?);
+        }
+        let mut amounts_in = vec![U256::zero(); len];
+        amounts_in[len - 1] = amount_out;
+        for (i, (reserve_in, reserve_out)) in reserves.into_iter().enumerate().rev() {
+            amounts_in[i] = Self::get_amount_in(amounts_in[i + 1], reserve_in, reserve_out)?;
+        }
+        Ok((amounts_out, amounts_in))
+    }
+
+    /// Same as [`get_amounts_out`](Self::get_amounts_out), but calls the router's `getAmountsOut`
+    /// instead of performing the calculations locally if any token of the path is in `exotic`.
+    ///
+    /// This is meant for tokens, like rebasing ones, for which the locally fetched reserves may not
+    /// match what the router uses.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_amounts_out_with_exotic<M: Middleware>(
+        factory: &Factory<M>,
+        router: &Router<M>,
+        amount_in: U256,
+        path: &[Address],
+        exotic: &HashSet<Address>,
+    ) -> Result<Vec<U256>> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+        if path.iter().any(|token| exotic.contains(token)) {
+            Ok(router.contract().get_amounts_out(amount_in, path.to_vec()).call().await?)
+        } else {
+            Self::get_amounts_out(factory, amount_in, path).await
+        }
+    }
+
+    /// Compares the result of [`get_amounts_out`](Self::get_amounts_out) with the router's
+    /// `getAmountsOut`, returning the per-hop discrepancy as `router - library`.
+    ///
+    /// A non-zero discrepancy means that the library's math diverges from the protocol's, for
+    /// example because a fork uses a different swap fee.
+    ///
+    /// Note: the two results are fetched with separate calls, so a block may get mined in between.
+    #[doc(alias = "dry_run_only")]
+    pub async fn assert_matches_router<M: Middleware>(
+        factory: &Factory<M>,
+        router: &Router<M>,
+        amount_in: U256,
+        path: &[Address],
+    ) -> Result<Vec<I256>> {
+        let amounts = Self::get_amounts_out(factory, amount_in, path).await?;
+        let router_amounts =
+            router.contract().get_amounts_out(amount_in, path.to_vec()).call().await?;
+        Ok(router_amounts
+            .into_iter()
+            .zip(amounts)
+            .map(|(a, b)| I256::from_raw(a) - I256::from_raw(b))
+            .collect())
+    }
+}
+
+/// Lossy conversion of a [U256] to a [f64].
+pub(super) fn u256_to_f64(value: U256) -> f64 {
+    value.0.iter().rev().fold(0.0, |acc, &limb| acc * 2f64.powi(64) + limb as f64)
+}
+
+/// Returns `10_000 - fee_bps`, or an error if the fee is not lower than 100%.
+#[inline]
+pub(super) fn fee_multiplier(fee_bps: u32) -> Result<U256> {
+    if fee_bps >= 10_000 {
+        return Err(Error::InvalidFee);
+    }
+    Ok(BPS_U256 - fee_bps)
+}
+
+#[cfg(all(test, feature = "addresses"))]
+mod tests {
+    use super::*;
+    use crate::ProtocolType;
+    use ethers_contract::Lazy;
+    use ethers_core::types::Chain;
+    use ethers_providers::{Http, Provider, MAINNET};
+
+    static FACTORY: Lazy<Factory<Provider<Http>>> = Lazy::new(|| {
+        Factory::new_with_chain(MAINNET.provider().into(), Chain::Mainnet, ProtocolType::UniswapV2)
+            .unwrap()
+    });
+    static WETH: Lazy<Address> =
+        Lazy::new(|| "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap());
+    static USDC: Lazy<Address> =
+        Lazy::new(|| "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap());
+    static WETH_USDC: Lazy<Address> =
+        Lazy::new(|| "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc".parse().unwrap());
+
+    #[test]
+    fn can_sort_tokens() {
+        let (a, b) = (Address::random(), Address::random());
+        let (res_a, res_b) = Library::sort_tokens(a, b);
+        match a.cmp(&b) {
+            Ordering::Less | Ordering::Equal => {
+                assert_eq!(res_a, a);
+                assert_eq!(res_b, b);
+            }
+            Ordering::Greater => {
+                assert_eq!(res_a, b);
+                assert_eq!(res_b, a);
+            }
+        }
+    }
+
+    #[test]
+    fn can_sort_tokens_with_flag() {
+        let (a, b) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        assert_eq!(Library::sort_tokens_with_flag(a, b).unwrap(), (a, b, false));
+        assert_eq!(Library::sort_tokens_with_flag(b, a).unwrap(), (a, b, true));
+        let res = Library::sort_tokens_with_flag(a, a);
+        assert!(matches!(res.unwrap_err(), Error::IdenticalAddresses));
+    }
+
+    #[test]
+    fn can_check_base_token0() {
+        let (a, b) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        assert!(Library::is_base_token0(a, b).unwrap());
+        assert!(!Library::is_base_token0(b, a).unwrap());
+        assert_eq!(Library::is_base_token0(a, b).unwrap(), Library::sort_tokens(a, b).0 == a);
+        assert!(matches!(Library::is_base_token0(a, a).unwrap_err(), Error::IdenticalAddresses));
+    }
+
+    #[test]
+    fn can_get_reserve_for_token() {
+        let tokens = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        let reserves = (U256::from(100), U256::from(200));
+        assert_eq!(Library::reserve_for_token(tokens.0, tokens, reserves).unwrap(), reserves.0);
+        assert_eq!(Library::reserve_for_token(tokens.1, tokens, reserves).unwrap(), reserves.1);
+        let res = Library::reserve_for_token(Address::repeat_byte(0x33), tokens, reserves);
+        assert!(matches!(res.unwrap_err(), Error::TokenNotInPair));
+    }
+
+    #[test]
+    fn can_get_pair_for() {
+        assert_eq!(Library::pair_for(&*FACTORY, *WETH, *USDC), *WETH_USDC);
+    }
+
+    async fn get_weth_usdc_reserves() -> (U256, U256) {
+        Library::get_reserves(&*FACTORY, *WETH, *USDC).await.unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_get_reserves() {
+        get_weth_usdc_reserves().await;
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_get_reserves_with_timestamp() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b] = MockReserves::tokens();
+        mock.set_pair_reserves(&factory, a, 1000, b, 2000);
+
+        let (reserve_a, reserve_b, _) =
+            Library::get_reserves_with_timestamp(&factory, a, b).await.unwrap();
+        assert_eq!((reserve_a, reserve_b), (1000.into(), 2000.into()));
+        let (reserve_b, reserve_a, _) =
+            Library::get_reserves_with_timestamp(&factory, b, a).await.unwrap();
+        assert_eq!((reserve_a, reserve_b), (1000.into(), 2000.into()));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_get_amounts_out_and_in() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b, c] = MockReserves::tokens();
+        mock.set_pair_reserves(&factory, a, 1_000_000, b, 2_000_000)
+            .set_pair_reserves(&factory, b, 3_000_000, c, 1_000_000);
+        let path = [a, b, c];
+
+        let (amounts_out, amounts_in) =
+            Library::get_amounts_out_and_in(&factory, 1000.into(), 500.into(), &path)
+                .await
+                .unwrap();
+        let calls = mock.requested_methods().iter().filter(|m| *m == "eth_call").count();
+        assert_eq!(calls, 1);
+        assert_eq!(
+            amounts_out,
+            Library::get_amounts_out(&factory, 1000.into(), &path).await.unwrap()
+        );
+        assert_eq!(amounts_in, Library::get_amounts_in(&factory, 500.into(), &path).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_get_pair_tvl() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b, usd] = MockReserves::tokens();
+        // 2 usd per a, 5 usd per b
+        mock.set_pair_reserves(&factory, a, 1000, b, 400)
+            .set_pair_reserves(&factory, a, 100, usd, 200)
+            .set_pair_reserves(&factory, b, 100, usd, 500);
+
+        assert_eq!(Library::pair_tvl(&factory, a, b, usd).await.unwrap(), 4000.into());
+        assert_eq!(Library::pair_tvl(&factory, a, usd, usd).await.unwrap(), 400.into());
+        assert_eq!(Library::pair_tvl(&factory, usd, b, usd).await.unwrap(), 1000.into());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_build_reserves_multicall() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b, c] = MockReserves::tokens();
+        mock.set_pair_reserves(&factory, a, 1000, b, 2000)
+            .set_pair_reserves(&factory, b, 3000, c, 1000);
+
+        let path = [c, b, a];
+        let (mut multicall, decoder) =
+            Library::build_reserves_multicall(&factory, &path).await.unwrap();
+        assert_eq!(decoder.len(), 2);
+
+        // append an unrelated call
+        let pair = IUniswapV2Pair::new(Library::pair_for(&factory, a, b), factory.client());
+        multicall.add_call(pair.get_reserves(), false);
+
+        let tokens = multicall.call_raw().await.unwrap();
+        assert_eq!(tokens.len(), 3);
+        let reserves = decoder.decode(&tokens).unwrap();
+        assert_eq!(reserves, Library::get_reserves_multi(&factory, &path).await.unwrap());
+        assert_eq!(reserves, [(1000.into(), 3000.into()), (2000.into(), 1000.into())]);
+
+        // c > b and b > a, so both hops swap token1 for token0
+        assert_eq!(decoder.orientation(), [true, true]);
+        let oriented =
+            Library::get_reserves_multi_with_orientation(&factory, &[a, b, c]).await.unwrap();
+        assert_eq!(
+            oriented,
+            [((1000.into(), 2000.into()), false), ((3000.into(), 1000.into()), false)]
+        );
+    }
+
+    #[test]
+    fn can_decode_reserves_encoding() {
+        let reserves = |a: U256, b: U256| (a, b, 1u32).into_token();
+        let wide = U256::one() << 200;
+        let tokens = [
+            reserves(1.into(), 2.into()),
+            Token::Tuple(vec![Token::Bool(true), reserves(wide, 2.into())]),
+        ];
+        let decoder = ReservesDecoder { sorted: vec![false, true] };
+        let decoded = decoder.decode_with_encoding(&tokens).unwrap();
+        assert_eq!(
+            decoded,
+            [
+                ((1.into(), 2.into()), ReservesEncoding::Uint112),
+                ((2.into(), wide), ReservesEncoding::Uint256)
+            ]
+        );
+        assert!(decoder.decode(&tokens[..1]).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_get_reserves_multi() {
+        let path = [*WETH, *USDC, *WETH, *USDC];
+        Library::get_reserves_multi(&*FACTORY, &path).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_get_amounts_out_batch() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b, c] = MockReserves::tokens();
+        let base = 10u128.pow(18);
+        mock.set_pair_reserves(&factory, a, 1000 * base, b, 2000 * base).set_pair_reserves(
+            &factory,
+            b,
+            3000 * base,
+            c,
+            1000 * base,
+        );
+
+        let amount = U256::exp10(18);
+        let queries = vec![(amount, vec![a, b, c]), (amount, vec![c, b]), (amount * 2, vec![b, a])];
+        let amounts = Library::get_amounts_out_batch(&factory, &queries).await.unwrap();
+        assert_eq!(amounts.len(), 3);
+        for ((amount_in, path), amounts) in queries.iter().zip(&amounts) {
+            assert_eq!(
+                *amounts,
+                Library::get_amounts_out(&factory, *amount_in, path).await.unwrap()
+            );
+        }
+
+        let res = Library::get_amounts_out_batch(&factory, &[(amount, vec![a])]).await;
+        assert!(matches!(res.unwrap_err(), Error::InvalidPath));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_get_amounts_out_with_pairs() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b, c] = MockReserves::tokens();
+        mock.set_pair_reserves(&factory, a, 1000, b, 2000)
+            .set_pair_reserves(&factory, b, 3000, c, 1000);
+
+        let path = [a, b, c];
+        let (amounts, pairs) =
+            Library::get_amounts_out_with_pairs(&factory, 100.into(), &path).await.unwrap();
+        assert_eq!(amounts, Library::get_amounts_out(&factory, 100.into(), &path).await.unwrap());
+        assert_eq!(pairs, [Library::pair_for(&factory, a, b), Library::pair_for(&factory, b, c)]);
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_resolve_pairs_on_chain() {
+        let mut factory = FACTORY.clone();
+        factory.set_pair_resolution(PairResolution::OnChain);
+        let pairs = [(*WETH, *USDC), (*USDC, *WETH)];
+        let addresses = Library::resolve_pairs(&factory, &pairs).await.unwrap();
+        assert_eq!(addresses, vec![*WETH_USDC; 2]);
+
+        let res = Library::resolve_pairs(&factory, &[(*WETH, Address::repeat_byte(0x11))]).await;
+        assert!(matches!(res.unwrap_err(), Error::PairNotFound));
+
+        let path = [*WETH, *USDC, *WETH];
+        let reserves = Library::get_reserves_multi(&factory, &path).await.unwrap();
+        assert_eq!(reserves[0], (reserves[1].1, reserves[1].0));
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_get_protocol_fees_active() {
+        let sushiswap = Factory::new_with_chain(
+            MAINNET.provider().into(),
+            Chain::Mainnet,
+            ProtocolType::Sushiswap,
+        )
+        .unwrap();
+        let factories = [FACTORY.clone(), sushiswap];
+        let active = Library::protocol_fees_active(&factories).await.unwrap();
+        assert_eq!(active, vec![false, true]);
+
+        assert!(Library::protocol_fees_active::<Provider<Http>>(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_validate_path_on_chain() {
+        let tokens = Library::pair_tokens(FACTORY.client(), *WETH_USDC).await.unwrap();
+        assert_eq!(tokens, (*USDC, *WETH));
+
+        Library::validate_path_on_chain(&*FACTORY, &[*WETH, *USDC, *WETH]).await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_get_amounts_out_with_decimals() {
+        let amount_in = U256::exp10(18);
+        let path = [*WETH, *USDC];
+        let amounts =
+            Library::get_amounts_out_with_decimals(&*FACTORY, amount_in, &path, &[18, 6]).await;
+        let amounts = amounts.unwrap();
+        assert_eq!(amounts[0], (amount_in, 18));
+        assert_eq!(amounts[1].1, 6);
+
+        let res = Library::get_amounts_out_with_decimals(&*FACTORY, amount_in, &path, &[18]).await;
+        assert!(matches!(res.unwrap_err(), Error::DecimalsMismatch));
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_get_price_in_usd() {
+        let amount = U256::exp10(6);
+        let price = Library::price_in_usd(&*FACTORY, Chain::Mainnet, *USDC, amount).await.unwrap();
+        assert_eq!(price, amount);
+
+        let amount = U256::exp10(18);
+        let price = Library::price_in_usd(&*FACTORY, Chain::Mainnet, *WETH, amount).await.unwrap();
+        let amounts = Library::get_amounts_out(&*FACTORY, amount, &[*WETH, *USDC]).await.unwrap();
+        assert_eq!(price, amounts[1]);
+
+        let dai = crate::contracts::addresses::address("DAI", Chain::Mainnet);
+        let price = Library::price_in_usd(&*FACTORY, Chain::Mainnet, dai, amount).await.unwrap();
+        assert!(!price.is_zero());
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_try_get_amounts_out() {
+        let amount_in = U256::exp10(18);
+        let path = [*WETH, *USDC];
+        let amounts = Library::try_get_amounts_out(&*FACTORY, amount_in, &path).await.unwrap();
+        assert_eq!(amounts.unwrap().len(), 2);
+
+        let path = [*WETH, Address::repeat_byte(0x11), *USDC];
+        let amounts = Library::try_get_amounts_out(&*FACTORY, amount_in, &path).await.unwrap();
+        assert_eq!(amounts, None);
+
+        let res = Library::try_get_amounts_out(&*FACTORY, amount_in, &path[..1]).await;
+        assert!(matches!(res.unwrap_err(), Error::InvalidPath));
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_get_pair_reserves_multi() {
+        let path = [*WETH, *USDC, *WETH];
+        let reserves = Library::get_pair_reserves_multi(&*FACTORY, &path).await.unwrap();
+        assert_eq!(reserves.len(), 2);
+        assert_eq!(reserves[0], reserves[1]);
+
+        let pair = reserves[0];
+        assert_eq!(pair.pair, *WETH_USDC);
+        assert_eq!((pair.token0, pair.token1), (*USDC, *WETH));
+        assert_eq!(pair.reserves_for(*WETH), Some((pair.reserve1, pair.reserve0)));
+        assert_eq!(pair.reserves_for(Address::zero()), None);
+    }
+
+    #[test]
     fn can_quote() {
         let base = U256::exp10(18);
         let amount_a = U256::from(100) * base;
@@ -261,6 +1943,556 @@ mod tests {
         assert_eq!(amount_b, (amount_a * reserve_b) / reserve_a);
     }
 
+    #[test]
+    fn can_get_amount_out_with_fee() {
+        let base = U256::exp10(18);
+        let amount_in = U256::from(3) * base;
+        let reserve_in = U256::from(1000) * base;
+        let reserve_out = U256::from(5000) * base;
+
+        // 30 bps is the same as 997 / 1000
+        let amount_out = Library::get_amount_out(amount_in, reserve_in, reserve_out).unwrap();
+        let expected = (amount_in * 997 * reserve_out) / (reserve_in * 1000 + amount_in * 997);
+        assert_eq!(amount_out, expected);
+
+        let res = Library::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, 10_000);
+        assert!(matches!(res.unwrap_err(), Error::InvalidFee));
+
+        let no_fee =
+            Library::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, 0).unwrap();
+        assert!(no_fee > amount_out);
+    }
+
+    #[test]
+    fn can_get_bounded_amounts_out() {
+        let base = U256::exp10(18);
+        let reserves = [
+            (U256::from(1000) * base, U256::from(5000) * base),
+            (U256::from(5000) * base, U256::from(10) * base),
+        ];
+        let amount_in = base;
+
+        let amounts = Library::bounded_amounts_out(amount_in, &reserves, U256::zero()).unwrap();
+        assert_eq!(amounts.len(), 3);
+        assert!(amounts[1] > U256::from(4) * base);
+        assert!(amounts[2] < base / 100);
+
+        let res = Library::bounded_amounts_out(amount_in, &reserves, base / 100);
+        assert!(matches!(res.unwrap_err(), Error::BelowThreshold { hop: 1 }));
+
+        let res = Library::bounded_amounts_out(amount_in, &reserves, U256::from(5) * base);
+        assert!(matches!(res.unwrap_err(), Error::BelowThreshold { hop: 0 }));
+    }
+
+    #[test]
+    fn can_get_hop_amounts() {
+        let base = U256::exp10(18);
+        let reserves = [
+            (U256::from(1000) * base, U256::from(5000) * base),
+            (U256::from(5000) * base, U256::from(10) * base),
+        ];
+        let hops = Library::hop_amounts(base, &reserves, Library::FEE_BPS).unwrap();
+        assert_eq!(hops.len(), 2);
+        assert_eq!(hops[0].fee, base * 3 / 1000);
+        assert_eq!(hops[0].amount_out, hops[1].amount_in);
+        assert_eq!(hops[1].fee, hops[1].amount_in * 3 / 1000);
+
+        let amounts = Library::bounded_amounts_out(base, &reserves, U256::zero()).unwrap();
+        assert_eq!(hops[1].amount_out, amounts[2]);
+    }
+
+    #[test]
+    fn can_get_batch_output() {
+        let base = U256::exp10(18);
+        let (token0, token1) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        let reserves = PairReserves {
+            token0,
+            token1,
+            reserve0: U256::from(1000) * base,
+            reserve1: U256::from(2000) * base,
+            pair: Address::zero(),
+        };
+
+        let swaps = [(token0, base * 10), (token0, base * 10), (token1, base * 50)];
+        let outputs = Library::batch_output(&swaps, reserves, 30).unwrap();
+        assert_eq!(outputs.len(), 3);
+        let first =
+            Library::get_amount_out_with_fee(base * 10, reserves.reserve0, reserves.reserve1, 30);
+        assert_eq!(outputs[0], first.unwrap());
+        // the second swap moves the price further
+        assert!(outputs[1] < outputs[0]);
+
+        let (mut reserve0, mut reserve1) = (reserves.reserve0, reserves.reserve1);
+        for _ in 0..2 {
+            (reserve0, reserve1) =
+                Library::reserves_after_swap(base * 10, reserve0, reserve1, 30).unwrap();
+        }
+        let third = Library::get_amount_out_with_fee(base * 50, reserve1, reserve0, 30).unwrap();
+        assert_eq!(outputs[2], third);
+
+        let res = Library::batch_output(&[(Address::zero(), base)], reserves, 30);
+        assert!(matches!(res.unwrap_err(), Error::TokenNotInPair));
+        assert!(Library::batch_output(&[], reserves, 30).unwrap().is_empty());
+    }
+
+    #[test]
+    fn can_get_staged_min_out() {
+        let (reserve_in, reserve_out) = (U256::exp10(21), U256::exp10(21));
+        let total = U256::exp10(20) + 2;
+        let min_outs = Library::staged_min_out(total, 3, reserve_in, reserve_out, 30, 50).unwrap();
+        assert_eq!(min_outs.len(), 3);
+        assert!(min_outs.windows(2).all(|w| w[0] > w[1]));
+
+        let tranche = total / 3;
+        let first = Library::get_amount_out_with_fee(tranche, reserve_in, reserve_out, 30).unwrap();
+        assert_eq!(min_outs[0], first * 9950 / 10000);
+
+        // the fees of the earlier tranches stay in the pair, so staging costs some output
+        let exact = Library::staged_min_out(total, 3, reserve_in, reserve_out, 30, 0).unwrap();
+        let single = Library::get_amount_out_with_fee(total, reserve_in, reserve_out, 30).unwrap();
+        let staged = exact.iter().fold(U256::zero(), |acc, amount| acc + amount);
+        assert!(staged < single);
+
+        assert!(Library::staged_min_out(total, 0, reserve_in, reserve_out, 30, 50)
+            .unwrap()
+            .is_empty());
+        let res = Library::staged_min_out(total, 3, reserve_in, reserve_out, 30, 10_001);
+        assert!(matches!(res.unwrap_err(), Error::InvalidSlippage));
+    }
+
+    #[test]
+    fn can_get_fillable_output() {
+        let (reserve_in, reserve_out) = (U256::exp10(21), U256::exp10(21));
+        let desired_out = U256::exp10(18);
+        let required_in =
+            Library::get_amount_in_with_fee(desired_out, reserve_in, reserve_out, 30).unwrap();
+
+        let filled =
+            Library::fillable_output(desired_out, reserve_in, reserve_out, U256::MAX >> 128, 30);
+        assert_eq!(filled.unwrap(), (desired_out, required_in));
+        let filled =
+            Library::fillable_output(desired_out, reserve_in, reserve_out, required_in, 30);
+        assert_eq!(filled.unwrap(), (desired_out, required_in));
+
+        let max_in = required_in / 2;
+        let (actual_out, amount_in) =
+            Library::fillable_output(desired_out, reserve_in, reserve_out, max_in, 30).unwrap();
+        assert_eq!(amount_in, max_in);
+        assert!(actual_out < desired_out);
+        assert_eq!(
+            actual_out,
+            Library::get_amount_out_with_fee(max_in, reserve_in, reserve_out, 30).unwrap()
+        );
+
+        // more than the reserve can never be filled completely
+        let (actual_out, _) =
+            Library::fillable_output(reserve_out, reserve_in, reserve_out, reserve_in, 30).unwrap();
+        assert!(actual_out < reserve_out);
+    }
+
+    #[test]
+    fn can_get_output_band() {
+        let (reserve_in, reserve_out) = (U256::exp10(21), U256::exp10(21));
+        let amount_in = U256::exp10(18);
+        let (min, expected, max) =
+            Library::output_band(amount_in, reserve_in, reserve_out, 30, 50).unwrap();
+        assert_eq!(
+            expected,
+            Library::get_amount_out_with_fee(amount_in, reserve_in, reserve_out, 30).unwrap()
+        );
+        assert_eq!(min, expected * 9950 / 10000);
+        assert_eq!(max, expected * 10050 / 10000);
+
+        let (min, _, _) = Library::output_band(amount_in, reserve_in, reserve_out, 30, 0).unwrap();
+        assert_eq!(min, expected);
+        let res = Library::output_band(amount_in, reserve_in, reserve_out, 30, 10_001);
+        assert!(matches!(res.unwrap_err(), Error::InvalidSlippage));
+    }
+
+    #[test]
+    fn can_get_recommended_slippage() {
+        assert_eq!(Library::recommended_slippage_bps(0), 50);
+        assert_eq!(Library::recommended_slippage_bps(100), 130);
+        assert_eq!(Library::recommended_slippage_bps(5000), 1000);
+        assert_eq!(Library::recommended_slippage_bps(u32::MAX), 1000);
+    }
+
+    #[test]
+    fn can_check_min_out() {
+        let amounts = vec![U256::from(100), U256::from(50)];
+        assert_eq!(Library::check_min_out(amounts.clone(), 50.into()).unwrap(), amounts);
+        let res = Library::check_min_out(amounts, 51.into());
+        assert!(
+            matches!(res.unwrap_err(), Error::BelowMinimum { amount_out } if amount_out == 50.into())
+        );
+    }
+
+    #[test]
+    fn can_quote_with_sanity() {
+        let base = U256::exp10(18);
+        let (reserve_in, reserve_out) = (U256::from(1000) * base, U256::from(2000) * base);
+        let reference_price = U256::from(2) * PRICE_SCALE;
+        let quote = |max_deviation_bps| {
+            Library::quote_with_sanity(
+                base,
+                reserve_in,
+                reserve_out,
+                Library::FEE_BPS,
+                reference_price,
+                max_deviation_bps,
+            )
+        };
+
+        // ~30 bps of fee and ~10 bps of price impact
+        let amount_out = quote(50).unwrap();
+        assert_eq!(
+            amount_out,
+            Library::get_amount_out_with_fee(base, reserve_in, reserve_out, Library::FEE_BPS)
+                .unwrap()
+        );
+        assert!(matches!(quote(30).unwrap_err(), Error::PriceDeviation { deviation_bps: 39 }));
+
+        let res = Library::quote_with_sanity(
+            U256::zero(),
+            reserve_in,
+            reserve_out,
+            Library::FEE_BPS,
+            reference_price,
+            50,
+        );
+        assert!(matches!(res.unwrap_err(), Error::InsufficientInputAmount));
+    }
+
+    #[test]
+    fn can_get_price_impact() {
+        let base = U256::exp10(18);
+        let (reserve_in, reserve_out) = (U256::from(1000) * base, U256::from(5000) * base);
+        let impact = |amount| {
+            Library::price_impact_bps(amount, reserve_in, reserve_out, Library::FEE_BPS).unwrap()
+        };
+        assert_eq!(impact(U256::zero()), Library::FEE_BPS);
+        // 1% of the reserves: 1 - 0.997 / 1.00997
+        assert_eq!(impact(U256::from(10) * base), 129);
+        assert!(impact(U256::from(100) * base) > impact(U256::from(10) * base));
+
+        for max_impact in [30, 50, 100, 129, 1000, 9999] {
+            let amount =
+                Library::max_amount_for_impact(reserve_in, reserve_out, max_impact, 30).unwrap();
+            assert!(impact(amount) <= max_impact);
+            assert!(impact(amount + amount / 1000 + 1) >= max_impact);
+        }
+
+        let res = Library::max_amount_for_impact(reserve_in, reserve_out, 10, 30);
+        assert_eq!(res.unwrap(), U256::zero());
+        let res = Library::max_amount_for_impact(reserve_in, reserve_out, 10_000, 30);
+        assert!(matches!(res.unwrap_err(), Error::InvalidPriceImpact));
+    }
+
+    #[test]
+    fn can_get_amount_to_reach_ratio() {
+        let base = U256::exp10(18);
+        let (reserve_a, reserve_b) = (U256::from(1000) * base, U256::from(2000) * base);
+        let ratio = |a: U256, b: U256| b * PRICE_SCALE / a;
+
+        // from 2 to 1.5 B per A by selling A
+        let target = PRICE_SCALE * 3 / 2;
+        let (sell_a, amount) =
+            Library::amount_to_reach_ratio(reserve_a, reserve_b, target, 30).unwrap();
+        assert!(sell_a);
+        let (a, b) = Library::reserves_after_swap(amount, reserve_a, reserve_b, 30).unwrap();
+        assert!(ratio(a, b) >= target && ratio(a, b) - target < 1000.into());
+        let (a, b) = Library::reserves_after_swap(amount + base, reserve_a, reserve_b, 30).unwrap();
+        assert!(ratio(a, b) < target);
+
+        // from 2 to 3 B per A by selling B
+        let target = PRICE_SCALE * 3;
+        let (sell_a, amount) =
+            Library::amount_to_reach_ratio(reserve_a, reserve_b, target, 30).unwrap();
+        assert!(!sell_a);
+        let (b, a) = Library::reserves_after_swap(amount, reserve_b, reserve_a, 30).unwrap();
+        assert!(ratio(a, b) <= target && target - ratio(a, b) < 1000.into());
+
+        let current = ratio(reserve_a, reserve_b);
+        let (_, amount) =
+            Library::amount_to_reach_ratio(reserve_a, reserve_b, current, 30).unwrap();
+        assert!(amount.is_zero());
+        let res = Library::amount_to_reach_ratio(reserve_a, reserve_b, U256::zero(), 30);
+        assert!(matches!(res.unwrap_err(), Error::InvalidRatio));
+    }
+
+    #[test]
+    fn can_get_input_for_output_within_impact() {
+        let base = U256::exp10(18);
+        let (reserve_in, reserve_out) = (U256::from(1000) * base, U256::from(5000) * base);
+        let input = |amount_out: U256, max_impact| {
+            Library::input_for_output_within_impact(
+                amount_out,
+                reserve_in,
+                reserve_out,
+                max_impact,
+                Library::FEE_BPS,
+            )
+            .unwrap()
+        };
+
+        let amount_out = U256::from(50) * base;
+        let amount_in = Library::get_amount_in(amount_out, reserve_in, reserve_out).unwrap();
+        assert_eq!(
+            Library::get_amount_in_with_fee(amount_out, reserve_in, reserve_out, Library::FEE_BPS)
+                .unwrap(),
+            amount_in
+        );
+        let impact = Library::price_impact_bps(amount_in, reserve_in, reserve_out, 30).unwrap();
+        assert_eq!(input(amount_out, impact), Some(amount_in));
+        assert_eq!(input(amount_out, impact - 1), None);
+        assert_eq!(input(reserve_out, 9999), None);
+
+        let res = Library::input_for_output_within_impact(
+            amount_out,
+            reserve_in,
+            reserve_out,
+            10_000,
+            30,
+        );
+        assert!(matches!(res.unwrap_err(), Error::InvalidPriceImpact));
+    }
+
+    #[test]
+    fn can_get_realized_slippage() {
+        let slippage = |expected: u64, actual: u64| {
+            Library::realized_slippage_bps(expected.into(), actual.into())
+        };
+        assert_eq!(slippage(10_000, 10_000), 0);
+        assert_eq!(slippage(10_000, 9_950), 50);
+        assert_eq!(slippage(10_000, 10_025), -25);
+        assert_eq!(slippage(10_000, 0), 10_000);
+        // rounds towards zero
+        assert_eq!(slippage(30_000, 29_999), 0);
+        assert_eq!(slippage(30_000, 30_001), 0);
+        assert_eq!(slippage(0, 100), 0);
+        assert_eq!(Library::realized_slippage_bps(U256::one(), U256::MAX), -i32::MAX);
+    }
+
+    #[test]
+    fn can_get_add_liquidity_amounts() {
+        let amounts = |a: u64, b: u64, reserve_a: u64, reserve_b: u64| {
+            Library::add_liquidity_amounts(a.into(), b.into(), reserve_a.into(), reserve_b.into())
+                .map(|(a, b)| (a.as_u64(), b.as_u64()))
+        };
+
+        // empty pair
+        assert_eq!(amounts(100, 300, 0, 0).unwrap(), (100, 300));
+        // 1 A = 2 B: B is quoted from A
+        assert_eq!(amounts(100, 300, 1000, 2000).unwrap(), (100, 200));
+        // A is quoted from B
+        assert_eq!(amounts(100, 150, 1000, 2000).unwrap(), (75, 150));
+        assert_eq!(amounts(100, 200, 1000, 2000).unwrap(), (100, 200));
+
+        assert!(matches!(amounts(100, 100, 1000, 0).unwrap_err(), Error::InsufficientLiquidity));
+    }
+
+    #[test]
+    fn can_get_liquidity_minted() {
+        let zero = U256::zero();
+        let minted = |total_supply: u64, a: u64, b: u64, reserve_a: u64, reserve_b: u64| {
+            Library::liquidity_minted(
+                total_supply.into(),
+                a.into(),
+                b.into(),
+                reserve_a.into(),
+                reserve_b.into(),
+            )
+        };
+
+        // bootstrap: sqrt(4000 * 1000) - 1000
+        assert_eq!(minted(0, 4000, 1000, 0, 0).unwrap(), 1000.into());
+        // sqrt(1001 * 1001) = 1001
+        assert_eq!(minted(0, 1001, 1001, 0, 0).unwrap(), 1.into());
+        // sqrt(1000 * 1000) = 1000 is not enough
+        assert!(matches!(minted(0, 1000, 1000, 0, 0).unwrap_err(), Error::InsufficientLiquidity));
+        assert!(matches!(minted(0, 1000, 1002, 0, 0).unwrap_err(), Error::InsufficientLiquidity));
+
+        // proportional to the smaller share
+        assert_eq!(minted(1000, 100, 300, 1000, 2000).unwrap(), 100.into());
+        assert!(matches!(
+            minted(1000, 0, 300, 1000, 2000).unwrap_err(),
+            Error::InsufficientLiquidity
+        ));
+        assert!(Library::liquidity_minted(1.into(), 1.into(), 1.into(), zero, zero).is_err());
+    }
+
+    #[test]
+    fn can_get_protocol_fee_liquidity() {
+        let base = U256::exp10(18);
+        let total_supply = U256::from(1000) * base;
+        // sqrt(k) grew from 1000 to 1100
+        let k_last = (U256::from(1000) * base).pow(2.into());
+        let (reserve_a, reserve_b) = (U256::from(1100) * base, U256::from(1100) * base);
+
+        let minted =
+            Library::protocol_fee_liquidity(k_last, reserve_a, reserve_b, total_supply).unwrap();
+        // 1000 * 100 / (1100 * 5 + 1000)
+        assert_eq!(minted, total_supply * 100 / 6500);
+
+        // the protocol's share of the pool is 1/6th of the growth
+        let share = minted * base / (total_supply + minted);
+        let growth = (reserve_a - U256::from(1000) * base) * base / reserve_a;
+        assert_eq!(share, growth / 6);
+
+        let res = Library::protocol_fee_liquidity(U256::zero(), reserve_a, reserve_b, total_supply);
+        assert_eq!(res.unwrap(), U256::zero());
+        let res = Library::protocol_fee_liquidity(k_last, base, base, total_supply);
+        assert_eq!(res.unwrap(), U256::zero());
+        let res = Library::protocol_fee_liquidity_with_share(
+            k_last,
+            reserve_a,
+            reserve_b,
+            total_supply,
+            0,
+        );
+        assert!(matches!(res.unwrap_err(), Error::InvalidFee));
+    }
+
+    #[test]
+    fn can_get_invariant() {
+        let reserves =
+            PairReserves { reserve0: 1000.into(), reserve1: 4000.into(), ..Default::default() };
+        assert_eq!(reserves.invariant().unwrap(), 4_000_000.into());
+
+        let max_reserve = U256::from(u128::MAX);
+        assert_eq!(
+            Library::invariant(max_reserve, max_reserve).unwrap(),
+            max_reserve * max_reserve
+        );
+        let res = Library::invariant(U256::MAX, 2.into());
+        assert!(matches!(res.unwrap_err(), Error::Overflow));
+
+        let growth = Library::invariant_growth(4_000_000.into(), 4_040_000.into());
+        assert!((growth - 0.01).abs() < 1e-12);
+        assert_eq!(Library::invariant_growth(U256::zero(), 1.into()), 0.0);
+    }
+
+    #[test]
+    fn can_get_lp_breakeven_fees() {
+        assert_eq!(Library::impermanent_loss(1.0), 0.0);
+        assert_eq!(Library::lp_breakeven_fees(1.0), 0.0);
+
+        let fees = Library::lp_breakeven_fees(2.0);
+        assert!((fees - 0.057_190_958).abs() < 1e-9);
+        assert!((Library::lp_breakeven_fees(0.5) - fees).abs() < 1e-12);
+        assert!((Library::impermanent_loss(4.0) + 0.2).abs() < 1e-12);
+        assert!(Library::lp_breakeven_fees(-1.0).is_nan());
+    }
+
+    #[test]
+    fn can_get_path_price() {
+        let amounts = [U256::exp10(18), U256::from(5), U256::from(2_000) * U256::exp10(6)];
+        assert!((Library::path_price(&amounts, 18, 6) - 2000.0).abs() < 1e-9);
+        let amounts = [U256::from(2_000) * U256::exp10(6), U256::exp10(18)];
+        assert!((Library::path_price(&amounts, 6, 18) - 0.0005).abs() < 1e-12);
+        assert_eq!(Library::path_price(&[], 18, 18), 0.0);
+        assert_eq!(Library::path_price(&[U256::zero(), U256::one()], 18, 18), 0.0);
+    }
+
+    #[test]
+    fn can_estimate_fee_apr() {
+        let base = U256::exp10(18);
+        // 1M volume on 10M reserves at 0.3% => 300 * 365 / 10M = 10.95%
+        let apr = Library::estimate_fee_apr(
+            U256::from(1_000_000) * base,
+            U256::from(10_000_000) * base,
+            Library::FEE_BPS,
+        );
+        assert!((apr - 0.1095).abs() < 1e-12);
+        assert_eq!(Library::estimate_fee_apr(base, U256::zero(), Library::FEE_BPS), 0.0);
+        assert_eq!(u256_to_f64(U256::MAX), 2f64.powi(256));
+    }
+
+    #[test]
+    fn can_get_marginal_price() {
+        let base = U256::exp10(18);
+        let (reserve_in, reserve_out) = (U256::from(1000) * base, U256::from(5000) * base);
+
+        let price = Library::marginal_price(reserve_in, reserve_out, 0).unwrap();
+        assert_eq!(price, Library::quote(PRICE_SCALE, reserve_in, reserve_out).unwrap());
+
+        // 5 * 0.997
+        let price = Library::marginal_price(reserve_in, reserve_out, Library::FEE_BPS).unwrap();
+        assert_eq!(price, U256::from(4985) * U256::exp10(15));
+
+        // any actual trade gets a worse price than the marginal price
+        let amount_out = Library::get_amount_out(base, reserve_in, reserve_out).unwrap();
+        assert!(amount_out < price);
+
+        let res = Library::marginal_price(U256::zero(), reserve_out, Library::FEE_BPS);
+        assert!(matches!(res.unwrap_err(), Error::InsufficientLiquidity));
+    }
+
+    #[test]
+    fn can_get_prices_both_ways() {
+        let base = U256::exp10(18);
+        let (reserve_a, reserve_b) = (U256::from(1000) * base, U256::from(5000) * base);
+
+        let (a_to_b, b_to_a) = Library::prices_both_ways(reserve_a, reserve_b, 0).unwrap();
+        assert_eq!(a_to_b, U256::from(5) * PRICE_SCALE);
+        assert_eq!(b_to_a, PRICE_SCALE / 5);
+
+        // 5 * 0.997 and 0.2 * 0.997
+        let prices = Library::prices_both_ways(reserve_a, reserve_b, Library::FEE_BPS).unwrap();
+        assert_eq!(
+            prices,
+            (U256::from(4985) * U256::exp10(15), U256::from(1994) * U256::exp10(14))
+        );
+
+        let res = Library::prices_both_ways(reserve_a, U256::zero(), Library::FEE_BPS);
+        assert!(matches!(res.unwrap_err(), Error::InsufficientLiquidity));
+    }
+
+    #[test]
+    fn can_get_rounded_amounts() {
+        let (reserve_a, reserve_b) = (U256::from(3), U256::from(5));
+        let quote = |amount, mode| Library::quote_rounded(amount, reserve_a, reserve_b, mode);
+        // 1 * 5 / 3 = 1.67
+        assert_eq!(quote(1.into(), RoundingMode::Floor).unwrap(), 1.into());
+        assert_eq!(quote(1.into(), RoundingMode::Ceil).unwrap(), 2.into());
+        assert_eq!(quote(1.into(), RoundingMode::Nearest).unwrap(), 2.into());
+        // 3 * 5 / 3 = 5
+        assert_eq!(quote(3.into(), RoundingMode::Ceil).unwrap(), 5.into());
+        // 2 * 5 / 3 = 3.33
+        assert_eq!(quote(2.into(), RoundingMode::Nearest).unwrap(), 3.into());
+
+        let base = U256::exp10(18);
+        let (amount_in, reserve_in, reserve_out) = (base, U256::from(100) * base, U256::from(7));
+        let floor = Library::get_amount_out(amount_in, reserve_in, reserve_out).unwrap();
+        let rounded =
+            |mode| Library::get_amount_out_rounded(amount_in, reserve_in, reserve_out, mode);
+        assert_eq!(rounded(RoundingMode::default()).unwrap(), floor);
+        assert_eq!(rounded(RoundingMode::Ceil).unwrap(), floor + 1);
+    }
+
+    #[test]
+    fn can_get_reserves_after_swap() {
+        let base = U256::exp10(18);
+        let amount_in = U256::from(10) * base;
+        let reserve_in = U256::from(1000) * base;
+        let reserve_out = U256::from(5000) * base;
+
+        let amount_out = Library::get_amount_out(amount_in, reserve_in, reserve_out).unwrap();
+        let (new_in, new_out) =
+            Library::reserves_after_swap(amount_in, reserve_in, reserve_out, Library::FEE_BPS)
+                .unwrap();
+        assert_eq!(new_in, reserve_in + amount_in);
+        assert_eq!(new_out, reserve_out - amount_out);
+        // k never decreases
+        assert!(new_in * new_out >= reserve_in * reserve_out);
+
+        // chaining: the second swap gets a worse price
+        let (new_in_2, new_out_2) =
+            Library::reserves_after_swap(amount_in, new_in, new_out, Library::FEE_BPS).unwrap();
+        assert_eq!(new_in_2, reserve_in + amount_in * 2);
+        assert!(new_out - new_out_2 < amount_out);
+    }
+
     #[tokio::test]
     #[ignore = "async test"]
     async fn can_quote_async() {
@@ -278,6 +2510,45 @@ mod tests {
         Library::get_amount_in(usdc_amount, usdc_reserve, weth_reserve).unwrap();
     }
 
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_match_router() {
+        let router = Router::new_with_chain(
+            MAINNET.provider().into(),
+            Chain::Mainnet,
+            ProtocolType::UniswapV2,
+        )
+        .unwrap();
+        let path = [*WETH, *USDC];
+        let discrepancy =
+            Library::assert_matches_router(&*FACTORY, &router, U256::exp10(18), &path)
+                .await
+                .unwrap();
+        assert_eq!(discrepancy.len(), 2);
+        assert!(discrepancy.iter().all(|d| d.is_zero()));
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_get_amounts_out_with_exotic() {
+        let router = Router::new_with_chain(
+            MAINNET.provider().into(),
+            Chain::Mainnet,
+            ProtocolType::UniswapV2,
+        )
+        .unwrap();
+        let path = [*WETH, *USDC];
+        let amount_in = U256::exp10(18);
+        let local = Library::get_amounts_out(&*FACTORY, amount_in, &path).await.unwrap();
+        for exotic in [HashSet::new(), HashSet::from([*USDC])] {
+            let amounts =
+                Library::get_amounts_out_with_exotic(&*FACTORY, &router, amount_in, &path, &exotic)
+                    .await
+                    .unwrap();
+            assert_eq!(amounts, local);
+        }
+    }
+
     #[tokio::test]
     #[ignore = "async test"]
     async fn can_get_amounts() {
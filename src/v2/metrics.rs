@@ -0,0 +1,121 @@
+use super::{library::u256_to_f64, Factory, Library, PoolSnapshot};
+use crate::errors::Result;
+use ethers_contract::ContractError;
+use ethers_core::types::Address;
+use ethers_providers::Middleware;
+use std::{collections::HashMap, fmt::Write};
+
+/// The gauges exported by [`PoolSnapshot::to_prometheus`]: name, help.
+const GAUGES: [(&str, &str); 3] = [
+    ("uniswap_v2_reserve0", "The reserve of the pair's token0, in the token's smallest unit."),
+    ("uniswap_v2_reserve1", "The reserve of the pair's token1, in the token's smallest unit."),
+    (
+        "uniswap_v2_price",
+        "The price of the pair's token0 in terms of token1, not adjusted for decimals.",
+    ),
+];
+
+impl PoolSnapshot {
+    /// Formats the reserves of the snapshot's pairs as Prometheus [text-format] gauges.
+    ///
+    /// Every pair is labeled with its address, the chain and the symbols of its tokens, taken from
+    /// `symbols` or defaulting to the token's address. The price is NaN if `reserve0` is zero.
+    ///
+    /// [text-format]: https://prometheus.io/docs/instrumenting/exposition_formats/#text-based-format
+    pub fn to_prometheus(&self, symbols: &HashMap<Address, String>) -> String {
+        let chain = match (self.chain(), self.chain_id) {
+            (Some(chain), _) => chain.to_string(),
+            (None, Some(id)) => id.to_string(),
+            (None, None) => "unknown".to_string(),
+        };
+        let symbol = |token: &Address| match symbols.get(token) {
+            Some(symbol) => escape_label(symbol),
+            None => format!("{token:?}"),
+        };
+
+        let mut out = String::new();
+        for (i, (name, help)) in GAUGES.iter().enumerate() {
+            // writing to a String cannot fail
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} gauge");
+            for pair in &self.pairs {
+                let value = match i {
+                    0 => pair.reserve0.to_string(),
+                    1 => pair.reserve1.to_string(),
+                    _ if pair.reserve0.is_zero() => "NaN".to_string(),
+                    _ => (u256_to_f64(pair.reserve1) / u256_to_f64(pair.reserve0)).to_string(),
+                };
+                let _ = writeln!(
+                    out,
+                    "{name}{{chain=\"{chain}\",pair=\"{:?}\",token0=\"{}\",token1=\"{}\"}} {value}",
+                    pair.pair,
+                    symbol(&pair.token0),
+                    symbol(&pair.token1),
+                );
+            }
+        }
+        out
+    }
+}
+
+impl Library {
+    /// Fetches the reserves of `pairs` at the latest block and formats them as Prometheus gauges
+    /// with [`PoolSnapshot::to_prometheus`]. Makes 2 calls to the client, plus 1 call to resolve
+    /// the pairs' addresses with [`PairResolution::OnChain`](super::PairResolution::OnChain).
+    #[doc(alias = "dry_run_only")]
+    pub async fn reserves_metrics<M: Middleware>(
+        factory: &Factory<M>,
+        pairs: &[(Address, Address)],
+        symbols: &HashMap<Address, String>,
+    ) -> Result<String> {
+        let block = factory
+            .client()
+            .get_block_number()
+            .await
+            .map_err(ContractError::<M>::MiddlewareError)?;
+        let snapshot = Self::capture_snapshot(factory, pairs, block.as_u64()).await?;
+        Ok(snapshot.to_prometheus(symbols))
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::PairReserves;
+    use ethers_core::types::U256;
+
+    #[test]
+    fn can_format_prometheus() {
+        let (a, b) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        let pair = Address::repeat_byte(0xaa);
+        let snapshot = PoolSnapshot {
+            block: 1,
+            chain_id: Some(1),
+            pairs: vec![
+                PairReserves {
+                    token0: a,
+                    token1: b,
+                    reserve0: 1000.into(),
+                    reserve1: 2500.into(),
+                    pair,
+                },
+                PairReserves { token0: b, reserve1: U256::one(), ..Default::default() },
+            ],
+        };
+        let symbols = HashMap::from([(a, "WETH".to_string()), (b, "A\"B".to_string())]);
+        let text = snapshot.to_prometheus(&symbols);
+
+        let labels =
+            format!("chain=\"mainnet\",pair=\"{pair:?}\",token0=\"WETH\",token1=\"A\\\"B\"");
+        assert!(text.contains("# TYPE uniswap_v2_reserve0 gauge\n"));
+        assert!(text.contains(&format!("uniswap_v2_reserve0{{{labels}}} 1000\n")));
+        assert!(text.contains(&format!("uniswap_v2_reserve1{{{labels}}} 2500\n")));
+        assert!(text.contains(&format!("uniswap_v2_price{{{labels}}} 2.5\n")));
+        assert!(text.contains(&format!("token1=\"{:?}\"}} NaN\n", Address::zero())));
+        assert_eq!(text.lines().count(), 3 * 4);
+    }
+}
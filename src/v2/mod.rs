@@ -1,13 +1,41 @@
 //! The [Uniswap V2 protocol](https://docs.uniswap.org/contracts/v2/overview).
 
+mod arbitrage;
+mod decode;
+mod events;
 mod factory;
+mod flash_swap;
 mod library;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod oracle;
 mod pair;
 mod protocol;
+mod route;
 mod router;
+mod snapshot;
+mod source;
 
-pub use factory::Factory;
-pub use library::Library;
+pub use arbitrage::{ArbCosts, ArbOpportunity, ArbWatcher};
+pub use decode::{decode_router_call, DecodedSwap};
+pub use events::{
+    decode_burn, decode_mint, BurnEvent, MintEvent, PairCreatedEvent, PriceImpact, SwapEvent,
+};
+#[cfg(feature = "addresses")]
+pub use factory::all_factories;
+pub use factory::{Factory, FactoryHealth, PairResolution};
+pub use flash_swap::FlashSwapBuilder;
+pub use library::{HopAmounts, Library, PairReserves, ReservesDecoder, ReservesEncoding};
+pub use oracle::{Observation, Oracle};
 pub use pair::Pair;
 pub use protocol::Protocol;
-pub use router::Router;
+pub use route::{
+    best_route_concurrent, best_route_concurrent_with_options, compare_routes,
+    compare_routes_with_gas_model, find_routes, route_score, DefaultGasModel, GasAccounting,
+    GasModel, Route, RouteOptions, RouteQuote,
+};
+#[cfg(feature = "addresses")]
+pub use route::{prepare_comparison, quote_eth_in};
+pub use router::{router_matches_factory, Router};
+pub use snapshot::PoolSnapshot;
+pub use source::{FallbackReserveSource, LensReserveSource, MulticallReserveSource, ReserveSource};
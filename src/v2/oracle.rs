@@ -0,0 +1,126 @@
+use crate::{contracts::bindings::i_uniswap_v2_pair::IUniswapV2Pair, errors::Result};
+use ethers_contract::{Multicall, MulticallVersion};
+use ethers_core::types::{Address, U256};
+use ethers_providers::Middleware;
+use std::sync::Arc;
+
+/// An observation of a pair's cumulative prices.
+///
+/// The cumulative prices are sums of the pair's UQ112x112 prices weighted by the seconds elapsed,
+/// so they are meant to overflow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Observation {
+    /// The block timestamp of the observation, modulo 2^32.
+    pub timestamp: u32,
+
+    /// The pair's `price0CumulativeLast`.
+    pub price0_cumulative: U256,
+
+    /// The pair's `price1CumulativeLast`.
+    pub price1_cumulative: U256,
+}
+
+/// A time-weighted average price (TWAP) oracle, using the cumulative prices of Uniswap V2 pairs.
+///
+/// See the original [example oracle].
+///
+/// [example oracle]: https://github.com/Uniswap/v2-periphery/blob/master/contracts/examples/ExampleOracleSimple.sol
+pub struct Oracle;
+
+impl Oracle {
+    /// Fetches the cumulative prices of a pair as of its last update. Makes only 1 call to the
+    /// client by using [Multicall].
+    #[doc(alias = "dry_run_only")]
+    pub async fn observe<M: Middleware>(client: Arc<M>, pair: Address) -> Result<Observation> {
+        let pair = IUniswapV2Pair::new(pair, client);
+        let mut multicall =
+            Multicall::new(pair.client(), None).await?.version(MulticallVersion::Multicall);
+        multicall
+            .add_call(pair.price_0_cumulative_last(), false)
+            .add_call(pair.price_1_cumulative_last(), false)
+            .add_call(pair.get_reserves(), false);
+        let (price0_cumulative, price1_cumulative, (_, _, timestamp)): (
+            U256,
+            U256,
+            (u128, u128, u32),
+        ) = multicall.call().await?;
+        Ok(Observation { timestamp, price0_cumulative, price1_cumulative })
+    }
+
+    /// Returns the time-weighted average prices `(price0, price1)` between two observations, as
+    /// UQ112x112 numbers.
+    ///
+    /// Returns zeros if no time has elapsed between the observations.
+    pub fn twap(start: &Observation, end: &Observation) -> (U256, U256) {
+        Self::twap_series(&[*start, *end])
+    }
+
+    /// Returns the time-weighted average prices `(price0, price1)` across a series of
+    /// observations sorted by time, as UQ112x112 numbers.
+    ///
+    /// The timestamps wrap around at 2^32, so consecutive observations must be less than ~136
+    /// years apart. Returns zeros if there are less than 2 observations or no time has elapsed.
+    pub fn twap_series(observations: &[Observation]) -> (U256, U256) {
+        let mut elapsed = 0u64;
+        let (mut price0, mut price1) = (U256::zero(), U256::zero());
+        for window in observations.windows(2) {
+            let (start, end) = (&window[0], &window[1]);
+            elapsed += end.timestamp.wrapping_sub(start.timestamp) as u64;
+            // overflow is desired
+            price0 = price0
+                .overflowing_add(end.price0_cumulative.overflowing_sub(start.price0_cumulative).0)
+                .0;
+            price1 = price1
+                .overflowing_add(end.price1_cumulative.overflowing_sub(start.price1_cumulative).0)
+                .0;
+        }
+        if elapsed == 0 {
+            return (U256::zero(), U256::zero());
+        }
+        (price0 / elapsed, price1 / elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q112(price: u64) -> U256 {
+        U256::from(price) << 112
+    }
+
+    fn observation(timestamp: u32, price0_cumulative: U256) -> Observation {
+        Observation { timestamp, price0_cumulative, price1_cumulative: U256::zero() }
+    }
+
+    #[test]
+    fn can_get_twap() {
+        let start = observation(1000, U256::zero());
+        let end = observation(1100, q112(2) * 100);
+        assert_eq!(Oracle::twap(&start, &end).0, q112(2));
+        assert_eq!(Oracle::twap(&start, &start), (U256::zero(), U256::zero()));
+    }
+
+    #[test]
+    fn can_get_twap_series() {
+        // price 2 for 100s, then price 5 for 300s => (200 + 1500) / 400
+        let observations = [
+            observation(1000, U256::zero()),
+            observation(1100, q112(2) * 100),
+            observation(1400, q112(2) * 100 + q112(5) * 300),
+        ];
+        let expected = (q112(2) * 100 + q112(5) * 300) / 400;
+        assert_eq!(Oracle::twap_series(&observations).0, expected);
+        assert_eq!(Oracle::twap_series(&observations[..1]), (U256::zero(), U256::zero()));
+    }
+
+    #[test]
+    fn twap_series_handles_wrap() {
+        // both the timestamp and the cumulative price overflow
+        let start = observation(u32::MAX - 49, U256::MAX - q112(3) * 50 + 1);
+        let middle = observation(50, q112(3) * 50);
+        let end = observation(150, q112(3) * 150);
+        assert_eq!(Oracle::twap_series(&[start, middle, end]).0, q112(3));
+    }
+}
@@ -1,11 +1,15 @@
 use super::{Factory, Library};
-use crate::{contracts::bindings::i_uniswap_v2_pair::IUniswapV2Pair, errors::Result, ProtocolType};
+use crate::{
+    contracts::bindings::i_uniswap_v2_pair::IUniswapV2Pair,
+    errors::{Error, Result},
+    Pool, ProtocolType,
+};
 use ethers_contract::{
     builders::ContractCall, ContractError, Multicall, MulticallError, MulticallVersion,
 };
 use ethers_core::{
     abi::{Detokenize, Token},
-    types::{Address, Chain, H256},
+    types::{Address, Chain, H256, U256},
 };
 use ethers_providers::Middleware;
 use std::{fmt, sync::Arc};
@@ -87,7 +91,31 @@ impl<M> Pair<M> {
     }
 }
 
+impl<M> Pool for Pair<M> {
+    fn tokens(&self) -> Result<(Address, Address)> {
+        self.tokens.ok_or(Error::NotSynced)
+    }
+
+    /// Uses the pair's synced reserves.
+    fn amount_out(&self, token_in: Address, amount_in: U256) -> Result<U256> {
+        let (token0, token1) = Pool::tokens(self)?;
+        let (reserve0, reserve1, _) = self.reserves.ok_or(Error::NotSynced)?;
+        let (reserve0, reserve1) = (reserve0.into(), reserve1.into());
+        let (reserve_in, reserve_out) = if token_in == token0 {
+            (reserve0, reserve1)
+        } else if token_in == token1 {
+            (reserve1, reserve0)
+        } else {
+            return Err(Error::TokenNotInPair);
+        };
+        Library::get_amount_out(amount_in, reserve_in, reserve_out)
+    }
+}
+
 impl<M: Middleware> Pair<M> {
+    /// The approximate number of blocks produced in 24 hours on Ethereum, with 12 second blocks.
+    pub const BLOCKS_PER_DAY: u64 = 7200;
+
     /// Creates a new instance using the provided client and address.
     pub fn new(client: Arc<M>, address: Address, protocol: ProtocolType) -> Self {
         let contract = IUniswapV2Pair::new(address, client);
@@ -95,11 +123,18 @@ impl<M: Middleware> Pair<M> {
     }
 
     /// Creates a new instance using the provided client, factory and tokens' addresses.
+    ///
+    /// The pair's address is computed locally, so this returns [`Error::OnChainPairResolution`]
+    /// if the factory's [`PairResolution`](super::PairResolution) is `OnChain`. Use
+    /// [`Factory::pair_for`] instead.
     pub fn new_with_factory(
         factory: &Factory<M>,
         token0: Address,
         token1: Address,
     ) -> Result<Self> {
+        if factory.pair_resolution() == super::PairResolution::OnChain {
+            return Err(Error::OnChainPairResolution);
+        }
         let (token0, token1) = Library::sort_tokens(token0, token1);
         let address = Library::pair_for(factory, token0, token1);
         let contract = IUniswapV2Pair::new(address, factory.client());
@@ -113,6 +148,33 @@ impl<M: Middleware> Pair<M> {
         })
     }
 
+    /// Returns the total input amounts of token0 and token1 swapped in the last `blocks` blocks, by
+    /// querying the pair's `Swap` events.
+    ///
+    /// These are the amounts that paid the swap fee. Use [`BLOCKS_PER_DAY`] for the volume of the
+    /// last 24 hours on Ethereum. Note that some providers limit the block range of log queries.
+    ///
+    /// [`BLOCKS_PER_DAY`]: Self::BLOCKS_PER_DAY
+    #[doc(alias = "dry_run_only")]
+    pub async fn swap_volume(&self, blocks: u64) -> Result<(U256, U256)> {
+        let latest = self
+            .client()
+            .get_block_number()
+            .await
+            .map_err(ContractError::<M>::MiddlewareError)?
+            .as_u64();
+        let events = self
+            .contract
+            .swap_filter()
+            .from_block(latest.saturating_sub(blocks))
+            .to_block(latest)
+            .query()
+            .await?;
+        Ok(events.into_iter().fold((U256::zero(), U256::zero()), |(volume0, volume1), event| {
+            (volume0 + event.amount_0_in, volume1 + event.amount_1_in)
+        }))
+    }
+
     /// Returns the contract calls for getting the addresses of the pair's tokens.
     pub fn get_tokens(&self) -> (ContractCall<M, Address>, ContractCall<M, Address>) {
         (self.contract.token_0(), self.contract.token_1())
@@ -121,6 +183,7 @@ impl<M: Middleware> Pair<M> {
     /// Syncs the tokens and reserves of the pair by querying the blockchain.
     ///
     /// Assumes that any call failure means the pair has not been deployed yet.
+    #[doc(alias = "dry_run_only")]
     pub async fn sync(&mut self, sync_tokens: bool, sync_reserves: bool) -> Result<&mut Self> {
         // let sync_tokens = self.tokens.is_none() || !self.deployed;
         // let sync_reserves = self.reserves.is_none() || !self.deployed;
@@ -380,4 +443,13 @@ mod tests {
         assert_ne!(reserves.1, 0);
         assert_ne!(reserves.2, 0);
     }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    #[cfg(feature = "addresses")]
+    async fn can_get_swap_volume() {
+        let pair = default_pair();
+        let (volume0, volume1) = pair.swap_volume(100).await.unwrap();
+        assert!(!volume0.is_zero() || !volume1.is_zero());
+    }
 }
@@ -1,4 +1,4 @@
-use super::{Factory, Pair, Router};
+use super::{Factory, Library, Pair, Router};
 use crate::{errors::Result, Amount, ProtocolType};
 use ethers_contract::builders::ContractCall;
 use ethers_core::types::{Address, Chain, H256, U256};
@@ -90,8 +90,9 @@ impl<M: Middleware> Protocol<M> {
 
     /// The factory's `pair_for` method. See documentation of [Factory] for more details.
     #[inline(always)]
-    pub fn pair_for(&self, token_a: Address, token_b: Address) -> Pair<M> {
-        self.factory.pair_for(token_a, token_b)
+    #[doc(alias = "dry_run_only")]
+    pub async fn pair_for(&self, token_a: Address, token_b: Address) -> Result<Pair<M>> {
+        self.factory.pair_for(token_a, token_b).await
     }
 
     /* ----------------------------------------- Router ----------------------------------------- */
@@ -162,4 +163,19 @@ impl<M: Middleware> Protocol<M> {
     ) -> Result<ContractCall<M, Vec<U256>>> {
         self.router.swap(&self.factory, amount, slippage_tolerance, path, to, deadline, weth).await
     }
+
+    /// Returns the reserves of the pair of two tokens. See [`Library::get_reserves`].
+    #[inline(always)]
+    #[doc(alias = "dry_run_only")]
+    pub async fn reserves(&self, token_a: Address, token_b: Address) -> Result<(U256, U256)> {
+        Library::get_reserves(&self.factory, token_a, token_b).await
+    }
+
+    /// Returns the output amounts of swapping `amount_in` along the path. See
+    /// [`Library::get_amounts_out`].
+    #[inline(always)]
+    #[doc(alias = "dry_run_only")]
+    pub async fn quote(&self, amount_in: U256, path: &[Address]) -> Result<Vec<U256>> {
+        Library::get_amounts_out(&self.factory, amount_in, path).await
+    }
 }
@@ -0,0 +1,986 @@
+use super::{library::u256_to_f64, Factory, Library};
+use crate::{
+    errors::{Error, Result},
+    ProtocolType,
+};
+use ethers_core::types::{Address, I256, U256};
+use ethers_providers::Middleware;
+use futures_util::stream::{self, StreamExt};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "addresses")]
+use ethers_core::types::Chain;
+#[cfg(feature = "addresses")]
+use futures_util::future;
+
+/// A path of pairs deployed by a factory.
+pub struct Route<M> {
+    /// The factory of the pairs.
+    factory: Factory<M>,
+
+    /// The path of tokens.
+    path: Vec<Address>,
+}
+
+impl<M> Clone for Route<M> {
+    fn clone(&self) -> Self {
+        Self { factory: self.factory.clone(), path: self.path.clone() }
+    }
+}
+
+impl<M> fmt::Debug for Route<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Route").field("factory", &self.factory).field("path", &self.path).finish()
+    }
+}
+
+impl<M> Route<M> {
+    /// Creates a new route, validating that the path has at least 2 tokens and that no token is
+    /// swapped into itself.
+    pub fn new(factory: Factory<M>, path: Vec<Address>) -> Result<Self> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+        if path.windows(2).any(|slice| slice[0] == slice[1]) {
+            return Err(Error::IdenticalAddresses);
+        }
+        Ok(Self { factory, path })
+    }
+
+    /// Returns the protocol of the route's factory.
+    pub fn protocol(&self) -> ProtocolType {
+        self.factory.protocol()
+    }
+
+    /// Returns the route's factory.
+    pub fn factory(&self) -> &Factory<M> {
+        &self.factory
+    }
+
+    /// Returns the route's path.
+    pub fn path(&self) -> &[Address] {
+        &self.path
+    }
+}
+
+impl<M: Middleware> Route<M> {
+    /// Returns the amounts received by swapping `amount_in` through the route.
+    ///
+    /// See [`Library::get_amounts_out`].
+    #[doc(alias = "dry_run_only")]
+    pub async fn amounts_out(&self, amount_in: U256) -> Result<Vec<U256>> {
+        Library::get_amounts_out(&self.factory, amount_in, &self.path).await
+    }
+
+    /// Returns the amounts required to receive `amount_out` at the end of the route.
+    ///
+    /// See [`Library::get_amounts_in`].
+    #[doc(alias = "dry_run_only")]
+    pub async fn amounts_in(&self, amount_out: U256) -> Result<Vec<U256>> {
+        Library::get_amounts_in(&self.factory, amount_out, &self.path).await
+    }
+}
+
+/// Creates a [Route] for each of `entries`, resolving each protocol's factory from the
+/// [addressbook] and validating each path, without making any external calls.
+///
+/// [addressbook]: crate::contracts::addresses
+#[cfg(feature = "addresses")]
+pub fn prepare_comparison<M: Middleware>(
+    client: Arc<M>,
+    chain: Chain,
+    entries: &[(ProtocolType, Vec<Address>)],
+) -> Vec<Result<Route<M>>> {
+    entries
+        .iter()
+        .map(|(protocol, path)| {
+            if !protocol.is_v2() {
+                return Err(Error::NotV2Protocol);
+            }
+            let factory = Factory::new_with_chain(client.clone(), chain, *protocol)
+                .ok_or(Error::MissingAddress(protocol.contract_names().0))?;
+            Route::new(factory, path.clone())
+        })
+        .collect()
+}
+
+/// Returns the amounts of swapping `eth_amount` of the native token, wrapped, for `token_out` on
+/// `protocol`, resolving WETH and the factory from the [addressbook].
+///
+/// The direct WETH pair is used if it exists, otherwise the best route through one of the USDC,
+/// USDT and DAI connectors.
+///
+/// [addressbook]: crate::contracts::addresses
+#[cfg(feature = "addresses")]
+#[doc(alias = "dry_run_only")]
+pub async fn quote_eth_in<M: Middleware>(
+    client: Arc<M>,
+    chain: Chain,
+    protocol: ProtocolType,
+    eth_amount: U256,
+    token_out: Address,
+) -> Result<Vec<U256>> {
+    use crate::contracts::addresses::try_address;
+
+    if !protocol.is_v2() {
+        return Err(Error::NotV2Protocol);
+    }
+    let weth = try_address("WETH", chain).ok_or(Error::MissingAddress("WETH"))?;
+    if token_out == weth {
+        return Err(Error::SwapToSelf);
+    }
+    let factory = Factory::new_with_chain(client, chain, protocol)
+        .ok_or(Error::MissingAddress(protocol.contract_names().0))?;
+
+    let direct_err = match Library::get_amounts_out(&factory, eth_amount, &[weth, token_out]).await
+    {
+        Ok(amounts) => return Ok(amounts),
+        Err(e) => e,
+    };
+
+    let connectors: Vec<_> =
+        ["USDC", "USDT", "DAI"].into_iter().filter_map(|token| try_address(token, chain)).collect();
+    // skip the direct path
+    let paths = find_routes(weth, token_out, &connectors, 2).into_iter().skip(1);
+    let factory = &factory;
+    let quotes = future::join_all(
+        paths.map(|path| async move { Library::get_amounts_out(factory, eth_amount, &path).await }),
+    )
+    .await;
+    quotes
+        .into_iter()
+        .filter_map(Result::ok)
+        .max_by_key(|amounts| amounts.last().copied())
+        .ok_or(direct_err)
+}
+
+/// Estimates the gas used by swapping through a route, used for comparing routes by their net
+/// output.
+pub trait GasModel: fmt::Debug {
+    /// Returns the gas used by a swap, excluding the hops.
+    fn base_cost(&self) -> U256 {
+        RouteQuote::GAS_BASE.into()
+    }
+
+    /// Returns the gas used by a single hop through a pool of `protocol`.
+    fn hop_cost(&self, protocol: ProtocolType) -> U256;
+
+    /// Returns the gas used by swapping through `hops` pools of `protocol`.
+    fn route_cost(&self, protocol: ProtocolType, hops: usize) -> U256 {
+        self.base_cost() + self.hop_cost(protocol) * hops
+    }
+}
+
+/// A [GasModel] with rough constants for each [ProtocolType].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DefaultGasModel;
+
+impl DefaultGasModel {
+    /// The gas used by a hop through a Uniswap V3 pool, or a fork of it.
+    pub const GAS_PER_V3_HOP: u64 = 100_000;
+}
+
+impl GasModel for DefaultGasModel {
+    fn hop_cost(&self, protocol: ProtocolType) -> U256 {
+        let gas = match protocol {
+            ProtocolType::UniswapV2
+            | ProtocolType::Sushiswap
+            | ProtocolType::Pancakeswap
+            | ProtocolType::Quickswap
+            | ProtocolType::Spookyswap
+            | ProtocolType::Traderjoe => RouteQuote::GAS_PER_HOP,
+            ProtocolType::UniswapV3 => Self::GAS_PER_V3_HOP,
+            ProtocolType::Custom { is_v2: true, .. } => RouteQuote::GAS_PER_HOP,
+            ProtocolType::Custom { is_v2: false, .. } => Self::GAS_PER_V3_HOP,
+        };
+        gas.into()
+    }
+}
+
+/// Estimates the storage gas of ERC20 approvals and WETH unwraps, including the refunds of
+/// clearing a storage slot, for accounting the gas actually paid by a transaction.
+///
+/// `eth_estimateGas` returns the gas needed to execute a transaction, before refunds, so it
+/// over-estimates the cost of transactions that reset an allowance or a balance to zero. Assumes
+/// the slot is written once per transaction and the post-[EIP-3529] gas schedule.
+///
+/// [EIP-3529]: https://eips.ethereum.org/EIPS/eip-3529
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasAccounting;
+
+impl GasAccounting {
+    /// The gas cost of the first read of a storage slot in a transaction.
+    pub const COLD_SLOAD: u64 = 2_100;
+
+    /// The gas cost of writing a storage slot that was already written to in the transaction, or
+    /// to its current value.
+    pub const WARM_SSTORE: u64 = 100;
+
+    /// The gas cost of writing a non-zero value to a zero storage slot, excluding the cold read.
+    pub const SSTORE_SET: u64 = 20_000;
+
+    /// The gas cost of writing to a non-zero storage slot, excluding the cold read.
+    pub const SSTORE_RESET: u64 = 2_900;
+
+    /// The gas refunded by writing zero to a non-zero storage slot.
+    pub const SSTORE_CLEAR_REFUND: u64 = 4_800;
+
+    /// The gas refunded by a transaction is at most its gas used divided by this quotient.
+    pub const MAX_REFUND_QUOTIENT: u64 = 5;
+
+    /// Returns the `(cost, refund)` of writing `target` to a cold storage slot holding `current`.
+    pub fn sstore(current: U256, target: U256) -> (U256, U256) {
+        let (cost, refund) = if current == target {
+            (Self::WARM_SSTORE, 0)
+        } else if current.is_zero() {
+            (Self::SSTORE_SET, 0)
+        } else if target.is_zero() {
+            (Self::SSTORE_RESET, Self::SSTORE_CLEAR_REFUND)
+        } else {
+            (Self::SSTORE_RESET, 0)
+        };
+        ((Self::COLD_SLOAD + cost).into(), refund.into())
+    }
+
+    /// Returns whether approving `target` when the allowance is `current_allowance` triggers a
+    /// refund, i.e. resets the allowance to zero.
+    pub fn approval_refunds(current_allowance: U256, target: U256) -> bool {
+        !Self::approval_refund(current_allowance, target).is_zero()
+    }
+
+    /// Returns the gas refunded by approving `target` when the allowance is `current_allowance`.
+    pub fn approval_refund(current_allowance: U256, target: U256) -> U256 {
+        Self::sstore(current_allowance, target).1
+    }
+
+    /// Returns the gas refunded by a `transferFrom` of `amount`, like the one of a router swap,
+    /// when the allowance is `current_allowance`, which is non-zero only when spending the whole
+    /// allowance. An infinite allowance is assumed not to be decremented.
+    pub fn transfer_from_refund(current_allowance: U256, amount: U256) -> U256 {
+        if current_allowance == U256::MAX {
+            return U256::zero();
+        }
+        Self::approval_refund(current_allowance, current_allowance.saturating_sub(amount))
+    }
+
+    /// Returns the gas refunded by unwrapping `amount` of a WETH `balance`, which is non-zero
+    /// only when unwrapping the whole balance.
+    pub fn unwrap_refund(balance: U256, amount: U256) -> U256 {
+        Self::sstore(balance, balance.saturating_sub(amount)).1
+    }
+
+    /// Returns the gas paid by a transaction with a pre-refund `estimate`, like the one returned
+    /// by `eth_estimateGas`, after applying `refund`, capped at
+    /// `estimate / `[`MAX_REFUND_QUOTIENT`](Self::MAX_REFUND_QUOTIENT).
+    pub fn apply_refund(estimate: U256, refund: U256) -> U256 {
+        estimate - refund.min(estimate / Self::MAX_REFUND_QUOTIENT)
+    }
+
+    /// Returns the gas paid by an approval transaction with a pre-refund `estimate`.
+    ///
+    /// See [`approval_refund`](Self::approval_refund) and [`apply_refund`](Self::apply_refund).
+    pub fn adjust_approval_estimate(estimate: U256, current_allowance: U256, target: U256) -> U256 {
+        Self::apply_refund(estimate, Self::approval_refund(current_allowance, target))
+    }
+}
+
+/// A quote for swapping through a path of pairs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RouteQuote {
+    /// The path of the route.
+    pub path: Vec<Address>,
+
+    /// The input amount.
+    pub amount_in: U256,
+
+    /// The output amount.
+    pub amount_out: U256,
+
+    /// The estimated gas used by swapping through the route.
+    pub gas_estimate: U256,
+
+    /// The depth of the route's shallowest pair, as the pair's output reserve valued in the
+    /// route's output token at the mid-prices of the following pairs. Zero if unknown.
+    pub liquidity: U256,
+}
+
+impl RouteQuote {
+    /// The estimated base gas cost of a swap through the router.
+    pub const GAS_BASE: u64 = 21_000 + 40_000;
+
+    /// The estimated gas cost of every hop of a swap through the router.
+    pub const GAS_PER_HOP: u64 = 60_000;
+
+    /// Creates a new quote, estimating the gas used from the number of Uniswap V2 hops of `path`
+    /// with the [DefaultGasModel].
+    pub fn new(path: Vec<Address>, amount_in: U256, amount_out: U256) -> Self {
+        let protocol = ProtocolType::UniswapV2;
+        Self::new_with_gas_model(path, amount_in, amount_out, protocol, &DefaultGasModel)
+    }
+
+    /// Creates a new quote, estimating the gas used from the number of hops of `path`, all
+    /// through pools of `protocol`, with `gas_model`.
+    pub fn new_with_gas_model<G: GasModel + ?Sized>(
+        path: Vec<Address>,
+        amount_in: U256,
+        amount_out: U256,
+        protocol: ProtocolType,
+        gas_model: &G,
+    ) -> Self {
+        let gas_estimate = gas_model.route_cost(protocol, path.len().saturating_sub(1));
+        Self { path, amount_in, amount_out, gas_estimate, liquidity: U256::zero() }
+    }
+
+    /// Sets the [liquidity](Self::liquidity) of the route.
+    pub fn with_liquidity(mut self, liquidity: U256) -> Self {
+        self.liquidity = liquidity;
+        self
+    }
+
+    /// Reduces the [gas estimate](Self::gas_estimate) of the route by the `refund` of its storage
+    /// writes, for example from [`GasAccounting::transfer_from_refund`], so that the
+    /// [gas cost](Self::gas_cost) reflects the gas actually paid.
+    ///
+    /// See [`GasAccounting::apply_refund`].
+    pub fn with_gas_refund(mut self, refund: U256) -> Self {
+        self.gas_estimate = GasAccounting::apply_refund(self.gas_estimate, refund);
+        self
+    }
+
+    /// Returns the number of hops of the route.
+    pub fn hops(&self) -> usize {
+        self.path.len().saturating_sub(1)
+    }
+
+    /// Returns the estimated gas cost of the route in output token units.
+    ///
+    /// `native_price_in_output_token` is the price of 1 wei of the native currency in the output
+    /// token's smallest unit, e.g. `2e-9` for 2000 USDC (6 decimals) per ETH (18 decimals).
+    pub fn gas_cost(&self, gas_price: U256, native_price_in_output_token: f64) -> U256 {
+        let cost = (self.gas_estimate * gas_price).low_u128() as f64;
+        U256::from((cost * native_price_in_output_token) as u128)
+    }
+
+    /// Returns the output amount minus the estimated gas cost of the route in output token units.
+    ///
+    /// See [`gas_cost`](Self::gas_cost).
+    pub fn net_amount_out(&self, gas_price: U256, native_price_in_output_token: f64) -> I256 {
+        let cost = self.gas_cost(gas_price, native_price_in_output_token);
+        I256::from_raw(self.amount_out).saturating_sub(I256::from_raw(cost))
+    }
+}
+
+/// Returns the index of the route with the best output amount after subtracting the estimated gas
+/// cost, or None if `routes` is empty.
+///
+/// See [`RouteQuote::gas_cost`] for the meaning of `native_price_in_output_token`.
+pub fn compare_routes(
+    routes: &[RouteQuote],
+    gas_price: U256,
+    native_price_in_output_token: f64,
+) -> Option<usize> {
+    routes
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, route)| route.net_amount_out(gas_price, native_price_in_output_token))
+        .map(|(i, _)| i)
+}
+
+/// Same as [`compare_routes`], but re-estimates the gas of every route from its number of hops
+/// through pools of `protocol` with `gas_model`, instead of using the routes' gas estimates.
+pub fn compare_routes_with_gas_model(
+    routes: &[RouteQuote],
+    protocol: ProtocolType,
+    gas_model: &dyn GasModel,
+    gas_price: U256,
+    native_price_in_output_token: f64,
+) -> Option<usize> {
+    routes
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, route)| {
+            let gas_estimate = gas_model.route_cost(protocol, route.hops());
+            let route = RouteQuote { gas_estimate, ..(*route).clone() };
+            route.net_amount_out(gas_price, native_price_in_output_token)
+        })
+        .map(|(i, _)| i)
+}
+
+/// Returns a score combining the output amount and the [liquidity](RouteQuote::liquidity) of a
+/// route, where higher is better.
+///
+/// The score is `ln(amount_out) + liquidity_weight * ln(liquidity)`, so with a weight of zero
+/// routes are ranked by output amount alone, and with a small positive weight the deeper route wins
+/// near-ties in output amount. Routes without output or liquidity score negative infinity.
+pub fn route_score(quote: &RouteQuote, liquidity_weight: f64) -> f64 {
+    let amount_out = u256_to_f64(quote.amount_out).ln();
+    if liquidity_weight == 0.0 {
+        return amount_out;
+    }
+    amount_out + liquidity_weight * u256_to_f64(quote.liquidity).ln()
+}
+
+/// Returns all the paths from `token_in` to `token_out` with at most `max_hops` hops, routing
+/// through `connectors`, sorted by number of hops.
+///
+/// Every path is unique and contains each token at most once. The number of paths grows
+/// exponentially with `max_hops`, so it should be kept small: 3 is usually enough. Returns no
+/// paths if `token_in` and `token_out` are the same.
+///
+/// The paths can be evaluated with [`best_route_concurrent`].
+pub fn find_routes(
+    token_in: Address,
+    token_out: Address,
+    connectors: &[Address],
+    max_hops: usize,
+) -> Vec<Vec<Address>> {
+    if token_in == token_out {
+        return Vec::new();
+    }
+    let mut seen = HashSet::new();
+    let connectors: Vec<_> = connectors
+        .iter()
+        .copied()
+        .filter(|&token| token != token_in && token != token_out && seen.insert(token))
+        .collect();
+
+    let mut routes = Vec::new();
+    let mut partial = vec![vec![token_in]];
+    for hops in 1..=max_hops {
+        let mut next = Vec::new();
+        for path in partial {
+            if hops < max_hops {
+                for &connector in connectors.iter().filter(|token| !path.contains(token)) {
+                    let mut path = path.clone();
+                    path.push(connector);
+                    next.push(path);
+                }
+            }
+            let mut route = path;
+            route.push(token_out);
+            routes.push(route);
+        }
+        partial = next;
+    }
+    routes
+}
+
+/// Options for filtering and ranking the routes evaluated by
+/// [`best_route_concurrent_with_options`].
+#[derive(Clone, Debug, Default)]
+pub struct RouteOptions {
+    /// The token used to measure the liquidity of the pairs, for example WETH or a stablecoin.
+    pub reference_token: Option<Address>,
+
+    /// The minimum reserve of `reference_token` in every pair that contains it. Routes with any
+    /// such pair below this threshold are skipped.
+    ///
+    /// Pairs which do not contain `reference_token` are not filtered.
+    pub min_reserve_value: U256,
+
+    /// Tokens that must not be routed through. Routes with an intermediate token in this set are
+    /// skipped.
+    pub blocklist: HashSet<Address>,
+
+    /// If greater than zero, routes are ranked by [`route_score`] with this weight instead of by
+    /// output amount, preferring deeper liquidity.
+    pub liquidity_weight: f64,
+
+    /// The model used to estimate the gas of the routes, with the factory's protocol. Defaults to
+    /// [DefaultGasModel].
+    pub gas_model: Option<Arc<dyn GasModel + Send + Sync>>,
+
+    /// If greater than zero, and `liquidity_weight` is zero, routes are ranked by their output
+    /// amount minus their estimated gas cost at this gas price, as in [`compare_routes`].
+    pub gas_price: U256,
+
+    /// The price of 1 wei of the native currency in the output token's smallest unit, used with
+    /// `gas_price`. See [`RouteQuote::gas_cost`].
+    pub native_price_in_output_token: f64,
+}
+
+/// Evaluates `candidate_paths` concurrently, with at most `concurrency` paths being fetched at the
+/// same time, and returns the quote with the highest output amount.
+///
+/// The reserves of the pairs are cached and shared across all the paths, so each pair is fetched
+/// only once unless it is requested by multiple paths at the same time. Paths which fail to be
+/// quoted, for example because a pair does not exist, are ignored.
+#[doc(alias = "dry_run_only")]
+pub async fn best_route_concurrent<M: Middleware>(
+    factory: &Factory<M>,
+    amount_in: U256,
+    candidate_paths: Vec<Vec<Address>>,
+    concurrency: usize,
+) -> Option<RouteQuote> {
+    let options = RouteOptions::default();
+    // cannot fail without a blocklist
+    best_route_concurrent_with_options(factory, amount_in, candidate_paths, concurrency, &options)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Same as [`best_route_concurrent`], but skips the routes filtered out by `options`.
+///
+/// Returns [`Error::BlockedToken`] if the first or last token of any of `candidate_paths` is in
+/// the options' blocklist, since no route could ever be found for it.
+#[doc(alias = "dry_run_only")]
+pub async fn best_route_concurrent_with_options<M: Middleware>(
+    factory: &Factory<M>,
+    amount_in: U256,
+    candidate_paths: Vec<Vec<Address>>,
+    concurrency: usize,
+    options: &RouteOptions,
+) -> Result<Option<RouteQuote>> {
+    let mut endpoints =
+        candidate_paths.iter().flat_map(|path| path.first().into_iter().chain(path.last()));
+    if let Some(&token) = endpoints.find(|token| options.blocklist.contains(token)) {
+        return Err(Error::BlockedToken(token));
+    }
+
+    let gas_model = options.gas_model.as_deref().unwrap_or(&DefaultGasModel);
+    let protocol = factory.protocol();
+    let (gas_price, native_price) = (options.gas_price, options.native_price_in_output_token);
+
+    let cache = Mutex::new(HashMap::new());
+    let cache = &cache;
+    let best = stream::iter(candidate_paths)
+        .filter(|path| {
+            let blocked = path.iter().any(|token| options.blocklist.contains(token));
+            async move { !blocked }
+        })
+        .map(|path| async move {
+            let (amount_out, liquidity) =
+                quote_cached(factory, cache, options, amount_in, &path).await.ok()?;
+            let quote =
+                RouteQuote::new_with_gas_model(path, amount_in, amount_out, protocol, gas_model);
+            Some(quote.with_liquidity(liquidity))
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|quote| async move { quote })
+        .fold(None, |best: Option<RouteQuote>, quote| async move {
+            let is_better = |best: &RouteQuote| {
+                if options.liquidity_weight > 0.0 {
+                    route_score(best, options.liquidity_weight)
+                        >= route_score(&quote, options.liquidity_weight)
+                } else if !gas_price.is_zero() {
+                    best.net_amount_out(gas_price, native_price)
+                        >= quote.net_amount_out(gas_price, native_price)
+                } else {
+                    best.amount_out >= quote.amount_out
+                }
+            };
+            match best {
+                Some(best) if is_better(&best) => Some(best),
+                _ => Some(quote),
+            }
+        })
+        .await;
+    Ok(best)
+}
+
+/// Sorted token addresses => sorted reserves.
+type ReserveCache = Mutex<HashMap<(Address, Address), (U256, U256)>>;
+
+async fn quote_cached<M: Middleware>(
+    factory: &Factory<M>,
+    cache: &ReserveCache,
+    options: &RouteOptions,
+    amount_in: U256,
+    path: &[Address],
+) -> Result<(U256, U256)> {
+    if path.len() < 2 {
+        return Err(Error::InvalidPath);
+    }
+
+    let mut amount = amount_in;
+    let mut hops = Vec::with_capacity(path.len() - 1);
+    for (hop, slice) in path.windows(2).enumerate() {
+        let (a, b) = (slice[0], slice[1]);
+        let key = Library::sort_tokens(a, b);
+        let cached = cache.lock().unwrap().get(&key).copied();
+        let (reserve0, reserve1) = match cached {
+            Some(reserves) => reserves,
+            None => {
+                let reserves = Library::get_reserves(factory, key.0, key.1).await?;
+                cache.lock().unwrap().insert(key, reserves);
+                reserves
+            }
+        };
+        let (reserve_in, reserve_out) =
+            if a == key.0 { (reserve0, reserve1) } else { (reserve1, reserve0) };
+
+        let reference_reserve = match options.reference_token {
+            Some(token) if token == a => Some(reserve_in),
+            Some(token) if token == b => Some(reserve_out),
+            _ => None,
+        };
+        if reference_reserve.map_or(false, |reserve| reserve < options.min_reserve_value) {
+            return Err(Error::BelowThreshold { hop });
+        }
+
+        amount = Library::get_amount_out(amount, reserve_in, reserve_out)?;
+        hops.push((reserve_in, reserve_out));
+    }
+    Ok((amount, route_liquidity(&hops)?))
+}
+
+/// Returns the smallest output reserve of `reserves`, each valued in the last output token by
+/// quoting it through the following pairs.
+fn route_liquidity(reserves: &[(U256, U256)]) -> Result<U256> {
+    let mut liquidity = U256::MAX;
+    for (i, &(_, reserve_out)) in reserves.iter().enumerate() {
+        let mut value = reserve_out;
+        for &(reserve_in, reserve_out) in &reserves[i + 1..] {
+            value = Library::quote(value, reserve_in, reserve_out)?;
+        }
+        liquidity = liquidity.min(value);
+    }
+    Ok(liquidity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_providers::{Http, Provider};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    #[cfg(all(feature = "mock", feature = "addresses"))]
+    async fn can_quote_eth_in() {
+        use crate::{contracts::addresses::address, mock::MockReserves};
+
+        let mock = MockReserves::new(1);
+        let client = Arc::new(mock.clone().into_provider());
+        let chain = Chain::Mainnet;
+        let protocol = ProtocolType::UniswapV2;
+        let factory = Factory::new_with_chain(client.clone(), chain, protocol).unwrap();
+        let (weth, usdc, dai) =
+            (address("WETH", chain), address("USDC", chain), address("DAI", chain));
+        let [direct, routed] = MockReserves::tokens();
+        let base = 10u128.pow(9);
+        mock.set_pair_reserves(&factory, weth, 1000 * base, direct, 2000 * base)
+            .set_pair_reserves(&factory, weth, 1000 * base, usdc, 2_000_000 * base)
+            .set_pair_reserves(&factory, usdc, 1000 * base, routed, 1000 * base)
+            .set_pair_reserves(&factory, weth, 1000 * base, dai, 2_000_000 * base)
+            .set_pair_reserves(&factory, dai, 1000 * base, routed, 2000 * base);
+        let amount = U256::from(base);
+
+        let amounts = quote_eth_in(client.clone(), chain, protocol, amount, direct).await.unwrap();
+        assert_eq!(
+            amounts,
+            Library::get_amounts_out(&factory, amount, &[weth, direct]).await.unwrap()
+        );
+
+        // the best connector
+        let amounts = quote_eth_in(client.clone(), chain, protocol, amount, routed).await.unwrap();
+        let expected =
+            Library::get_amounts_out(&factory, amount, &[weth, dai, routed]).await.unwrap();
+        assert_eq!(amounts, expected);
+
+        let res = quote_eth_in(client.clone(), chain, protocol, amount, Address::repeat_byte(0x33));
+        assert!(res.await.is_err());
+        let res = quote_eth_in(client, chain, protocol, amount, weth).await;
+        assert!(matches!(res.unwrap_err(), Error::SwapToSelf));
+    }
+
+    #[test]
+    fn can_compare_routes() {
+        let (a, b, c) =
+            (Address::repeat_byte(0x11), Address::repeat_byte(0x22), Address::repeat_byte(0x33));
+        let amount_in = U256::exp10(18);
+        // 2000 USDC per ETH
+        let price = 2e-9;
+        let gas_price = U256::from(50) * U256::exp10(9);
+
+        let direct = RouteQuote::new(vec![a, c], amount_in, U256::from(1000) * U256::exp10(6));
+        let hop = RouteQuote::new(vec![a, b, c], amount_in, U256::from(1001) * U256::exp10(6));
+        assert_eq!(hop.gas_estimate - direct.gas_estimate, RouteQuote::GAS_PER_HOP.into());
+        // 60k * 50 gwei * 2000 = 6 USDC
+        assert_eq!(
+            hop.gas_cost(gas_price, price) - direct.gas_cost(gas_price, price),
+            6_000_000.into()
+        );
+
+        let routes = [direct, hop];
+        assert_eq!(compare_routes(&routes, gas_price, price), Some(0));
+        assert_eq!(compare_routes(&routes, U256::zero(), price), Some(1));
+        assert_eq!(compare_routes(&[], gas_price, price), None);
+
+        // without gas per hop the longer route wins
+        #[derive(Debug)]
+        struct Flat;
+        impl GasModel for Flat {
+            fn hop_cost(&self, _: ProtocolType) -> U256 {
+                U256::zero()
+            }
+        }
+        let protocol = ProtocolType::UniswapV2;
+        let compare = |model: &dyn GasModel| {
+            compare_routes_with_gas_model(&routes, protocol, model, gas_price, price)
+        };
+        assert_eq!(compare(&DefaultGasModel), Some(0));
+        assert_eq!(compare(&Flat), Some(1));
+    }
+
+    #[test]
+    fn can_score_routes() {
+        let (a, b) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        let shallow =
+            RouteQuote::new(vec![a, b], 1.into(), 1010.into()).with_liquidity(10_000.into());
+        let deep =
+            RouteQuote::new(vec![a, b], 1.into(), 1000.into()).with_liquidity(1_000_000.into());
+
+        assert!(route_score(&shallow, 0.0) > route_score(&deep, 0.0));
+        assert!(route_score(&shallow, 0.1) < route_score(&deep, 0.1));
+        assert_eq!(route_score(&RouteQuote::default(), 0.1), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn can_find_routes() {
+        let [a, b, c, d] = [0x11, 0x22, 0x33, 0x44].map(Address::repeat_byte);
+        assert_eq!(find_routes(a, b, &[c, d], 1), vec![vec![a, b]]);
+        assert_eq!(
+            find_routes(a, b, &[c, d, c, a, b], 2),
+            vec![vec![a, b], vec![a, c, b], vec![a, d, b]]
+        );
+
+        let routes = find_routes(a, b, &[c, d], 3);
+        assert_eq!(routes.len(), 5);
+        assert!(routes.contains(&vec![a, c, d, b]) && routes.contains(&vec![a, d, c, b]));
+        assert!(routes.windows(2).all(|w| w[0].len() <= w[1].len()));
+
+        assert!(find_routes(a, b, &[c], 0).is_empty());
+        assert!(find_routes(a, a, &[c], 2).is_empty());
+    }
+
+    #[test]
+    fn can_account_gas_refunds() {
+        let (zero, one) = (U256::zero(), U256::one());
+        assert_eq!(GasAccounting::sstore(zero, one), (22_100.into(), zero));
+        assert_eq!(GasAccounting::sstore(one, 2.into()), (5_000.into(), zero));
+        assert_eq!(GasAccounting::sstore(one, one), (2_200.into(), zero));
+        assert_eq!(GasAccounting::sstore(one, zero), (5_000.into(), 4_800.into()));
+
+        assert!(GasAccounting::approval_refunds(U256::MAX, zero));
+        assert!(!GasAccounting::approval_refunds(zero, U256::MAX));
+        assert!(!GasAccounting::approval_refunds(zero, zero));
+        assert_eq!(GasAccounting::unwrap_refund(100.into(), 100.into()), 4_800.into());
+        assert_eq!(GasAccounting::unwrap_refund(100.into(), 99.into()), zero);
+        assert_eq!(GasAccounting::transfer_from_refund(100.into(), 100.into()), 4_800.into());
+        assert_eq!(GasAccounting::transfer_from_refund(100.into(), 99.into()), zero);
+        assert_eq!(GasAccounting::transfer_from_refund(U256::MAX, U256::MAX), zero);
+
+        // refund is capped at a fifth of the gas used
+        assert_eq!(
+            GasAccounting::adjust_approval_estimate(46_000.into(), one, zero),
+            41_200.into()
+        );
+        assert_eq!(
+            GasAccounting::adjust_approval_estimate(20_000.into(), one, zero),
+            16_000.into()
+        );
+        assert_eq!(
+            GasAccounting::adjust_approval_estimate(46_000.into(), one, 2.into()),
+            46_000.into()
+        );
+
+        let quote = RouteQuote::new(vec![Address::zero(); 2], one, one);
+        let gas_estimate = quote.gas_estimate;
+        let refund = GasAccounting::transfer_from_refund(one, one);
+        assert_eq!(quote.with_gas_refund(refund).gas_estimate, gas_estimate - 4_800);
+    }
+
+    #[test]
+    fn can_use_gas_model() {
+        #[derive(Debug)]
+        struct Expensive;
+        impl GasModel for Expensive {
+            fn base_cost(&self) -> U256 {
+                U256::zero()
+            }
+
+            fn hop_cost(&self, _: ProtocolType) -> U256 {
+                1_000_000.into()
+            }
+        }
+
+        let path = vec![Address::repeat_byte(0x11); 4];
+        let (amount_in, amount_out) = (U256::one(), U256::one());
+        let quote = RouteQuote::new(path.clone(), amount_in, amount_out);
+        let v3 = RouteQuote::new_with_gas_model(
+            path.clone(),
+            amount_in,
+            amount_out,
+            ProtocolType::UniswapV3,
+            &DefaultGasModel,
+        );
+        assert!(v3.gas_estimate > quote.gas_estimate);
+
+        let model: Box<dyn GasModel> = Box::new(Expensive);
+        let protocol = ProtocolType::Pancakeswap;
+        let quote = RouteQuote::new_with_gas_model(path, amount_in, amount_out, protocol, &*model);
+        assert_eq!(quote.gas_estimate, 3_000_000.into());
+    }
+
+    #[test]
+    fn can_validate_route() {
+        let client = Arc::new(Provider::<Http>::try_from("http://example.com").unwrap());
+        let factory = Factory::new(client, Address::zero(), ProtocolType::UniswapV2);
+        let (a, b) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+
+        let route = Route::new(factory.clone(), vec![a, b, a]).unwrap();
+        assert_eq!(route.path(), &[a, b, a]);
+        assert_eq!(route.protocol(), ProtocolType::UniswapV2);
+
+        let res = Route::new(factory.clone(), vec![a]);
+        assert!(matches!(res.unwrap_err(), Error::InvalidPath));
+        let res = Route::new(factory, vec![a, b, b]);
+        assert!(matches!(res.unwrap_err(), Error::IdenticalAddresses));
+    }
+
+    #[test]
+    #[cfg(feature = "addresses")]
+    fn can_prepare_comparison() {
+        let client = Arc::new(Provider::<Http>::try_from("http://example.com").unwrap());
+        let (a, b) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        let entries = [
+            (ProtocolType::UniswapV2, vec![a, b]),
+            (ProtocolType::Sushiswap, vec![a]),
+            (ProtocolType::UniswapV3, vec![a, b]),
+            (ProtocolType::Pancakeswap, vec![a, b]),
+        ];
+
+        let routes = prepare_comparison(client, Chain::Mainnet, &entries);
+        assert_eq!(routes[0].as_ref().unwrap().protocol(), ProtocolType::UniswapV2);
+        assert!(matches!(routes[1], Err(Error::InvalidPath)));
+        assert!(matches!(routes[2], Err(Error::NotV2Protocol)));
+        assert!(matches!(routes[3], Err(Error::MissingAddress("PancakeFactory"))));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_filter_routes_by_reserves() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b, c, weth] = MockReserves::tokens();
+        let base = U256::exp10(18);
+        // a -> weth (thin) -> c has a better price than a -> b -> c
+        mock.set_pair_reserves(&factory, a, base, weth, base * 2)
+            .set_pair_reserves(&factory, weth, base * 2, c, base * 4)
+            .set_pair_reserves(&factory, a, base * 1000, b, base * 1000)
+            .set_pair_reserves(&factory, b, base * 1000, c, base * 1000);
+        let paths = vec![vec![a, weth, c], vec![a, b, c]];
+        let amount_in = base / 1000;
+
+        let best = best_route_concurrent(&factory, amount_in, paths.clone(), 2).await.unwrap();
+        assert_eq!(best.path, paths[0]);
+
+        let options = RouteOptions {
+            reference_token: Some(weth),
+            min_reserve_value: base * 10,
+            ..Default::default()
+        };
+        let best =
+            best_route_concurrent_with_options(&factory, amount_in, paths.clone(), 2, &options)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(best.path, paths[1]);
+
+        // near-tie broken by the deeper liquidity of a -> b -> c
+        let liquidity = |weight| RouteOptions { liquidity_weight: weight, ..Default::default() };
+        let best = best_route_concurrent_with_options(
+            &factory,
+            amount_in,
+            paths.clone(),
+            2,
+            &liquidity(0.1),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(best.path, paths[0]);
+        assert_eq!(best.liquidity, base * 4);
+        let best = best_route_concurrent_with_options(
+            &factory,
+            amount_in,
+            paths.clone(),
+            2,
+            &liquidity(0.5),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(best.path, paths[1]);
+        assert_eq!(best.liquidity, base * 1000);
+
+        // routing through weth is blocked
+        let options = RouteOptions { blocklist: HashSet::from([weth]), ..Default::default() };
+        let best =
+            best_route_concurrent_with_options(&factory, amount_in, paths.clone(), 2, &options)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(best.path, paths[1]);
+
+        let options = RouteOptions { blocklist: HashSet::from([c]), ..Default::default() };
+        let res = best_route_concurrent_with_options(&factory, amount_in, paths, 2, &options);
+        assert!(matches!(res.await.unwrap_err(), Error::BlockedToken(token) if token == c));
+
+        // the direct route has a slightly worse output, but costs one hop less
+        #[derive(Debug)]
+        struct Expensive;
+        impl GasModel for Expensive {
+            fn hop_cost(&self, _: ProtocolType) -> U256 {
+                1_000_000.into()
+            }
+        }
+        mock.set_pair_reserves(&factory, a, base * 1000, c, base * 3980);
+        let paths = vec![vec![a, weth, c], vec![a, c]];
+        let best = best_route_concurrent(&factory, amount_in, paths.clone(), 2).await.unwrap();
+        assert_eq!(best.path, paths[0]);
+        let options = RouteOptions {
+            gas_model: Some(Arc::new(Expensive)),
+            gas_price: U256::exp10(9),
+            native_price_in_output_token: 1.0,
+            ..Default::default()
+        };
+        let best =
+            best_route_concurrent_with_options(&factory, amount_in, paths.clone(), 2, &options)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(best.path, paths[1]);
+        assert_eq!(best.gas_estimate, Expensive.route_cost(ProtocolType::UniswapV2, 1));
+    }
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    #[cfg(feature = "addresses")]
+    async fn can_find_best_route_concurrent() {
+        use crate::{contracts::addresses::address, ProtocolType};
+        use ethers_core::types::Chain;
+        use ethers_providers::MAINNET;
+
+        let chain = Chain::Mainnet;
+        let factory =
+            Factory::new_with_chain(MAINNET.provider().into(), chain, ProtocolType::UniswapV2)
+                .unwrap();
+        let (weth, usdc, dai) =
+            (address("WETH", chain), address("USDC", chain), address("DAI", chain));
+        let paths =
+            vec![vec![weth, usdc], vec![weth, dai, usdc], vec![weth, Address::zero(), usdc]];
+
+        let best = best_route_concurrent(&factory, U256::exp10(18), paths, 2).await.unwrap();
+        assert_eq!(best.path.first(), Some(&weth));
+        assert_eq!(best.path.last(), Some(&usdc));
+        assert!(!best.amount_out.is_zero());
+    }
+}
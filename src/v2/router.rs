@@ -24,6 +24,21 @@ contract_struct! {
     }
 }
 
+/// Returns whether the router at `router` belongs to `factory`, by reading the router's
+/// `factory()` getter.
+///
+/// This catches configurations mixing up the addresses of different protocols, since the router
+/// would swap through the pairs of another factory than the one used for quoting.
+#[doc(alias = "dry_run_only")]
+pub async fn router_matches_factory<M: Middleware>(
+    client: Arc<M>,
+    router: Address,
+    factory: Address,
+) -> Result<bool> {
+    let router = IUniswapV2Router02::new(router, client);
+    Ok(router.factory().call().await? == factory)
+}
+
 impl<M: Middleware> Router<M> {
     /// Creates a new instance using the provided address.
     pub fn new(client: Arc<M>, address: Address) -> Self {
@@ -169,7 +184,11 @@ impl<M: Middleware> Router<M> {
     /// See documentation of [Dex] for more details on arguments.
     ///
     /// Note: this function does not perform many sanity checks and it should be called by using the
-    /// [Dex] struct.
+    /// [Dex] struct. Returns [`Error::UnsupportedNativeSwap`] when swapping the native token on a
+    /// protocol that does not [use the standard
+    /// methods](crate::ProtocolType::uses_eth_router_methods), and
+    /// [`Error::NativeWrapperMismatch`] when `weth` is not the [wrapped native
+    /// token](crate::utils::native_wrapper_symbol) of the factory's chain in the addressbook.
     ///
     /// [UniswapV2Router]: https://github.com/Uniswap/v2-periphery/blob/master/contracts/UniswapV2Router01.sol
     /// [Dex]: crate::Dex
@@ -185,6 +204,17 @@ impl<M: Middleware> Router<M> {
     ) -> Result<ContractCall<M, Vec<U256>>> {
         let router = self.contract();
         let (from_native, to_native) = is_native_path(path);
+        if (from_native || to_native) && !factory.protocol().uses_eth_router_methods() {
+            return Err(Error::UnsupportedNativeSwap);
+        }
+        #[cfg(feature = "addresses")]
+        if let (true, Some(chain)) = (from_native || to_native, factory.chain()) {
+            let symbol = crate::utils::native_wrapper_symbol(chain);
+            let wrapper = crate::contracts::addresses::try_address(symbol, chain);
+            if wrapper.map_or(false, |wrapper| wrapper != weth) {
+                return Err(Error::NativeWrapperMismatch { weth });
+            }
+        }
         let mut path = path.to_vec();
         map_native(&mut path, weth);
         let call = match amount {
@@ -259,3 +289,28 @@ impl<M: Middleware> Router<M> {
         Ok(call)
     }
 }
+
+#[cfg(all(test, feature = "addresses", feature = "mock"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn can_reject_wrong_native_wrapper() {
+        use crate::{constants::NATIVE_ADDRESS, contracts::addresses::address, mock::MockReserves};
+
+        let mut factory = MockReserves::new(1).factory();
+        factory.set_chain(Chain::BinanceSmartChain);
+        let router = Router::new(factory.client(), Address::repeat_byte(0xee));
+
+        let [token] = MockReserves::tokens();
+        let path = [NATIVE_ADDRESS, token];
+        let amount = Amount::ExactIn(U256::exp10(18));
+        let weth = address("WETH", Chain::BinanceSmartChain);
+        let swap = router.swap(&factory, amount, 100.0, &path, Address::zero(), U256::MAX, weth);
+        assert!(swap.await.is_ok());
+
+        let weth = address("WETH", Chain::Mainnet);
+        let swap = router.swap(&factory, amount, 100.0, &path, Address::zero(), U256::MAX, weth);
+        assert!(matches!(swap.await.unwrap_err(), Error::NativeWrapperMismatch { .. }));
+    }
+}
@@ -0,0 +1,230 @@
+use super::{Factory, Library, PairReserves};
+use crate::{
+    contracts::bindings::i_uniswap_v2_pair::IUniswapV2Pair,
+    errors::{Error, Result},
+};
+use ethers_contract::{ContractError, Multicall, MulticallVersion};
+use ethers_core::{
+    abi::Tokenizable,
+    types::{Address, BlockNumber, Chain, U256},
+};
+use ethers_providers::Middleware;
+
+/// The reserves of a set of pairs at a specific block, used for replaying quotes offline.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PoolSnapshot {
+    /// The block number at which the reserves were captured.
+    pub block: u64,
+
+    /// The chain ID, if known.
+    pub chain_id: Option<u64>,
+
+    /// The reserves of the pairs.
+    pub pairs: Vec<PairReserves>,
+}
+
+impl PoolSnapshot {
+    /// Returns the snapshot's chain, if known.
+    pub fn chain(&self) -> Option<Chain> {
+        self.chain_id.and_then(|id| Chain::try_from(id).ok())
+    }
+
+    /// Returns the snapshot's reserves for the pair of `a` and `b`, sorted as `(reserve_a,
+    /// reserve_b)`.
+    pub fn get_reserves(&self, a: Address, b: Address) -> Result<(U256, U256)> {
+        let (token0, token1) = Library::sort_tokens(a, b);
+        self.pairs
+            .iter()
+            .find(|pair| pair.token0 == token0 && pair.token1 == token1)
+            .and_then(|pair| pair.reserves_for(a))
+            .ok_or(Error::PairNotFound)
+    }
+}
+
+impl Library {
+    /// Fetches the reserves of `pairs` at `block` and stores them in a [PoolSnapshot]. Makes only 1
+    /// call to the client by using [Multicall], plus 1 call to resolve the pairs' addresses with
+    /// [`PairResolution::OnChain`](super::PairResolution::OnChain).
+    #[doc(alias = "dry_run_only")]
+    pub async fn capture_snapshot<M: Middleware>(
+        factory: &Factory<M>,
+        pairs: &[(Address, Address)],
+        block: u64,
+    ) -> Result<PoolSnapshot> {
+        let client = factory.client();
+        let mut multicall = Multicall::new(client.clone(), None)
+            .await?
+            .version(MulticallVersion::Multicall)
+            .block(block);
+
+        let addresses = Self::resolve_pairs(factory, pairs).await?;
+        let pair = IUniswapV2Pair::new(Address::zero(), client);
+        let call = pair.get_reserves();
+        let mut sorted = Vec::with_capacity(pairs.len());
+        for (&(a, b), address) in pairs.iter().zip(addresses) {
+            let (token0, token1) = Self::sort_tokens(a, b);
+            sorted.push((token0, token1, address));
+
+            let mut call = call.clone();
+            call.tx.set_to(address);
+            multicall.add_call(call, false);
+        }
+
+        let pairs = multicall
+            .call_raw()
+            .await?
+            .into_iter()
+            .zip(sorted)
+            .map(|(token, (token0, token1, pair))| {
+                let (reserve0, reserve1, _): (U256, U256, u32) = Tokenizable::from_token(token)?;
+                Ok(PairReserves { token0, token1, reserve0, reserve1, pair })
+            })
+            .collect::<Result<_>>()?;
+
+        let chain_id = factory.chain().map(|chain| chain as u64);
+        Ok(PoolSnapshot { block, chain_id, pairs })
+    }
+
+    /// Same as [`get_reserves_multi`](Self::get_reserves_multi), but pins the call to the latest
+    /// block and verifies that its hash has not changed after fetching.
+    ///
+    /// Returns [`Error::Reorg`] if the block was reorganized in the meantime.
+    #[doc(alias = "dry_run_only")]
+    pub async fn get_reserves_multi_consistent<M: Middleware>(
+        factory: &Factory<M>,
+        path: &[Address],
+    ) -> Result<Vec<(U256, U256)>> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+
+        let client = factory.client();
+        let block = client
+            .get_block(BlockNumber::Latest)
+            .await
+            .map_err(ContractError::<M>::MiddlewareError)?
+            .ok_or(Error::Reorg)?;
+        let (number, hash) = block.number.zip(block.hash).ok_or(Error::Reorg)?;
+
+        let pairs: Vec<_> = path.windows(2).map(|slice| (slice[0], slice[1])).collect();
+        let snapshot = Self::capture_snapshot(factory, &pairs, number.as_u64()).await?;
+
+        let block = client.get_block(number).await.map_err(ContractError::<M>::MiddlewareError)?;
+        if block.and_then(|block| block.hash) != Some(hash) {
+            return Err(Error::Reorg);
+        }
+
+        pairs
+            .into_iter()
+            .zip(snapshot.pairs)
+            .map(|((a, _), pair)| pair.reserves_for(a).ok_or(Error::TokenNotInPair))
+            .collect()
+    }
+
+    /// Performs chained get_amount_out calculations on any number of pairs, using only the
+    /// reserves stored in `snapshot`.
+    pub fn get_amounts_out_from_snapshot(
+        snapshot: &PoolSnapshot,
+        amount_in: U256,
+        path: &[Address],
+    ) -> Result<Vec<U256>> {
+        if path.len() < 2 {
+            return Err(Error::InvalidPath);
+        }
+
+        let mut amounts = Vec::with_capacity(path.len());
+        amounts.push(amount_in);
+        for (i, slice) in path.windows(2).enumerate() {
+            let (reserve_in, reserve_out) = snapshot.get_reserves(slice[0], slice[1])?;
+            amounts.push(Self::get_amount_out(amounts[i], reserve_in, reserve_out)?);
+        }
+        Ok(amounts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    #[cfg(feature = "addresses")]
+    async fn can_get_reserves_multi_consistent() {
+        use crate::{contracts::addresses::address, ProtocolType};
+        use ethers_providers::MAINNET;
+
+        let chain = Chain::Mainnet;
+        let factory =
+            Factory::new_with_chain(MAINNET.provider().into(), chain, ProtocolType::UniswapV2)
+                .unwrap();
+        let path = [address("WETH", chain), address("USDC", chain), address("WETH", chain)];
+        let reserves = Library::get_reserves_multi_consistent(&factory, &path).await.unwrap();
+        assert_eq!(reserves[0], (reserves[1].1, reserves[1].0));
+    }
+
+    fn snapshot() -> PoolSnapshot {
+        let base = U256::exp10(18);
+        let (a, b, c) =
+            (Address::repeat_byte(0x11), Address::repeat_byte(0x22), Address::repeat_byte(0x33));
+        let pair = |(token_a, reserve_a), (token_b, reserve_b)| {
+            let (token0, token1) = Library::sort_tokens(token_a, token_b);
+            let (reserve0, reserve1) =
+                if token0 == token_a { (reserve_a, reserve_b) } else { (reserve_b, reserve_a) };
+            PairReserves { token0, token1, reserve0, reserve1, pair: Address::random() }
+        };
+        let pairs = vec![
+            pair((a, U256::from(1000) * base), (b, U256::from(2000) * base)),
+            pair((c, U256::from(3000) * base), (b, U256::from(1000) * base)),
+        ];
+        PoolSnapshot { block: 1, chain_id: Some(1), pairs }
+    }
+
+    #[test]
+    fn can_get_amounts_out_from_snapshot() {
+        let snapshot = snapshot();
+        assert_eq!(snapshot.chain(), Some(Chain::Mainnet));
+
+        let (a, b, c) =
+            (Address::repeat_byte(0x11), Address::repeat_byte(0x22), Address::repeat_byte(0x33));
+        let amount_in = U256::exp10(18);
+        let amounts = Library::get_amounts_out_from_snapshot(&snapshot, amount_in, &[a, b, c]);
+        let amounts = amounts.unwrap();
+
+        let (reserve_a, reserve_b) = snapshot.get_reserves(a, b).unwrap();
+        let amount_b = Library::get_amount_out(amount_in, reserve_a, reserve_b).unwrap();
+        let (reserve_b, reserve_c) = snapshot.get_reserves(b, c).unwrap();
+        let amount_c = Library::get_amount_out(amount_b, reserve_b, reserve_c).unwrap();
+        assert_eq!(amounts, vec![amount_in, amount_b, amount_c]);
+
+        let res = Library::get_amounts_out_from_snapshot(&snapshot, amount_in, &[a, c]);
+        assert!(matches!(res.unwrap_err(), Error::PairNotFound));
+    }
+
+    #[test]
+    fn can_quote_pools() {
+        use crate::Pool;
+
+        let snapshot = snapshot();
+        let (a, b) = (Address::repeat_byte(0x11), Address::repeat_byte(0x22));
+        let pools: Vec<Box<dyn Pool>> =
+            snapshot.pairs.iter().map(|pair| Box::new(*pair) as Box<dyn Pool>).collect();
+
+        let amount_in = U256::exp10(18);
+        let (reserve_a, reserve_b) = snapshot.get_reserves(a, b).unwrap();
+        let expected = Library::get_amount_out(amount_in, reserve_a, reserve_b).unwrap();
+        assert_eq!(pools[0].tokens().unwrap(), Library::sort_tokens(a, b));
+        assert_eq!(pools[0].amount_out(a, amount_in).unwrap(), expected);
+
+        let res = pools[0].amount_out(Address::zero(), amount_in);
+        assert!(matches!(res.unwrap_err(), Error::TokenNotInPair));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn can_serialize_snapshot() {
+        let snapshot = snapshot();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<PoolSnapshot>(&json).unwrap(), snapshot);
+    }
+}
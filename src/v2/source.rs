@@ -0,0 +1,321 @@
+use super::{Factory, Library, ReservesDecoder};
+use crate::errors::{Error, Result};
+use ethers_contract::{Contract, ContractError};
+use ethers_core::{
+    abi::{Abi, Token},
+    types::{Address, U256},
+};
+use ethers_providers::Middleware;
+use futures_util::future::{BoxFuture, FutureExt};
+use std::{fmt, sync::RwLock};
+
+/// A source of the reserves of Uniswap V2 pairs.
+///
+/// This abstracts how the reserves are fetched, so that scanners can switch between
+/// [Multicall](ethers_contract::Multicall), a [lens contract](LensReserveSource), or other
+/// sources without changing how they consume the reserves.
+pub trait ReserveSource: fmt::Debug + Send + Sync {
+    /// Fetches the reserves of the pair of each `(a, b)` of `pairs`, sorted like the tokens
+    /// provided, like [`Library::get_reserves`].
+    fn get_reserves<'a>(
+        &'a self,
+        pairs: &'a [(Address, Address)],
+    ) -> BoxFuture<'a, Result<Vec<(U256, U256)>>>;
+}
+
+/// A [ReserveSource] that fetches the reserves of the pairs deployed by a factory with only 1
+/// [Multicall](ethers_contract::Multicall), after resolving their addresses with
+/// [`Library::resolve_pairs`].
+#[derive(Clone, Debug)]
+pub struct MulticallReserveSource<M> {
+    /// The factory of the pairs.
+    factory: Factory<M>,
+}
+
+impl<M> MulticallReserveSource<M> {
+    /// Creates a new instance using the provided factory.
+    pub fn new(factory: Factory<M>) -> Self {
+        Self { factory }
+    }
+
+    /// Returns the factory of the pairs.
+    pub fn factory(&self) -> &Factory<M> {
+        &self.factory
+    }
+}
+
+impl<M: Middleware> ReserveSource for MulticallReserveSource<M> {
+    fn get_reserves<'a>(
+        &'a self,
+        pairs: &'a [(Address, Address)],
+    ) -> BoxFuture<'a, Result<Vec<(U256, U256)>>> {
+        multicall_reserves(&self.factory, pairs).boxed()
+    }
+}
+
+/// A [ReserveSource] that fails over between an ordered list of factories, for reading reserves
+/// from several RPC endpoints.
+///
+/// The factories should be the same factory, each with the client of a different endpoint. The
+/// reserves are fetched like with a [MulticallReserveSource], from the first factory whose client
+/// succeeds. The next factory is only tried on [transport errors](Error::is_transport): other
+/// errors, like reverts, are deterministic and are returned immediately.
+#[derive(Clone, Debug)]
+pub struct FallbackReserveSource<M> {
+    /// The factories, in order of preference.
+    factories: Vec<Factory<M>>,
+}
+
+impl<M> FallbackReserveSource<M> {
+    /// Creates a new instance using the provided factories, in order of preference.
+    ///
+    /// # Panics
+    ///
+    /// If `factories` is empty.
+    pub fn new(factories: Vec<Factory<M>>) -> Self {
+        assert!(!factories.is_empty(), "at least one factory is required");
+        Self { factories }
+    }
+
+    /// Returns the factories, in order of preference.
+    pub fn factories(&self) -> &[Factory<M>] {
+        &self.factories
+    }
+}
+
+impl<M: Middleware> ReserveSource for FallbackReserveSource<M> {
+    fn get_reserves<'a>(
+        &'a self,
+        pairs: &'a [(Address, Address)],
+    ) -> BoxFuture<'a, Result<Vec<(U256, U256)>>> {
+        async move {
+            let mut last_error = None;
+            for factory in &self.factories {
+                match multicall_reserves(factory, pairs).await {
+                    Err(e) if e.is_transport() => last_error = Some(e),
+                    res => return res,
+                }
+            }
+            Err(last_error.expect("factories is not empty"))
+        }
+        .boxed()
+    }
+}
+
+/// A [ReserveSource] that fetches the reserves of many pairs with a single, non-multicall
+/// `eth_call` to a helper (lens) contract, like a `BalanceChecker`.
+///
+/// The lens' function is looked up by name in the provided ABI. It must take the pairs' addresses
+/// (`address[]`) as its only argument and return, for every pair in order, the result of its
+/// `getReserves`: a `(uint112,uint112,uint32)[]` or an equivalent `uint256[3][]`.
+///
+/// Whether the lens is deployed is checked once with `eth_getCode`. If it is not, the reserves are
+/// fetched with a [MulticallReserveSource] instead.
+#[derive(Debug)]
+pub struct LensReserveSource<M> {
+    /// The lens contract.
+    lens: Contract<M>,
+
+    /// The name of the lens' function.
+    function: String,
+
+    /// The fallback used when the lens is not deployed.
+    multicall: MulticallReserveSource<M>,
+
+    /// Whether the lens is deployed, once checked.
+    deployed: RwLock<Option<bool>>,
+}
+
+impl<M: Middleware> LensReserveSource<M> {
+    /// Creates a new instance for the pairs deployed by `factory`, that calls `function` of the
+    /// lens deployed at `address` with the provided ABI.
+    pub fn new(
+        factory: Factory<M>,
+        address: Address,
+        abi: Abi,
+        function: impl Into<String>,
+    ) -> Self {
+        let lens = Contract::new(address, abi, factory.client());
+        Self {
+            lens,
+            function: function.into(),
+            multicall: MulticallReserveSource::new(factory),
+            deployed: RwLock::new(None),
+        }
+    }
+
+    /// Returns the address of the lens contract.
+    pub fn address(&self) -> Address {
+        self.lens.address()
+    }
+
+    /// Returns whether there is code deployed at the lens' address. Makes a call to the client
+    /// only the first time.
+    #[doc(alias = "dry_run_only")]
+    pub async fn is_deployed(&self) -> Result<bool> {
+        if let Some(deployed) = *self.deployed.read().unwrap() {
+            return Ok(deployed);
+        }
+        let code = self
+            .multicall
+            .factory
+            .client()
+            .get_code(self.address(), None)
+            .await
+            .map_err(ContractError::<M>::MiddlewareError)?;
+        let deployed = !code.is_empty();
+        *self.deployed.write().unwrap() = Some(deployed);
+        Ok(deployed)
+    }
+
+    async fn lens_reserves(&self, pairs: &[(Address, Address)]) -> Result<Vec<(U256, U256)>> {
+        let factory = &self.multicall.factory;
+        let addresses = Library::resolve_pairs(factory, pairs).await?;
+        let result: Token = self
+            .lens
+            .method(&self.function, (addresses,))
+            .map_err(|e| Error::ContractError(e.to_string()))?
+            .call()
+            .await?;
+        decode_lens_reserves(result, pairs)
+    }
+}
+
+impl<M: Middleware> ReserveSource for LensReserveSource<M> {
+    fn get_reserves<'a>(
+        &'a self,
+        pairs: &'a [(Address, Address)],
+    ) -> BoxFuture<'a, Result<Vec<(U256, U256)>>> {
+        async move {
+            if pairs.is_empty() {
+                return Ok(vec![]);
+            }
+            if self.is_deployed().await? {
+                self.lens_reserves(pairs).await
+            } else {
+                self.multicall.get_reserves(pairs).await
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Fetches the reserves of `pairs` with only 1 multicall.
+async fn multicall_reserves<M: Middleware>(
+    factory: &Factory<M>,
+    pairs: &[(Address, Address)],
+) -> Result<Vec<(U256, U256)>> {
+    if pairs.is_empty() {
+        return Ok(vec![]);
+    }
+    let addresses = Library::resolve_pairs(factory, pairs).await?;
+    Library::fetch_reserves(factory, pairs, &addresses).await
+}
+
+/// Decodes the reserves returned by a lens, sorted like the tokens of `pairs`.
+fn decode_lens_reserves(result: Token, pairs: &[(Address, Address)]) -> Result<Vec<(U256, U256)>> {
+    let reserves = match result {
+        Token::Array(reserves) | Token::FixedArray(reserves) if reserves.len() == pairs.len() => {
+            reserves
+        }
+        _ => return Err(Error::ContractError("Invalid lens reserves".to_string())),
+    };
+    // the decoder expects the tuples returned by `getReserves`
+    let reserves: Vec<_> = reserves
+        .into_iter()
+        .map(|token| match token {
+            Token::Array(values) | Token::FixedArray(values) => Token::Tuple(values),
+            token => token,
+        })
+        .collect();
+    ReservesDecoder::for_pairs(pairs).decode(&reserves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_decode_lens_reserves() {
+        let (a, b, c) =
+            (Address::repeat_byte(0x11), Address::repeat_byte(0x22), Address::repeat_byte(0x33));
+        let reserves = |r0: u64, r1: u64| vec![Token::Uint(r0.into()), Token::Uint(r1.into())];
+        let timestamp = Token::Uint(U256::one());
+
+        // (uint112,uint112,uint32)[]
+        let result = Token::Array(vec![
+            Token::Tuple([reserves(1, 2), vec![timestamp.clone()]].concat()),
+            Token::Tuple([reserves(3, 4), vec![timestamp.clone()]].concat()),
+        ]);
+        let decoded = decode_lens_reserves(result, &[(a, b), (c, b)]).unwrap();
+        assert_eq!(decoded, vec![(1.into(), 2.into()), (4.into(), 3.into())]);
+
+        // uint256[3][]
+        let result =
+            Token::Array(vec![Token::FixedArray([reserves(5, 6), vec![timestamp]].concat())]);
+        let decoded = decode_lens_reserves(result.clone(), &[(b, a)]).unwrap();
+        assert_eq!(decoded, vec![(6.into(), 5.into())]);
+
+        assert!(decode_lens_reserves(result.clone(), &[(a, b), (b, c)]).is_err());
+        assert!(decode_lens_reserves(Token::Uint(U256::one()), &[(a, b)]).is_err());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_fall_back_to_multicall() {
+        use crate::mock::MockReserves;
+
+        let mock = MockReserves::new(1);
+        let factory = mock.factory();
+        let [a, b, c] = MockReserves::tokens();
+        mock.set_pair_reserves(&factory, a, 1000, b, 2000)
+            .set_pair_reserves(&factory, b, 3000, c, 1000);
+        let pairs = [(a, b), (c, b)];
+        let expected = vec![(1000.into(), 2000.into()), (1000.into(), 3000.into())];
+
+        let source = MulticallReserveSource::new(factory.clone());
+        assert_eq!(source.get_reserves(&pairs).await.unwrap(), expected);
+
+        // there is no code at the lens' address
+        let lens = LensReserveSource::new(factory, Address::repeat_byte(0xee), Abi::default(), "f");
+        let sources: [&dyn ReserveSource; 2] = [&source, &lens];
+        for source in sources {
+            assert_eq!(source.get_reserves(&pairs).await.unwrap(), expected);
+        }
+        // the code is checked only once
+        lens.get_reserves(&pairs).await.unwrap();
+        let methods = mock.requested_methods();
+        assert_eq!(methods.iter().filter(|m| *m == "eth_getCode").count(), 1);
+    }
+    #[tokio::test]
+    #[cfg(feature = "mock")]
+    async fn can_fail_over() {
+        use crate::mock::MockReserves;
+
+        let (down, up) = (MockReserves::new(1), MockReserves::new(1));
+        let factories = vec![down.factory(), up.factory()];
+        let [a, b, c] = MockReserves::tokens();
+        for mock in [&down, &up] {
+            mock.set_pair_reserves(&factories[0], a, 1000, b, 2000);
+        }
+        down.set_unavailable(true);
+        let source = FallbackReserveSource::new(factories.clone());
+
+        let reserves = source.get_reserves(&[(a, b)]).await.unwrap();
+        assert_eq!(reserves, vec![(1000.into(), 2000.into())]);
+        assert!(!down.requested_methods().is_empty());
+
+        // the last transport error is returned
+        up.set_unavailable(true);
+        let res = source.get_reserves(&[(a, b)]).await;
+        assert!(res.unwrap_err().is_transport());
+
+        // deterministic errors are not retried
+        down.set_unavailable(false);
+        up.set_unavailable(false);
+        let requests = up.requested_methods().len();
+        let res = source.get_reserves(&[(a, b), (b, c)]).await;
+        assert!(!res.unwrap_err().is_transport());
+        assert_eq!(up.requested_methods().len(), requests);
+    }
+}
@@ -0,0 +1,35 @@
+//! Math functions that do not overflow on intermediate products, ported from Uniswap's
+//! [`FullMath`] library.
+//!
+//! [`FullMath`]: https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/FullMath.sol
+
+use crate::errors::{Error, Result};
+use ethers_core::types::{U256, U512};
+
+/// Calculates `floor(a * b / denominator)` with full precision.
+///
+/// Returns [`Error::Overflow`] if the result does not fit in 256 bits or `denominator` is zero.
+pub(crate) fn mul_div(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    if denominator.is_zero() {
+        return Err(Error::Overflow);
+    }
+    U256::try_from(a.full_mul(b) / U512::from(denominator)).map_err(|_| Error::Overflow)
+}
+
+/// Calculates `ceil(a * b / denominator)` with full precision.
+///
+/// Returns [`Error::Overflow`] if the result does not fit in 256 bits or `denominator` is zero.
+pub(crate) fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> Result<U256> {
+    let result = mul_div(a, b, denominator)?;
+    if (a.full_mul(b) % U512::from(denominator)).is_zero() {
+        Ok(result)
+    } else {
+        result.checked_add(U256::one()).ok_or(Error::Overflow)
+    }
+}
+
+/// Calculates `ceil(a / denominator)`. `denominator` must not be zero.
+pub(crate) fn div_rounding_up(a: U256, denominator: U256) -> U256 {
+    let (quotient, remainder) = a.div_mod(denominator);
+    quotient + !remainder.is_zero() as u8
+}
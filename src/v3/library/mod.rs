@@ -1,3 +1,10 @@
+mod full_math;
+mod sqrt_price_math;
+mod swap_math;
+pub mod tick_math;
+
+pub use swap_math::{swap_within_tick, SwapStep};
+
 /// The default factory enabled fee amounts, denominated in hundredths of bips.
 #[allow(missing_docs)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -10,6 +17,9 @@ pub enum FeeAmount {
 }
 
 impl FeeAmount {
+    /// All the default fee amounts, sorted from lowest to highest.
+    pub const ALL: [Self; 4] = [Self::Lowest, Self::Low, Self::Medium, Self::High];
+
     /// The default factory tick spacings by fee amount.
     pub const fn tick_spacing(&self) -> usize {
         match self {
@@ -0,0 +1,162 @@
+//! Functions based on Q64.96 sqrt prices and liquidity, ported from Uniswap's [`SqrtPriceMath`]
+//! library.
+//!
+//! [`SqrtPriceMath`]: https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/SqrtPriceMath.sol
+
+use super::full_math::{div_rounding_up, mul_div, mul_div_rounding_up};
+use crate::errors::{Error, Result};
+use ethers_core::types::U256;
+
+/// `2 ^ 96`.
+const Q96: U256 = U256([0, 1 << 32, 0, 0]);
+
+/// Returns `value` if it fits in 160 bits, like Solidity's `SafeCast.toUint160`.
+fn to_u160(value: U256) -> Result<U256> {
+    if value.bits() > 160 {
+        return Err(Error::Overflow);
+    }
+    Ok(value)
+}
+
+/// Returns the next sqrt price given a delta of token0, always rounding up.
+fn get_next_sqrt_price_from_amount0_rounding_up(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256> {
+    if amount.is_zero() {
+        return Ok(sqrt_price);
+    }
+    let numerator1 = U256::from(liquidity) << 96;
+    let product = amount.checked_mul(sqrt_price);
+
+    if add {
+        if let Some(denominator) = product.and_then(|product| numerator1.checked_add(product)) {
+            return to_u160(mul_div_rounding_up(numerator1, sqrt_price, denominator)?);
+        }
+        let denominator = (numerator1 / sqrt_price).checked_add(amount).ok_or(Error::Overflow)?;
+        to_u160(div_rounding_up(numerator1, denominator))
+    } else {
+        match product {
+            Some(product) if numerator1 > product => {
+                to_u160(mul_div_rounding_up(numerator1, sqrt_price, numerator1 - product)?)
+            }
+            _ => Err(Error::InsufficientLiquidity),
+        }
+    }
+}
+
+/// Returns the next sqrt price given a delta of token1, always rounding down.
+fn get_next_sqrt_price_from_amount1_rounding_down(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256> {
+    let liquidity = U256::from(liquidity);
+    let fits_u160 = amount.bits() <= 160;
+
+    if add {
+        let quotient =
+            if fits_u160 { (amount << 96) / liquidity } else { mul_div(amount, Q96, liquidity)? };
+        to_u160(sqrt_price.checked_add(quotient).ok_or(Error::Overflow)?)
+    } else {
+        let quotient = if fits_u160 {
+            div_rounding_up(amount << 96, liquidity)
+        } else {
+            mul_div_rounding_up(amount, Q96, liquidity)?
+        };
+        if sqrt_price <= quotient {
+            return Err(Error::InsufficientLiquidity);
+        }
+        Ok(sqrt_price - quotient)
+    }
+}
+
+fn check_price_and_liquidity(sqrt_price: U256, liquidity: u128) -> Result<()> {
+    if sqrt_price.is_zero() {
+        return Err(Error::InvalidSqrtRatio);
+    }
+    if liquidity == 0 {
+        return Err(Error::InsufficientLiquidity);
+    }
+    Ok(())
+}
+
+/// Returns the next sqrt price after adding `amount_in` of token0 if `zero_for_one`, or token1
+/// otherwise.
+pub(crate) fn get_next_sqrt_price_from_input(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Result<U256> {
+    check_price_and_liquidity(sqrt_price, liquidity)?;
+    if zero_for_one {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price, liquidity, amount_in, true)
+    }
+}
+
+/// Returns the next sqrt price after removing `amount_out` of token1 if `zero_for_one`, or token0
+/// otherwise.
+pub(crate) fn get_next_sqrt_price_from_output(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount_out: U256,
+    zero_for_one: bool,
+) -> Result<U256> {
+    check_price_and_liquidity(sqrt_price, liquidity)?;
+    if zero_for_one {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price, liquidity, amount_out, false)
+    } else {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price, liquidity, amount_out, false)
+    }
+}
+
+/// Returns the amount of token0 required to cover a position of `liquidity` between the two sqrt
+/// prices.
+pub(crate) fn get_amount0_delta(
+    mut sqrt_price_a: U256,
+    mut sqrt_price_b: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256> {
+    if sqrt_price_a > sqrt_price_b {
+        (sqrt_price_a, sqrt_price_b) = (sqrt_price_b, sqrt_price_a);
+    }
+    if sqrt_price_a.is_zero() {
+        return Err(Error::InvalidSqrtRatio);
+    }
+
+    let numerator1 = U256::from(liquidity) << 96;
+    let numerator2 = sqrt_price_b - sqrt_price_a;
+    if round_up {
+        let amount = mul_div_rounding_up(numerator1, numerator2, sqrt_price_b)?;
+        Ok(div_rounding_up(amount, sqrt_price_a))
+    } else {
+        Ok(mul_div(numerator1, numerator2, sqrt_price_b)? / sqrt_price_a)
+    }
+}
+
+/// Returns the amount of token1 required to cover a position of `liquidity` between the two sqrt
+/// prices.
+pub(crate) fn get_amount1_delta(
+    mut sqrt_price_a: U256,
+    mut sqrt_price_b: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256> {
+    if sqrt_price_a > sqrt_price_b {
+        (sqrt_price_a, sqrt_price_b) = (sqrt_price_b, sqrt_price_a);
+    }
+
+    let liquidity = U256::from(liquidity);
+    if round_up {
+        mul_div_rounding_up(liquidity, sqrt_price_b - sqrt_price_a, Q96)
+    } else {
+        mul_div(liquidity, sqrt_price_b - sqrt_price_a, Q96)
+    }
+}
@@ -0,0 +1,192 @@
+//! Computes the result of a swap within a single tick range, ported from Uniswap's [`SwapMath`]
+//! library.
+//!
+//! [`SwapMath`]: https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/SwapMath.sol
+
+use super::{
+    full_math::{mul_div, mul_div_rounding_up},
+    sqrt_price_math::{
+        get_amount0_delta, get_amount1_delta, get_next_sqrt_price_from_input,
+        get_next_sqrt_price_from_output,
+    },
+};
+use crate::errors::{Error, Result};
+use ethers_core::types::{Sign, I256, U256};
+
+/// The fee denominator, in hundredths of bips.
+const ONE_IN_PIPS: u32 = 1_000_000;
+
+/// The result of swapping within a single tick range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SwapStep {
+    /// The sqrt price after the swap, as a Q64.96 number. Cannot exceed the target price.
+    pub sqrt_price_next: U256,
+
+    /// The amount to be swapped in, of either token0 or token1, based on the swap direction.
+    pub amount_in: U256,
+
+    /// The amount to be received, of either token0 or token1, based on the swap direction.
+    pub amount_out: U256,
+
+    /// The amount of input that will be taken as a fee.
+    pub fee_amount: U256,
+}
+
+/// Computes the result of swapping some amount in, or amount out, given the parameters of the
+/// swap, like `SwapMath.computeSwapStep`.
+///
+/// The swap direction is token0 for token1 if `sqrt_price_current >= sqrt_price_target`.
+/// `amount_remaining` is positive for an exact input swap and negative for an exact output swap.
+/// `fee` is denominated in hundredths of bips, like [`FeeAmount`](super::FeeAmount).
+///
+/// The fee, plus the amount in, will never exceed the amount remaining if the swap's
+/// `amount_remaining` is positive.
+pub fn swap_within_tick(
+    liquidity: u128,
+    sqrt_price_current: U256,
+    sqrt_price_target: U256,
+    amount_remaining: I256,
+    fee: u32,
+) -> Result<SwapStep> {
+    if fee >= ONE_IN_PIPS {
+        return Err(Error::InvalidFee);
+    }
+    let zero_for_one = sqrt_price_current >= sqrt_price_target;
+    let (sign, amount_remaining_abs) = amount_remaining.into_sign_and_abs();
+    let exact_in = sign == Sign::Positive;
+    let (fee, fee_complement) = (U256::from(fee), U256::from(ONE_IN_PIPS - fee));
+
+    let mut amount_in = U256::zero();
+    let mut amount_out = U256::zero();
+    let sqrt_price_next = if exact_in {
+        let amount_remaining_less_fee =
+            mul_div(amount_remaining_abs, fee_complement, ONE_IN_PIPS.into())?;
+        amount_in = if zero_for_one {
+            get_amount0_delta(sqrt_price_target, sqrt_price_current, liquidity, true)?
+        } else {
+            get_amount1_delta(sqrt_price_current, sqrt_price_target, liquidity, true)?
+        };
+        if amount_remaining_less_fee >= amount_in {
+            sqrt_price_target
+        } else {
+            get_next_sqrt_price_from_input(
+                sqrt_price_current,
+                liquidity,
+                amount_remaining_less_fee,
+                zero_for_one,
+            )?
+        }
+    } else {
+        amount_out = if zero_for_one {
+            get_amount1_delta(sqrt_price_target, sqrt_price_current, liquidity, false)?
+        } else {
+            get_amount0_delta(sqrt_price_current, sqrt_price_target, liquidity, false)?
+        };
+        if amount_remaining_abs >= amount_out {
+            sqrt_price_target
+        } else {
+            get_next_sqrt_price_from_output(
+                sqrt_price_current,
+                liquidity,
+                amount_remaining_abs,
+                zero_for_one,
+            )?
+        }
+    };
+
+    // whether the target price was reached
+    let max = sqrt_price_target == sqrt_price_next;
+    if zero_for_one {
+        if !max || !exact_in {
+            amount_in = get_amount0_delta(sqrt_price_next, sqrt_price_current, liquidity, true)?;
+        }
+        if !max || exact_in {
+            amount_out = get_amount1_delta(sqrt_price_next, sqrt_price_current, liquidity, false)?;
+        }
+    } else {
+        if !max || !exact_in {
+            amount_in = get_amount1_delta(sqrt_price_current, sqrt_price_next, liquidity, true)?;
+        }
+        if !max || exact_in {
+            amount_out = get_amount0_delta(sqrt_price_current, sqrt_price_next, liquidity, false)?;
+        }
+    }
+
+    // cap the output amount to not exceed the remaining output amount
+    if !exact_in && amount_out > amount_remaining_abs {
+        amount_out = amount_remaining_abs;
+    }
+
+    let fee_amount = if exact_in && sqrt_price_next != sqrt_price_target {
+        // we didn't reach the target, so take the remainder of the maximum input as fee
+        amount_remaining_abs - amount_in
+    } else {
+        mul_div_rounding_up(amount_in, fee, fee_complement)?
+    };
+
+    Ok(SwapStep { sqrt_price_next, amount_in, amount_out, fee_amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRICE: U256 = U256([0, 1 << 32, 0, 0]);
+
+    fn u256(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    fn i256(value: i128) -> I256 {
+        I256::from(value)
+    }
+
+    #[test]
+    fn can_swap_exact_in_capped_at_target() {
+        // sqrt(101 / 100)
+        let target = u256("79623317895830914510639640423");
+        let liquidity = 2 * 10u128.pow(18);
+        let step = swap_within_tick(liquidity, PRICE, target, i256(10i128.pow(18)), 600).unwrap();
+        assert_eq!(step.sqrt_price_next, target);
+        assert_eq!(step.amount_in, u256("9975124224178055"));
+        assert_eq!(step.amount_out, u256("9925619580021728"));
+        assert_eq!(step.fee_amount, u256("5988667735148"));
+
+        // exact output that gets capped at the target returns the same amounts
+        let exact_out =
+            swap_within_tick(liquidity, PRICE, target, i256(-10i128.pow(18)), 600).unwrap();
+        assert_eq!(exact_out, step);
+    }
+
+    #[test]
+    fn can_swap_exact_in_fully_spent() {
+        // sqrt(1000 / 100)
+        let target = u256("250541448375047931186413801569");
+        let liquidity = 2 * 10u128.pow(18);
+        let amount = 10i128.pow(18);
+        let step = swap_within_tick(liquidity, PRICE, target, i256(amount), 600).unwrap();
+        assert_eq!(step.sqrt_price_next, u256("118818475322642227089037862318"));
+        assert_eq!(step.amount_in, u256("999400000000000000"));
+        assert_eq!(step.amount_out, u256("666399946655997866"));
+        assert_eq!(step.fee_amount, u256("600000000000000"));
+        assert_eq!(step.amount_in + step.fee_amount, amount.into());
+    }
+
+    #[test]
+    fn can_swap_exact_out_fully_received() {
+        let target = u256("250541448375047931186413801569");
+        let liquidity = 2 * 10u128.pow(18);
+        let step = swap_within_tick(liquidity, PRICE, target, i256(-10i128.pow(18)), 600).unwrap();
+        assert_eq!(step.sqrt_price_next, u256("158456325028528675187087900672"));
+        assert_eq!(step.amount_in, u256("2000000000000000000"));
+        assert_eq!(step.amount_out, u256("1000000000000000000"));
+        assert_eq!(step.fee_amount, u256("1200720432259356"));
+    }
+
+    #[test]
+    fn swap_within_tick_validates_fee() {
+        let res = swap_within_tick(1, PRICE, PRICE + 1, i256(1), ONE_IN_PIPS);
+        assert!(matches!(res.unwrap_err(), Error::InvalidFee));
+    }
+}
@@ -0,0 +1,170 @@
+//! Computes sqrt prices from ticks and vice versa, ported from Uniswap's [`TickMath`] library.
+//!
+//! A tick is the base 1.0001 logarithm of a price, and sqrt prices are Q64.96 numbers.
+//!
+//! [`TickMath`]: https://github.com/Uniswap/v3-core/blob/main/contracts/libraries/TickMath.sol
+
+use crate::errors::{Error, Result};
+use ethers_core::types::{I256, U256};
+
+/// The minimum tick that may be passed to [`get_sqrt_ratio_at_tick`], computed from
+/// `log base 1.0001 of 2**-128`.
+pub const MIN_TICK: i32 = -887272;
+
+/// The maximum tick that may be passed to [`get_sqrt_ratio_at_tick`], computed from
+/// `log base 1.0001 of 2**128`.
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+/// The minimum value that can be returned from [`get_sqrt_ratio_at_tick`]. Equivalent to
+/// `get_sqrt_ratio_at_tick(MIN_TICK)`.
+pub const MIN_SQRT_RATIO: U256 = U256([4295128739, 0, 0, 0]);
+
+/// The maximum value that can be returned from [`get_sqrt_ratio_at_tick`]. Equivalent to
+/// `get_sqrt_ratio_at_tick(MAX_TICK)`.
+pub const MAX_SQRT_RATIO: U256 = U256([0x5d951d5263988d26, 0xefd1fc6a50648849, 0xfffd8963, 0]);
+
+/// `1 / sqrt(1.0001) ^ (2 ^ i)` for each bit `i` of the absolute tick after the first, as
+/// Q128.128 numbers.
+const RATIOS: [u128; 19] = [
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// Calculates `sqrt(1.0001 ^ tick) * 2 ^ 96`.
+///
+/// Returns [`Error::InvalidTick`] if `tick` is not in `MIN_TICK..=MAX_TICK`.
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U256> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK as u32 {
+        return Err(Error::InvalidTick);
+    }
+
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U256::from(0xfffcb933bd6fad37aa2d162d1a594001u128)
+    } else {
+        U256::one() << 128
+    };
+    for (i, &factor) in RATIOS.iter().enumerate() {
+        if abs_tick & (0x2 << i) != 0 {
+            ratio = (ratio * U256::from(factor)) >> 128;
+        }
+    }
+
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // divide by 2^32 rounding up to go from a Q128.128 to a Q128.96
+    let round_up = !(ratio & U256::from(u32::MAX)).is_zero();
+    Ok((ratio >> 32) + round_up as u8)
+}
+
+/// Calculates the greatest tick value such that `get_sqrt_ratio_at_tick(tick) <= sqrt_ratio`.
+///
+/// Returns [`Error::InvalidSqrtRatio`] if `sqrt_ratio` is not in
+/// `MIN_SQRT_RATIO..MAX_SQRT_RATIO`.
+pub fn get_tick_at_sqrt_ratio(sqrt_ratio: U256) -> Result<i32> {
+    if sqrt_ratio < MIN_SQRT_RATIO || sqrt_ratio >= MAX_SQRT_RATIO {
+        return Err(Error::InvalidSqrtRatio);
+    }
+
+    let ratio = sqrt_ratio << 32;
+    let msb = ratio.bits() - 1;
+    let mut r = if msb >= 128 { ratio >> (msb - 127) } else { ratio << (127 - msb) };
+
+    // integer part of log_2(ratio) as a Q64.64 number, followed by 14 bits of fractional part
+    let mut log_2 = I256::from((msb as i128 - 128) << 64);
+    for bit in (50..=63).rev() {
+        r = (r * r) >> 127;
+        let f = (r >> 128).low_u32();
+        log_2 += I256::from((f as u64) << bit);
+        r >>= f;
+    }
+
+    // log_sqrt(1.0001)(ratio) as a Q128.128 number
+    let log_sqrt10001 = log_2 * I256::from(255738958999603826347141u128);
+
+    let tick_low =
+        (log_sqrt10001 - I256::from(3402992956809132418596140100660247210u128)).asr(128).as_i32();
+    let tick_high =
+        (log_sqrt10001 + I256::from(291339464771989622907027621153398088495u128)).asr(128).as_i32();
+
+    if tick_low == tick_high || get_sqrt_ratio_at_tick(tick_high)? > sqrt_ratio {
+        Ok(tick_low)
+    } else {
+        Ok(tick_high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_get_sqrt_ratio_at_tick() {
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK).unwrap(), MIN_SQRT_RATIO);
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK + 1).unwrap(), 4295343490u64.into());
+        assert_eq!(get_sqrt_ratio_at_tick(0).unwrap(), U256::one() << 96);
+        assert_eq!(
+            get_sqrt_ratio_at_tick(1).unwrap(),
+            U256::from_dec_str("79232123823359799118286999568").unwrap()
+        );
+        assert_eq!(
+            get_sqrt_ratio_at_tick(MAX_TICK - 1).unwrap(),
+            U256::from_dec_str("1461373636630004318706518188784493106690254656249").unwrap()
+        );
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK).unwrap(), MAX_SQRT_RATIO);
+
+        assert!(matches!(get_sqrt_ratio_at_tick(MIN_TICK - 1).unwrap_err(), Error::InvalidTick));
+        assert!(matches!(get_sqrt_ratio_at_tick(MAX_TICK + 1).unwrap_err(), Error::InvalidTick));
+    }
+
+    #[test]
+    fn can_get_tick_at_sqrt_ratio() {
+        assert_eq!(get_tick_at_sqrt_ratio(MIN_SQRT_RATIO).unwrap(), MIN_TICK);
+        assert_eq!(get_tick_at_sqrt_ratio(4295343490u64.into()).unwrap(), MIN_TICK + 1);
+        assert_eq!(get_tick_at_sqrt_ratio(U256::one() << 96).unwrap(), 0);
+        assert_eq!(
+            get_tick_at_sqrt_ratio(
+                U256::from_dec_str("1461373636630004318706518188784493106690254656249").unwrap()
+            )
+            .unwrap(),
+            MAX_TICK - 1
+        );
+        assert_eq!(get_tick_at_sqrt_ratio(MAX_SQRT_RATIO - 1).unwrap(), MAX_TICK - 1);
+
+        let err = get_tick_at_sqrt_ratio(MIN_SQRT_RATIO - 1).unwrap_err();
+        assert!(matches!(err, Error::InvalidSqrtRatio));
+        let err = get_tick_at_sqrt_ratio(MAX_SQRT_RATIO).unwrap_err();
+        assert!(matches!(err, Error::InvalidSqrtRatio));
+    }
+
+    #[test]
+    fn tick_math_round_trips() {
+        for tick in [MIN_TICK, -200000, -5000, -1, 0, 1, 100, 60000, MAX_TICK - 1] {
+            let sqrt_ratio = get_sqrt_ratio_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_ratio(sqrt_ratio).unwrap(), tick);
+            // rounds down to the greatest tick below
+            if tick > MIN_TICK {
+                assert_eq!(get_tick_at_sqrt_ratio(sqrt_ratio - 1).unwrap(), tick - 1);
+            }
+        }
+    }
+}
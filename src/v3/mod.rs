@@ -3,9 +3,11 @@
 mod factory;
 mod library;
 mod pool;
+mod quoter;
 mod router;
 
 pub use factory::Factory;
-pub use library::FeeAmount;
+pub use library::{swap_within_tick, tick_math, FeeAmount, SwapStep};
 pub use pool::Pool;
+pub use quoter::Quoter;
 pub use router::Router;
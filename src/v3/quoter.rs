@@ -0,0 +1,100 @@
+use super::FeeAmount;
+use crate::{
+    contracts::bindings::i_quoter_v2::{IQuoterV2, QuoteExactInputSingleParams},
+    errors::Result,
+};
+use ethers_contract::{Multicall, MulticallVersion};
+use ethers_core::{
+    abi::{Token, Tokenizable},
+    types::{Address, U256},
+};
+use ethers_providers::Middleware;
+use std::sync::Arc;
+
+#[cfg(feature = "addresses")]
+use crate::contracts::addresses::try_address;
+#[cfg(feature = "addresses")]
+use ethers_core::types::Chain;
+
+contract_struct! {
+    /// A Uniswap V3 quoter.
+    pub struct Quoter<M> {
+        /// The quoter contract.
+        contract: IQuoterV2<M>,
+    }
+}
+
+impl<M: Middleware> Quoter<M> {
+    /// Creates a new instance using the provided address.
+    pub fn new(client: Arc<M>, address: Address) -> Self {
+        let contract = IQuoterV2::new(address, client);
+        Self { contract }
+    }
+
+    /// Creates a new instance by searching for the required addresses in the [addressbook].
+    ///
+    /// [addressbook]: crate::contracts::addresses
+    #[cfg(feature = "addresses")]
+    pub fn new_with_chain(client: Arc<M>, chain: Chain) -> Option<Self> {
+        try_address("QuoterV2", chain).map(|address| Self::new(client, address))
+    }
+
+    /// Quotes an exact input single swap in the pool of every [FeeAmount]. Makes only 1 call to
+    /// the client by using [Multicall].
+    ///
+    /// The quote is `None` if the pool has not been deployed or does not have enough liquidity.
+    #[doc(alias = "dry_run_only")]
+    pub async fn quote_all_tiers(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+    ) -> Result<Vec<(FeeAmount, Option<U256>)>> {
+        // allowing failure requires Multicall3
+        let mut multicall =
+            Multicall::new(self.client(), None).await?.version(MulticallVersion::Multicall3);
+        for fee in FeeAmount::ALL {
+            let params = QuoteExactInputSingleParams {
+                token_in,
+                token_out,
+                amount_in,
+                fee: fee as u32,
+                sqrt_price_limit_x96: U256::zero(),
+            };
+            multicall.add_call(self.contract.quote_exact_input_single(params), true);
+        }
+
+        let tokens = multicall.call_raw().await?;
+        let quotes = FeeAmount::ALL.into_iter().zip(tokens).map(|(fee, token)| {
+            let amount_out = match <(bool, Token)>::from_token(token) {
+                Ok((true, Token::Tuple(values))) => {
+                    values.into_iter().next().and_then(|value| U256::from_token(value).ok())
+                }
+                _ => None,
+            };
+            (fee, amount_out)
+        });
+        Ok(quotes.collect())
+    }
+}
+
+#[cfg(all(test, feature = "addresses"))]
+mod tests {
+    use super::*;
+    use crate::contracts::addresses::address;
+    use ethers_providers::MAINNET;
+
+    #[tokio::test]
+    #[ignore = "async test"]
+    async fn can_quote_all_tiers() {
+        let client = Arc::new(MAINNET.provider());
+        let quoter = Quoter::new_with_chain(client, Chain::Mainnet).unwrap();
+        let weth = address("WETH", Chain::Mainnet);
+        let usdc = address("USDC", Chain::Mainnet);
+
+        let amount_in = U256::exp10(18);
+        let quotes = quoter.quote_all_tiers(weth, usdc, amount_in).await.unwrap();
+        assert_eq!(quotes.len(), FeeAmount::ALL.len());
+        assert!(quotes.iter().any(|(_, amount_out)| amount_out.is_some()));
+    }
+}